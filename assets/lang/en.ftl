@@ -0,0 +1,140 @@
+# English messages, also used for any message missing from another language.
+
+## Main menu
+
+menu-continue = Continue
+menu-new-game = New Game
+menu-levels = Levels
+menu-settings = Settings
+menu-exit = Exit
+
+## Loading screen
+
+loading = Loading...
+
+## Level select
+
+level-select-title = Select Level
+level-select-locked = { $name } (locked)
+level-select-completed = { $name } *
+level-select-best-time = Best time: { $time }
+level-select-best-rank = Best rank: { $rank }
+level-select-deaths = Total deaths: { $count }
+level-select-help = Backspace to go back
+
+## In game
+
+hud-coins = Coins: { $count }
+hud-secrets = Secrets: { $found }/{ $total }
+hud-score = Score: { $points }
+hud-deaths = Deaths: { $count }
+
+## Run statistics
+
+stats-run-deaths = Deaths this run: { $count }
+stats-level-deaths = Deaths in this level: { $count }
+stats-damage = Damage taken: { $amount }
+stats-epoch-changes = Epoch changes: { $count }
+stats-section = Section { $index }: { $time }
+
+## Interaction prompt
+
+interact-prompt = [{ $key }] { $action }
+interact-talk = Talk
+interact-use = Use
+
+## Tutorial hints
+
+hint-dismiss = [{ $key }] OK
+
+## Pause menu
+
+pause-title = Paused
+pause-resume = Resume
+pause-restart = Restart
+pause-settings = Settings
+pause-quit = Quit to Menu
+pause-exit = Quit Game
+
+## Quit confirmation
+
+confirm-quit-title = Quit?
+confirm-quit-message = Unsaved progress will be lost.
+confirm-yes = Yes
+confirm-no = No
+
+## Game over
+
+game-over-title = Game Over
+game-over-score =
+    Score: { $points }
+    High score: { $high_score }
+game-over-retry = Retry from Checkpoint
+game-over-restart = Restart Level
+game-over-main-menu = Main Menu
+
+## Level results
+
+victory-title = Level Complete!
+victory-time = Time: { $time }
+victory-time-par = Time: { $time }  Par: { $par }
+victory-new-best = New best time! (was { $best })
+victory-best = Best: { $best }
+victory-first-clear = First clear!
+victory-coins = Coins: { $count }/{ $total }
+victory-rank = Rank
+victory-best-rank = Best: { $rank }
+victory-next-level = Next Level
+victory-retry = Retry
+victory-main-menu = Main Menu
+
+## Settings
+
+settings-title = Settings
+settings-tab-general = General
+settings-tab-audio = Audio
+settings-tab-video = Video
+settings-tab-controls = Controls
+settings-language = Language
+settings-master-volume = Master Volume
+settings-music-volume = Music Volume
+settings-effects-volume = Effects Volume
+settings-mute = Mute All Audio
+settings-display-mode = Window Mode
+settings-resolution = Resolution
+settings-letterbox = Keep Aspect Ratio
+settings-vsync = VSync
+settings-pixel-snap = Pixel Snapping
+settings-health-style = Health Display
+settings-show-timer = Level Timer
+settings-show-ghost = Best Run Ghost
+settings-reset-bindings = Reset to Defaults
+settings-back = Back
+settings-on = On
+settings-off = Off
+settings-display-windowed = Windowed
+settings-display-borderless = Borderless
+settings-display-fullscreen = Fullscreen
+settings-health-bar = Bar
+settings-health-segments = Segments
+settings-swapped = Swapped with { $action }
+settings-bindings-reset = Controls reset to defaults
+settings-rebinding = Press a key or button for { $action } (Esc to cancel)
+settings-help = Q/E: Tab  A/D: Change  Enter: Select  Esc: Back
+
+## Player actions
+
+action-left = Move Left
+action-right = Move Right
+action-up = Move Up
+action-down = Move Down
+action-jump = Jump
+action-fire = Fire
+action-interact = Interact
+
+## Performance overlay
+
+diagnostics-fps = FPS: { $fps }
+diagnostics-frame = Frame: { $time } ms
+diagnostics-entities = Entities: { $count }
+diagnostics-bodies = Bodies: { $count }
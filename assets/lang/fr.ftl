@@ -0,0 +1,140 @@
+# Messages en français.
+
+## Menu principal
+
+menu-continue = Continuer
+menu-new-game = Nouvelle partie
+menu-levels = Niveaux
+menu-settings = Options
+menu-exit = Quitter
+
+## Écran de chargement
+
+loading = Chargement...
+
+## Choix du niveau
+
+level-select-title = Choix du niveau
+level-select-locked = { $name } (verrouillé)
+level-select-completed = { $name } *
+level-select-best-time = Meilleur temps : { $time }
+level-select-best-rank = Meilleur rang : { $rank }
+level-select-deaths = Morts au total : { $count }
+level-select-help = Retour arrière pour revenir
+
+## En jeu
+
+hud-coins = Pièces : { $count }
+hud-secrets = Secrets : { $found }/{ $total }
+hud-score = Score : { $points }
+hud-deaths = Morts : { $count }
+
+## Statistiques de la partie
+
+stats-run-deaths = Morts de la partie : { $count }
+stats-level-deaths = Morts dans ce niveau : { $count }
+stats-damage = Dégâts subis : { $amount }
+stats-epoch-changes = Changements d'époque : { $count }
+stats-section = Section { $index } : { $time }
+
+## Invite d'interaction
+
+interact-prompt = [{ $key }] { $action }
+interact-talk = Parler
+interact-use = Utiliser
+
+## Astuces
+
+hint-dismiss = [{ $key }] OK
+
+## Menu pause
+
+pause-title = Pause
+pause-resume = Reprendre
+pause-restart = Recommencer
+pause-settings = Options
+pause-quit = Retour au menu
+pause-exit = Quitter le jeu
+
+## Confirmation pour quitter
+
+confirm-quit-title = Quitter ?
+confirm-quit-message = La progression non sauvegardée sera perdue.
+confirm-yes = Oui
+confirm-no = Non
+
+## Partie terminée
+
+game-over-title = Partie terminée
+game-over-score =
+    Score : { $points }
+    Meilleur score : { $high_score }
+game-over-retry = Reprendre au point de contrôle
+game-over-restart = Recommencer le niveau
+game-over-main-menu = Menu principal
+
+## Résultats du niveau
+
+victory-title = Niveau terminé !
+victory-time = Temps : { $time }
+victory-time-par = Temps : { $time }  Objectif : { $par }
+victory-new-best = Nouveau record ! (avant { $best })
+victory-best = Record : { $best }
+victory-first-clear = Premier passage !
+victory-coins = Pièces : { $count }/{ $total }
+victory-rank = Rang
+victory-best-rank = Record : { $rank }
+victory-next-level = Niveau suivant
+victory-retry = Recommencer
+victory-main-menu = Menu principal
+
+## Options
+
+settings-title = Options
+settings-tab-general = Général
+settings-tab-audio = Audio
+settings-tab-video = Vidéo
+settings-tab-controls = Commandes
+settings-language = Langue
+settings-master-volume = Volume général
+settings-music-volume = Volume musique
+settings-effects-volume = Volume effets
+settings-mute = Couper le son
+settings-display-mode = Affichage
+settings-resolution = Résolution
+settings-letterbox = Garder le format 4:3
+settings-vsync = Synchro verticale
+settings-pixel-snap = Alignement des pixels
+settings-health-style = Affichage de la vie
+settings-show-timer = Chronomètre
+settings-show-ghost = Fantôme du record
+settings-reset-bindings = Par défaut
+settings-back = Retour
+settings-on = Oui
+settings-off = Non
+settings-display-windowed = Fenêtre
+settings-display-borderless = Sans bordure
+settings-display-fullscreen = Plein écran
+settings-health-bar = Barre
+settings-health-segments = Segments
+settings-swapped = Échangé avec { $action }
+settings-bindings-reset = Commandes par défaut rétablies
+settings-rebinding = Appuyez sur une touche pour { $action } (Échap pour annuler)
+settings-help = Q/E : Onglet  A/D : Modifier  Entrée : Choisir  Échap : Retour
+
+## Actions du joueur
+
+action-left = Gauche
+action-right = Droite
+action-up = Haut
+action-down = Bas
+action-jump = Sauter
+action-fire = Tirer
+action-interact = Interagir
+
+## Performances
+
+diagnostics-fps = IPS : { $fps }
+diagnostics-frame = Image : { $time } ms
+diagnostics-entities = Entités : { $count }
+diagnostics-bodies = Corps : { $count }
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_kira_audio::{prelude::*, AudioSource};
+
+use crate::{sound::AmbienceChannel, AmbienceRegion, AmbienceSound, Player};
+
+/// Duration over which volume changes are smoothed, so the sound doesn't
+/// jump when the player teleports or respawns.
+const VOLUME_TWEEN_DURATION: Duration = Duration::from_millis(250);
+
+/// Looping sounds of the ambience regions, loaded once at startup.
+#[derive(Default, Resource)]
+struct AmbienceSounds(HashMap<AmbienceSound, Handle<AudioSource>>);
+
+/// Sounds currently playing for each ambience region.
+#[derive(Default, Resource)]
+struct AmbienceInstances(HashMap<Entity, Handle<AudioInstance>>);
+
+#[derive(Default)]
+pub struct AmbiencePlugin;
+
+impl Plugin for AmbiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AmbienceSounds>()
+            .init_resource::<AmbienceInstances>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(Update, update_ambience);
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<AmbienceSounds>) {
+    // Placeholder for all ambiences until dedicated samples are made
+    let placeholder: Handle<AudioSource> = asset_server.load("select1.ogg");
+    sounds.0 = [
+        AmbienceSound::Drips,
+        AmbienceSound::Wind,
+        AmbienceSound::Machinery,
+    ]
+    .into_iter()
+    .map(|sound| (sound, placeholder.clone()))
+    .collect();
+}
+
+/// Playback rate of each ambience, telling them apart while they share the
+/// placeholder sample.
+fn ambience_rate(sound: AmbienceSound) -> f64 {
+    match sound {
+        AmbienceSound::Drips => 1.5,
+        AmbienceSound::Wind => 0.25,
+        AmbienceSound::Machinery => 0.5,
+    }
+}
+
+/// Start the looping sound of new regions and stop the one of despawned
+/// regions, then fade each sound according to the distance of the player to
+/// its region.
+fn update_ambience(
+    q_player: Query<&Transform, With<Player>>,
+    q_regions: Query<(Entity, &AmbienceRegion, &GlobalTransform)>,
+    ambience: Res<AudioChannel<AmbienceChannel>>,
+    sounds: Res<AmbienceSounds>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut instances: ResMut<AmbienceInstances>,
+) {
+    instances.0.retain(|entity, handle| {
+        if q_regions.contains(*entity) {
+            return true;
+        }
+        if let Some(instance) = audio_instances.get_mut(handle) {
+            instance.stop(AudioTween::linear(VOLUME_TWEEN_DURATION));
+        }
+        false
+    });
+
+    let player_pos = q_player
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.xy());
+
+    for (entity, region, transform) in &q_regions {
+        // Silence all regions while there's no player, like during level
+        // transitions.
+        let volume = player_pos.map_or(0., |player_pos| {
+            let outside = ((player_pos - transform.translation().xy()).abs() - region.half_size)
+                .max(Vec2::ZERO)
+                .length();
+            let fade = 1. - outside / region.fade_distance.max(1.);
+            (region.volume * fade.clamp(0., 1.)) as f64
+        });
+
+        let Some(handle) = instances.0.get(&entity) else {
+            let Some(sound) = sounds.0.get(&region.sound) else {
+                continue;
+            };
+            let handle = ambience
+                .play(sound.clone())
+                .looped()
+                .with_volume(volume)
+                .with_playback_rate(ambience_rate(region.sound))
+                .handle();
+            instances.0.insert(entity, handle);
+            continue;
+        };
+        if let Some(instance) = audio_instances.get_mut(handle) {
+            instance.set_volume(volume, AudioTween::linear(VOLUME_TWEEN_DURATION));
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    checkpoint::RespawnEvent,
+    explosion::ExplosionEvent,
+    projectile::spawn_projectile,
+    trigger::{TriggerEnter, TriggerKind},
+    AppState, ArenaWall, Boss, BossAction, BossLocked, BossStep, Dying, Enemy, EnemyLife, Epoch,
+    Player, Projectile, ProjectileOwner,
+};
+
+/// Speed of the boss projectiles, in pixels per second.
+const BOSS_PROJECTILE_SPEED: f32 = 140.;
+
+/// Angle covered by a fan of projectiles, in radians.
+const SPREAD_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+
+#[derive(Default)]
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                reset_boss_fight,
+                start_boss_fight,
+                run_boss_patterns,
+                end_boss_fight,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Parse a boss attack pattern.
+///
+/// The pattern is a comma-separated list of steps, each made of an action and
+/// its duration in seconds, like `"fire 0.5, wait 1, spread:5 0.8, charge:120
+/// 1.5, explode:64 1"`.
+pub fn parse_pattern(pattern: &str) -> Option<Vec<BossStep>> {
+    pattern
+        .split(',')
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .map(|step| {
+            let (action, duration) = step.split_once(char::is_whitespace)?;
+            // Negative and non-finite durations parse but are invalid
+            let duration = Duration::try_from_secs_f32(duration.trim().parse().ok()?).ok()?;
+            let (name, arg) = match action.split_once(':') {
+                Some((name, arg)) => (name, Some(arg)),
+                None => (action, None),
+            };
+            let action = match name {
+                "wait" => BossAction::Wait,
+                "fire" => BossAction::Fire,
+                "spread" => BossAction::Spread(arg.map_or(Some(3), |a| a.parse().ok())?),
+                "charge" => BossAction::Charge(arg.map_or(Some(100.), |a| a.parse().ok())?),
+                "explode" => BossAction::Explode(arg.map_or(Some(48.), |a| a.parse().ok())?),
+                _ => return None,
+            };
+            Some(BossStep { action, duration })
+        })
+        .collect()
+}
+
+/// Default attack pattern for bosses without any configured phase.
+pub fn default_phases() -> Vec<Vec<BossStep>> {
+    vec![
+        parse_pattern("wait 1, fire 0.5, fire 0.5, fire 1").unwrap(),
+        parse_pattern("spread:3 1, charge:120 1.5, wait 0.5").unwrap(),
+        parse_pattern("spread:5 0.6, fire 0.3, charge:160 1").unwrap(),
+    ]
+}
+
+/// Reset a running boss fight when the player respawns, so it starts again once
+/// the player enters the arena, even from a checkpoint outside of it.
+fn reset_boss_fight(
+    mut commands: Commands,
+    mut q_bosses: Query<(&mut Boss, &mut EnemyLife, &mut Transform, &mut Velocity), Without<Dying>>,
+    q_walls: Query<Entity, With<ArenaWall>>,
+    mut q_epoch: Query<&mut Epoch>,
+    mut events: EventReader<RespawnEvent>,
+) {
+    if events.read().last().is_none() {
+        return;
+    }
+    if !q_bosses.iter().any(|(boss, ..)| boss.is_active) {
+        return;
+    }
+
+    info!("Boss fight reset");
+    for (mut boss, mut life, mut transform, mut velocity) in &mut q_bosses {
+        boss.reset();
+        life.life = life.max_life;
+        transform.translation = boss.start;
+        *velocity = Velocity::zero();
+    }
+    for entity in &q_walls {
+        commands.entity(entity).insert(ColliderDisabled);
+    }
+    if let Ok(mut epoch) = q_epoch.get_single_mut() {
+        epoch.locked = false;
+    }
+}
+
+/// Activate the bosses and lock the arena when the player enters it.
+fn start_boss_fight(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_bosses: Query<&mut Boss>,
+    q_walls: Query<Entity, With<ArenaWall>>,
+    mut q_epoch: Query<&mut Epoch>,
+    mut ev_enter: EventReader<TriggerEnter>,
+) {
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::BossArena {
+            continue;
+        }
+
+        let mut has_started = false;
+        for mut boss in q_bosses.iter_mut().filter(|boss| !boss.is_active) {
+            boss.is_active = true;
+            boss.step_start_time = time.elapsed();
+            has_started = true;
+        }
+        if !has_started {
+            continue;
+        }
+
+        info!("Boss fight started!");
+        for entity in &q_walls {
+            commands.entity(entity).remove::<ColliderDisabled>();
+        }
+        if let Ok(mut epoch) = q_epoch.get_single_mut() {
+            epoch.locked = true;
+        }
+    }
+}
+
+/// Play the attack pattern of each active boss.
+fn run_boss_patterns(
+    mut commands: Commands,
+    time: Res<Time>,
+    q_player: Query<&Transform, With<Player>>,
+    mut q_bosses: Query<
+        (
+            Entity,
+            &mut Boss,
+            &Enemy,
+            &EnemyLife,
+            &Transform,
+            &mut Velocity,
+        ),
+        (Without<Player>, Without<Dying>),
+    >,
+    mut ev_explosion: EventWriter<ExplosionEvent>,
+) {
+    let Ok(player_transform) = q_player.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.xy();
+
+    for (boss_entity, mut boss, enemy, enemy_life, transform, mut velocity) in &mut q_bosses {
+        if !boss.is_active || boss.phases.is_empty() {
+            continue;
+        }
+
+        // Switch phase based on remaining life
+        let phase = boss.phase_for_life(enemy_life.life / enemy_life.max_life);
+        let mut is_new_step = false;
+        if phase != boss.phase {
+            debug!("Boss entering phase #{}", phase);
+            boss.phase = phase;
+            boss.step = 0;
+            boss.step_start_time = time.elapsed();
+            is_new_step = true;
+        }
+
+        // Advance to the next step once the current one is over
+        let Some(step) = boss.current_step().copied() else {
+            continue;
+        };
+        if time.elapsed() >= boss.step_start_time + step.duration {
+            boss.step = (boss.step + 1) % boss.phases[boss.phase].len();
+            boss.step_start_time = time.elapsed();
+            is_new_step = true;
+        }
+        let Some(step) = boss.current_step().copied() else {
+            continue;
+        };
+
+        let pos = transform.translation.xy();
+        let to_player = (player_pos - pos).normalize_or_zero();
+        if is_new_step {
+            let angles: Vec<f32> = match step.action {
+                BossAction::Fire => vec![0.],
+                BossAction::Spread(count) if count > 1 => (0..count)
+                    .map(|i| SPREAD_ANGLE * (i as f32 / (count - 1) as f32 - 0.5))
+                    .collect(),
+                BossAction::Spread(_) => vec![0.],
+                BossAction::Wait | BossAction::Charge(_) | BossAction::Explode(_) => vec![],
+            };
+            if let BossAction::Explode(radius) = step.action {
+                ev_explosion.send(ExplosionEvent {
+                    position: pos,
+                    radius,
+                    damage: enemy.damage,
+                    source: Some(boss_entity),
+                });
+            }
+            for angle in angles {
+                let dir = Vec2::from_angle(angle).rotate(to_player);
+                let muzzle = pos + dir * (enemy.size.max_element() / 2. + 4.);
+                spawn_projectile(
+                    &mut commands,
+                    muzzle.extend(transform.translation.z),
+                    dir * BOSS_PROJECTILE_SPEED,
+                    Projectile {
+                        owner: ProjectileOwner::Enemy,
+                        damage: enemy.damage,
+                        spawn_time: time.elapsed(),
+                        lifetime: Duration::from_secs(3),
+                    },
+                );
+            }
+            boss.charge_dir = to_player.x.signum();
+        }
+
+        let linvel_x = match step.action {
+            BossAction::Charge(speed) => boss.charge_dir * speed,
+            _ => 0.,
+        };
+        if velocity.linvel.x != linvel_x {
+            velocity.linvel.x = linvel_x;
+        }
+    }
+}
+
+/// Unlock the arena and the level exit once all bosses are defeated.
+fn end_boss_fight(
+    mut commands: Commands,
+    q_dead_bosses: Query<(), (With<Boss>, Added<Dying>)>,
+    q_alive_bosses: Query<(), (With<Boss>, Without<Dying>)>,
+    q_walls: Query<Entity, With<ArenaWall>>,
+    q_locked: Query<Entity, With<BossLocked>>,
+    mut q_epoch: Query<&mut Epoch>,
+) {
+    if q_dead_bosses.is_empty() || !q_alive_bosses.is_empty() {
+        return;
+    }
+
+    info!("Boss defeated!");
+    for entity in &q_walls {
+        commands.entity(entity).insert(ColliderDisabled);
+    }
+    for entity in &q_locked {
+        commands
+            .entity(entity)
+            .remove::<ColliderDisabled>()
+            .remove::<BossLocked>();
+    }
+    if let Ok(mut epoch) = q_epoch.get_single_mut() {
+        epoch.locked = false;
+    }
+}
@@ -0,0 +1,488 @@
+use bevy::{
+    prelude::*,
+    render::camera::{CameraUpdateSystem, ScalingMode, Viewport},
+    utils::HashSet,
+    window::{PrimaryWindow, WindowResized},
+};
+use bevy_keith::Canvas;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    explosion::CameraShake,
+    layout::{ui_scale, UI_SIZE},
+    rng::GameRng,
+    timer::LevelTimer,
+    AppState, CameraPath, CameraZone, LevelEnd, LevelMeta, MainCamera, MapBounds, Player,
+    PlayerController, PlayerStart,
+};
+
+/// Size on screen of a texel of the game view, when the UI is not scaled.
+pub const PIXELS_PER_TEXEL: f32 = 3.;
+
+/// Camera follow options.
+#[derive(Debug, Resource)]
+pub struct CameraSettings {
+    /// Rate at which the camera catches up with the player horizontally, per
+    /// second. Higher values follow the player more tightly.
+    pub horizontal_damping: f32,
+    /// Rate at which the camera catches up with the player vertically, per
+    /// second.
+    pub vertical_damping: f32,
+    /// Distance beyond which the camera jumps to the player instead of
+    /// catching up smoothly, for example after teleporting.
+    pub snap_distance: f32,
+    /// Maximum distance the camera looks ahead of the player, in the direction
+    /// it's facing.
+    pub lookahead_distance: f32,
+    /// Horizontal speed of the player at which the camera looks ahead by the
+    /// full distance.
+    pub lookahead_speed: f32,
+    /// Rate at which the camera shifts ahead of the player or back onto it, per
+    /// second.
+    pub lookahead_damping: f32,
+    /// Size of the box around the camera center inside which the player can
+    /// move without the camera following.
+    pub deadzone: Vec2,
+    /// Rate at which the camera zooms in or out when entering a camera zone,
+    /// per second.
+    pub zone_damping: f32,
+    /// Snap the camera to the texel grid after smoothing, to prevent the tiles
+    /// from shimmering.
+    pub pixel_snap: bool,
+    /// Restrict the game view and the UI to a 4:3 area in the middle of the
+    /// window, with black bars on the sides.
+    pub letterbox: bool,
+    /// Only move the camera vertically when the player lands on a new ground
+    /// height, instead of following its jumps.
+    pub platform_snap: bool,
+    /// Vertical distance from the last ground height beyond which the camera
+    /// catches up with the player when platform snapping, like when falling
+    /// down a pit.
+    pub catch_up_distance: f32,
+    /// Rate at which the camera catches up with the player vertically when it
+    /// went too far from the last ground height, per second.
+    pub catch_up_damping: f32,
+    /// Speed of the camera when previewing the level, in pixels per second.
+    pub intro_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            horizontal_damping: 8.,
+            vertical_damping: 5.,
+            snap_distance: 200.,
+            lookahead_distance: 40.,
+            lookahead_speed: 80.,
+            lookahead_damping: 2.,
+            deadzone: Vec2::new(32., 48.),
+            zone_damping: 4.,
+            pixel_snap: true,
+            letterbox: false,
+            platform_snap: true,
+            catch_up_distance: 64.,
+            catch_up_damping: 12.,
+            intro_speed: 240.,
+        }
+    }
+}
+
+/// Camera flight along a path previewing the level when entering it, before
+/// the player gets control. Any key skips it.
+#[derive(Debug, Default, Resource)]
+pub struct CameraIntro {
+    /// Path of the flight, empty when not playing.
+    path: Vec<Vec2>,
+    /// Distance travelled along the path.
+    distance: f32,
+    /// Levels whose intro was already played, by level name.
+    played: HashSet<String>,
+}
+
+impl CameraIntro {
+    pub fn is_playing(&self) -> bool {
+        !self.path.is_empty()
+    }
+
+    /// Current camera position along the path, or `None` if the end was reached.
+    fn position(&self) -> Option<Vec2> {
+        let mut distance = self.distance;
+        for segment in self.path.windows(2) {
+            let length = segment[0].distance(segment[1]);
+            if distance <= length {
+                let ratio = if length > 0. { distance / length } else { 1. };
+                return Some(segment[0].lerp(segment[1], ratio));
+            }
+            distance -= length;
+        }
+        None
+    }
+}
+
+/// Run condition preventing player control during the level intro.
+pub fn is_intro_over(intro: Res<CameraIntro>) -> bool {
+    !intro.is_playing()
+}
+
+#[derive(Default)]
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>()
+            .init_resource::<CameraIntro>()
+            .add_systems(Startup, spawn_cameras)
+            .add_systems(
+                Update,
+                (center_on_player_start, start_intro, update_intro)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(PostUpdate, update_camera.run_if(in_state(AppState::InGame)))
+            .add_systems(PostUpdate, fit_to_window.before(CameraUpdateSystem));
+    }
+}
+
+/// Spawn the camera of the game view, and the camera drawing the UI canvas
+/// over it.
+fn spawn_cameras(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            projection: OrthographicProjection {
+                scale: 1.0,
+                near: -1000.0,
+                far: 1000.0,
+                viewport_origin: Vec2::new(0.5, 0.5),
+                scaling_mode: ScalingMode::WindowSize(PIXELS_PER_TEXEL),
+                ..default()
+            },
+            ..default()
+        },
+        MainCamera::default(),
+        Name::new("Camera"),
+    ));
+
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: 100,
+                ..default()
+            },
+            projection: OrthographicProjection {
+                scale: 1.0,
+                near: -1000.0,
+                far: 1000.0,
+                viewport_origin: Vec2::new(0.5, 0.5),
+                scaling_mode: ScalingMode::WindowSize(1.0),
+                ..default()
+            },
+            ..default()
+        },
+        Canvas::default(),
+        Name::new("UICamera"),
+    ));
+}
+
+/// Move the camera onto the start position of the level once loaded, instead of
+/// panning over the whole level to reach the player.
+fn center_on_player_start(
+    q_player_start: Query<&PlayerStart, Added<PlayerStart>>,
+    mut q_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Ok(player_start) = q_player_start.get_single() else {
+        return;
+    };
+    if let Ok(mut camera_transform) = q_camera.get_single_mut() {
+        camera_transform.translation.x = player_start.position.x;
+        camera_transform.translation.y = player_start.position.y;
+    }
+}
+
+/// Scale the game view and the UI canvas with the window, so the layout of the
+/// UI is the same whatever the window size. When letterboxing, also restrict
+/// both cameras to the 4:3 area in the middle of the window. The first camera
+/// clears the whole window, leaving the bars black.
+fn fit_to_window(
+    settings: Res<CameraSettings>,
+    mut ev_resized: EventReader<WindowResized>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_cameras: Query<(&mut Camera, &mut OrthographicProjection, Has<Canvas>)>,
+) {
+    if ev_resized.read().count() == 0 && !settings.is_changed() {
+        return;
+    }
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    if window.size().min_element() <= 0. {
+        return;
+    }
+    let scale = ui_scale(window.size());
+    debug!("Window resized to {:?}, UI scale {}", window.size(), scale);
+    let viewport = settings.letterbox.then(|| {
+        let window_size = window.physical_size().as_vec2();
+        let size = (UI_SIZE * ui_scale(window_size)).round();
+        Viewport {
+            physical_position: ((window_size - size) / 2.).as_uvec2(),
+            physical_size: size.as_uvec2(),
+            ..default()
+        }
+    });
+    for (mut camera, mut projection, is_ui) in &mut q_cameras {
+        let pixels_per_unit = if is_ui {
+            scale
+        } else {
+            PIXELS_PER_TEXEL * scale
+        };
+        projection.scaling_mode = ScalingMode::WindowSize(pixels_per_unit);
+        camera.viewport.clone_from(&viewport);
+    }
+}
+
+/// Start the level intro the first time the player spawns into a level with a
+/// `camera_pan` path, or with the `intro_pan` property.
+fn start_intro(
+    level_meta: Option<Res<LevelMeta>>,
+    mut intro: ResMut<CameraIntro>,
+    mut level_timer: ResMut<LevelTimer>,
+    q_player: Query<&Transform, Added<Player>>,
+    q_paths: Query<&CameraPath>,
+    q_level_end: Query<&GlobalTransform, With<LevelEnd>>,
+) {
+    let Ok(player_transform) = q_player.get_single() else {
+        return;
+    };
+    intro.path.clear();
+    let Some(level_meta) = level_meta else {
+        return;
+    };
+    if intro.played.contains(&level_meta.name) {
+        return;
+    }
+
+    let path = if let Some(path) = q_paths.iter().next() {
+        path.points.clone()
+    } else if level_meta.intro_pan {
+        // Fly back from the level end to the player
+        match q_level_end.iter().next() {
+            Some(level_end) => vec![
+                level_end.translation().xy(),
+                player_transform.translation.xy(),
+            ],
+            None => vec![],
+        }
+    } else {
+        vec![]
+    };
+    if path.len() < 2 {
+        return;
+    }
+
+    debug!("Starting camera intro of '{}'", level_meta.name);
+    intro.played.insert(level_meta.name.clone());
+    intro.path = path;
+    intro.distance = 0.;
+    level_timer.is_running = false;
+}
+
+fn update_intro(
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut intro: ResMut<CameraIntro>,
+    mut level_timer: ResMut<LevelTimer>,
+) {
+    if !intro.is_playing() {
+        return;
+    }
+    intro.distance += settings.intro_speed * time.delta_seconds();
+    let is_skipped = keyboard.get_just_pressed().next().is_some()
+        || gamepad_buttons.get_just_pressed().next().is_some();
+    if is_skipped || intro.position().is_none() {
+        intro.path.clear();
+        level_timer.is_running = true;
+    }
+}
+
+/// Smoothly follow the player, looking ahead in the direction it's moving,
+/// unless a [`CameraZone`] the player is in overrides it, or the level intro
+/// is playing.
+pub fn update_camera(
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    intro: Res<CameraIntro>,
+    shake: Res<CameraShake>,
+    mut rng: ResMut<GameRng>,
+    map_bounds: Option<Res<MapBounds>>,
+    q_player: Query<(&Transform, &Velocity, &PlayerController, Ref<Player>), Without<MainCamera>>,
+    mut q_camera: Query<
+        (
+            &mut Transform,
+            &Camera,
+            &mut OrthographicProjection,
+            &mut MainCamera,
+        ),
+        Without<Player>,
+    >,
+    q_zones: Query<&CameraZone>,
+) {
+    let Ok((player_transform, velocity, controller, player)) = q_player.get_single() else {
+        return;
+    };
+    let Ok((mut transform, render_camera, mut projection, mut camera)) = q_camera.get_single_mut()
+    else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    let player_position = player_transform.translation.xy();
+
+    // Look further ahead the faster the player moves, and ease back onto it
+    // when it stops
+    let dir = if controller.is_facing_left { -1. } else { 1. };
+    let speed_ratio = (velocity.linvel.x.abs() / settings.lookahead_speed).min(1.);
+    let lookahead = dir * settings.lookahead_distance * speed_ratio;
+    if player.is_added() {
+        camera.lookahead = lookahead;
+    } else {
+        camera.lookahead +=
+            (lookahead - camera.lookahead) * (1. - (-settings.lookahead_damping * dt).exp());
+    }
+
+    let mut target = intro
+        .position()
+        .unwrap_or(player_position + Vec2::X * camera.lookahead);
+    let mut deadzone = settings.deadzone;
+    let mut bounds = map_bounds.as_ref().map(|map_bounds| map_bounds.rect);
+    let mut zoom = 1.;
+    let mut vertical_damping = settings.vertical_damping;
+
+    // Only move vertically when the player lands on a new ground height, and
+    // catch up quickly if it goes too far from the last one
+    if settings.platform_snap && !intro.is_playing() {
+        let is_on_ground =
+            controller.is_grounded || controller.is_climbing || controller.is_in_water;
+        if player.is_added() || is_on_ground {
+            camera.ground_y = player_position.y;
+            camera.is_catching_up = false;
+        }
+        if (player_position.y - camera.ground_y).abs() > settings.catch_up_distance {
+            camera.is_catching_up = true;
+        }
+        if camera.is_catching_up {
+            vertical_damping = settings.catch_up_damping;
+        } else {
+            target.y = camera.ground_y;
+            deadzone.y = 0.;
+        }
+    }
+
+    // Apply the innermost camera zone the player is in
+    let zone = q_zones
+        .iter()
+        .filter(|zone| !intro.is_playing() && zone.rect.contains(player_position))
+        .min_by(|a, b| {
+            let area_a = a.rect.size().element_product();
+            let area_b = b.rect.size().element_product();
+            area_a.total_cmp(&area_b)
+        });
+    if let Some(zone) = zone {
+        let center = zone.rect.center();
+        if zone.is_fixed || zone.lock_x {
+            target.x = center.x;
+            deadzone.x = 0.;
+        }
+        if zone.is_fixed || zone.lock_y {
+            target.y = center.y;
+            deadzone.y = 0.;
+        }
+        if let Some(zone_bounds) = zone.bounds {
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.intersect(zone_bounds),
+                None => zone_bounds,
+            });
+        }
+        zoom = zone.zoom;
+    }
+
+    // Ease into the zoom of the zone
+    let scale = if player.is_added() {
+        zoom
+    } else {
+        projection.scale + (zoom - projection.scale) * (1. - (-settings.zone_damping * dt).exp())
+    };
+    if (scale - projection.scale).abs() > 1e-4 {
+        projection.scale = scale;
+    }
+
+    // Don't show anything beyond the bounds. The target is clamped to the bounds
+    // of the current zone, so the camera moves smoothly between zones, while the
+    // final position is only clamped to the map bounds.
+    let view_size = projection.area.size();
+    if let Some(bounds) = bounds {
+        target = clamp_to_bounds(target, bounds, view_size);
+    }
+
+    let delta = target - camera.position;
+    if player.is_added()
+        || intro.is_playing()
+        || delta.length_squared() > settings.snap_distance.powi(2)
+    {
+        camera.position = target;
+    } else {
+        // Only follow the part of the movement beyond the deadzone
+        let half_deadzone = deadzone / 2.;
+        let delta = delta - delta.clamp(-half_deadzone, half_deadzone);
+
+        // Exponential smoothing, independent of the frame rate
+        let ratio = Vec2::new(
+            1. - (-settings.horizontal_damping * dt).exp(),
+            1. - (-vertical_damping * dt).exp(),
+        );
+        camera.position += delta * ratio;
+    }
+
+    let mut position = camera.position + shake.offset(time.elapsed(), &mut rng);
+    if let Some(map_bounds) = &map_bounds {
+        position = clamp_to_bounds(position, map_bounds.rect, view_size);
+    }
+    if settings.pixel_snap {
+        if let Some(viewport_size) = render_camera.physical_viewport_size() {
+            position = snap_to_texels(position, viewport_size.as_vec2(), view_size);
+        }
+    }
+    transform.translation = position.extend(player_transform.translation.z);
+}
+
+/// Snap a camera position to the texel grid of the pixel art, so texels always
+/// cover the same number of screen pixels and don't shimmer. The grid is offset
+/// by half a screen pixel along the axes where the viewport has an odd size, for
+/// texel edges to fall on screen pixel edges.
+fn snap_to_texels(position: Vec2, viewport_size: Vec2, view_size: Vec2) -> Vec2 {
+    if view_size.cmple(Vec2::ZERO).any() {
+        return position.round();
+    }
+    let pixels_per_texel = viewport_size / view_size;
+    let offset = (viewport_size / 2.).fract() / pixels_per_texel;
+    (position - offset).round() + offset
+}
+
+/// Clamp a camera position such that a view of the given size stays inside the
+/// bounds, or is centered on them along the axes where it's larger.
+fn clamp_to_bounds(position: Vec2, bounds: Rect, view_size: Vec2) -> Vec2 {
+    let min = bounds.min + view_size / 2.;
+    let max = bounds.max - view_size / 2.;
+    let center = bounds.center();
+    Vec2::new(
+        if min.x <= max.x {
+            position.x.clamp(min.x, max.x)
+        } else {
+            center.x
+        },
+        if min.y <= max.y {
+            position.y.clamp(min.y, max.y)
+        } else {
+            center.y
+        },
+    )
+}
@@ -0,0 +1,255 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    fade::{FadeAction, FadeEvent, ScreenFade},
+    level::LevelRegistry,
+    sound::{AudioAssets, SfxChannel},
+    stats::RunStats,
+    timer::LevelTimer,
+    trigger::{TriggerEnter, TriggerKind},
+    AppState, CheckpointFlag, Epoch, LevelMeta, Player, PlayerBreath, PlayerLife, PlayerStart,
+};
+
+/// Duration of the invulnerability after respawning.
+const RESPAWN_INVULNERABILITY: Duration = Duration::from_secs(2);
+
+/// Remaining lives of the player. Each death consumes one, and the game is over
+/// when none is left.
+#[derive(Debug, Resource)]
+pub struct Lives {
+    pub count: u32,
+    /// Number of lives at the start of the game.
+    pub initial: u32,
+}
+
+impl Lives {
+    /// Restore the initial number of lives.
+    pub fn reset(&mut self) {
+        self.count = self.initial;
+    }
+}
+
+/// Request to respawn the player at the last checkpoint reached, or at the
+/// level start if none.
+#[derive(Debug, Default, Clone, Copy, Event)]
+pub struct RespawnEvent;
+
+/// Last checkpoint reached by the player, where it respawns after dying.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct Checkpoint {
+    /// Name of the level the checkpoint belongs to.
+    pub level: String,
+    /// Respawn position. If `None`, the player respawns at the level start.
+    pub position: Option<Vec3>,
+    /// Epoch at the time the checkpoint was reached.
+    pub epoch: Option<i32>,
+    /// Number of times the player died in the current level.
+    pub level_deaths: u32,
+}
+
+impl Checkpoint {
+    /// Start tracking the given level, forgetting the checkpoint reached in any
+    /// other level.
+    pub fn enter_level(&mut self, level: &str) {
+        if self.level != level {
+            self.level = level.to_string();
+            self.reset_level();
+        }
+    }
+
+    /// Forget the checkpoint reached in the current level, to restart it from
+    /// the beginning.
+    pub fn reset_level(&mut self) {
+        self.position = None;
+        self.epoch = None;
+        self.level_deaths = 0;
+    }
+}
+
+pub struct CheckpointPlugin {
+    /// Number of lives the player starts the game with.
+    pub lives: u32,
+}
+
+impl Default for CheckpointPlugin {
+    fn default() -> Self {
+        Self { lives: 3 }
+    }
+}
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Checkpoint>()
+            .insert_resource(Lives {
+                count: self.lives,
+                initial: self.lives,
+            })
+            .add_event::<RespawnEvent>()
+            .add_systems(
+                Update,
+                // Respawn before checking for death, so that a respawn requested
+                // from outside the game (like retrying after a game over) is
+                // not counted as another death.
+                (
+                    reach_checkpoints,
+                    respawn_player,
+                    kill_player,
+                    blink_invulnerable,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn reach_checkpoints(
+    mut checkpoint: ResMut<Checkpoint>,
+    level_timer: Res<LevelTimer>,
+    mut stats: ResMut<RunStats>,
+    q_player: Query<&Transform, With<Player>>,
+    q_flags: Query<&GlobalTransform, With<CheckpointFlag>>,
+    q_epoch: Query<&Epoch>,
+    mut ev_enter: EventReader<TriggerEnter>,
+) {
+    let Ok(player_transform) = q_player.get_single() else {
+        return;
+    };
+
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Checkpoint {
+            continue;
+        }
+        let Ok(flag_transform) = q_flags.get(ev.trigger) else {
+            continue;
+        };
+
+        let position = flag_transform
+            .translation()
+            .xy()
+            .extend(player_transform.translation.z);
+        if checkpoint.position != Some(position) {
+            info!("Checkpoint reached at {:?}", position);
+            checkpoint.position = Some(position);
+            checkpoint.epoch = q_epoch.get_single().ok().map(|epoch| epoch.cur);
+            stats.split(level_timer.elapsed);
+        }
+    }
+}
+
+/// Consume a life once the player runs out of life, and respawn it, or end the
+/// game if it has no life left.
+fn kill_player(
+    mut checkpoint: ResMut<Checkpoint>,
+    mut stats: ResMut<RunStats>,
+    mut lives: ResMut<Lives>,
+    mut registry: ResMut<LevelRegistry>,
+    q_player: Query<&PlayerLife, With<Player>>,
+    fade: Res<ScreenFade>,
+    mut ev_fade: EventWriter<FadeEvent>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<AudioAssets>,
+) {
+    let Ok(player_life) = q_player.get_single() else {
+        return;
+    };
+    // Wait for the fade out of the previous death to respawn the player
+    if player_life.life > 0. || fade.is_fading() {
+        return;
+    }
+
+    stats.deaths += 1;
+    checkpoint.level_deaths += 1;
+    if let Some(record) = registry.current_record_mut() {
+        record.deaths += 1;
+    }
+    audio.play(sounds.death.clone()).with_playback_rate(0.4);
+    lives.count = lives.count.saturating_sub(1);
+    if lives.count == 0 {
+        info!("Player died with no life left");
+        ev_fade.send(FadeEvent(FadeAction::SetState(AppState::GameOver)));
+        return;
+    }
+    info!(
+        "Player died (#{}) with {} lives left",
+        stats.deaths, lives.count
+    );
+    ev_fade.send(FadeEvent(FadeAction::Respawn));
+}
+
+/// Respawn the player at the last checkpoint.
+fn respawn_player(
+    time: Res<Time>,
+    checkpoint: Res<Checkpoint>,
+    level_meta: Option<Res<LevelMeta>>,
+    mut q_player: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut PlayerLife,
+            &mut PlayerBreath,
+        ),
+        With<Player>,
+    >,
+    q_player_start: Query<&PlayerStart>,
+    mut q_epoch: Query<&mut Epoch>,
+    mut events: EventReader<RespawnEvent>,
+) {
+    if events.read().last().is_none() {
+        return;
+    }
+    let Ok((mut transform, mut velocity, mut player_life, mut breath)) = q_player.get_single_mut()
+    else {
+        return;
+    };
+
+    let Some(position) = checkpoint.position.or_else(|| {
+        q_player_start
+            .get_single()
+            .ok()
+            .map(|start| start.position.xy().extend(transform.translation.z))
+    }) else {
+        warn!("No checkpoint nor player start to respawn at");
+        return;
+    };
+    info!("Respawning player at {:?}", position);
+
+    transform.translation = position;
+    *velocity = Velocity::zero();
+    *player_life = PlayerLife {
+        invulnerable_until: Some(time.elapsed() + RESPAWN_INVULNERABILITY),
+        ..default()
+    };
+    *breath = PlayerBreath::default();
+
+    let keep_epoch = level_meta
+        .as_ref()
+        .is_some_and(|meta| meta.keep_epoch_on_respawn);
+    if !keep_epoch {
+        let start_epoch = checkpoint
+            .epoch
+            .or_else(|| level_meta.as_ref().and_then(|meta| meta.start_epoch));
+        if let (Some(start_epoch), Ok(mut epoch)) = (start_epoch, q_epoch.get_single_mut()) {
+            epoch.cur = start_epoch.clamp(epoch.min, epoch.max);
+        }
+    }
+}
+
+/// Blink the player while invulnerable.
+fn blink_invulnerable(time: Res<Time>, mut q_player: Query<(&PlayerLife, &mut Sprite)>) {
+    let Ok((player_life, mut sprite)) = q_player.get_single_mut() else {
+        return;
+    };
+    let now = time.elapsed();
+    let alpha = if player_life.is_invulnerable(now) && (now.as_millis() / 100) % 2 == 1 {
+        0.3
+    } else {
+        1.
+    };
+    if sprite.color.alpha() != alpha {
+        sprite.color.set_alpha(alpha);
+    }
+}
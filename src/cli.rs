@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    level::{LevelInfo, LevelRegistry, LoadLevelEvent},
+    settings::{DisplayMode, Settings},
+};
+
+const USAGE: &str = "\
+Usage: wheel-of-time [OPTIONS]
+
+Options:
+  --level <PATH>  Start the level with the given asset path, like map1.tmx
+  --epoch <N>     Start levels at the given epoch
+  --seed <N>      Seed of the random values, to reproduce a run
+  --windowed      Force the windowed display mode
+  --skip-menu     Start the first level right away, without the main menu
+  --mute          Start with all audio muted
+  --help          Print this help";
+
+/// Startup options from the command line, to jump straight into a section of
+/// the game when playtesting.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct StartupArgs {
+    /// Asset path of the level to start right away, instead of showing the
+    /// main menu.
+    pub level: Option<String>,
+    /// Epoch levels start at, overriding the one of the level.
+    pub epoch: Option<i32>,
+    /// Seed of the [`GameRng`](crate::rng::GameRng).
+    pub seed: Option<u64>,
+    /// Force the windowed display mode for this session, until changed in the
+    /// settings menu. Never saved to the settings.
+    pub windowed: bool,
+    pub skip_menu: bool,
+    /// Mute all audio for this session, until unmuted in game. Never saved to
+    /// the settings.
+    pub mute: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum StartupArgsError {
+    #[error("Unknown argument '{0}'")]
+    Unknown(String),
+    #[error("Missing value for '{0}'")]
+    MissingValue(String),
+    #[error("Invalid epoch '{0}', expected an integer")]
+    InvalidEpoch(String),
+    #[error("Invalid seed '{0}', expected a positive integer")]
+    InvalidSeed(String),
+}
+
+impl StartupArgs {
+    /// Parse the arguments following the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, StartupArgsError> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| StartupArgsError::MissingValue(arg.clone()))
+            };
+            match arg.as_str() {
+                "--level" => parsed.level = Some(value()?),
+                "--epoch" => {
+                    let epoch = value()?;
+                    parsed.epoch = Some(
+                        epoch
+                            .parse()
+                            .map_err(|_| StartupArgsError::InvalidEpoch(epoch))?,
+                    );
+                }
+                "--seed" => {
+                    let seed = value()?;
+                    parsed.seed = Some(
+                        seed.parse()
+                            .map_err(|_| StartupArgsError::InvalidSeed(seed))?,
+                    );
+                }
+                "--windowed" => parsed.windowed = true,
+                "--skip-menu" => parsed.skip_menu = true,
+                "--mute" => parsed.mute = true,
+                _ => return Err(StartupArgsError::Unknown(arg)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Parse the arguments of the process, exiting with the usage on error or
+    /// when asked for help.
+    fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        match Self::parse(args) {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("{}\n\n{}", err, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Settings in effect for this session, with the overrides of the command
+    /// line applied on top of the saved ones.
+    pub fn apply_to(&self, settings: &Settings) -> Settings {
+        let mut settings = settings.clone();
+        settings.muted |= self.mute;
+        if self.windowed {
+            settings.display_mode = DisplayMode::Windowed;
+        }
+        settings
+    }
+
+    /// Start a level right away instead of showing the main menu.
+    fn starts_level(&self) -> bool {
+        self.skip_menu || self.level.is_some()
+    }
+}
+
+#[derive(Default)]
+pub struct CliPlugin;
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StartupArgs::from_env())
+            .add_systems(Startup, apply_startup_args);
+    }
+}
+
+/// Start the level requested by the startup options right away, if any.
+fn apply_startup_args(
+    args: Res<StartupArgs>,
+    mut registry: ResMut<LevelRegistry>,
+    mut ev_load_level: EventWriter<LoadLevelEvent>,
+) {
+    if !args.starts_level() {
+        return;
+    }
+
+    let index = match &args.level {
+        Some(path) => match registry.levels.iter().position(|level| level.path == *path) {
+            Some(index) => index,
+            // Allow playtesting maps not registered yet
+            None => {
+                let mut level = LevelInfo::new(path, path);
+                level.is_unlocked = true;
+                registry.levels.push(level);
+                registry.levels.len() - 1
+            }
+        },
+        None => 0,
+    };
+    info!("Starting level #{} from the command line", index);
+    ev_load_level.send(LoadLevelEvent(index));
+}
@@ -2,8 +2,48 @@ use std::time::Duration;
 
 use bevy::prelude::*;
 
+use crate::tuning::GameTuning;
+
 #[derive(Default, Component)]
-pub struct MainCamera {}
+pub struct MainCamera {
+    /// Smoothed position followed by the camera, before any shake and pixel
+    /// snapping.
+    pub position: Vec2,
+    /// Current horizontal offset ahead of the player.
+    pub lookahead: f32,
+    /// Height of the ground the player last stood on, followed by the camera
+    /// instead of the player itself when platform snapping.
+    pub ground_y: f32,
+    /// The player went too far from the last ground height, so the camera
+    /// follows it vertically until it lands again.
+    pub is_catching_up: bool,
+}
+
+/// Path the camera flies along to preview the level when entering it.
+#[derive(Debug, Clone, Component)]
+pub struct CameraPath {
+    /// World space points of the path, in order.
+    pub points: Vec<Vec2>,
+}
+
+/// Area of the level overriding the camera behavior while the player is inside,
+/// like a room.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct CameraZone {
+    /// World space area of the zone.
+    pub rect: Rect,
+    /// Keep the camera at the center of the zone.
+    pub is_fixed: bool,
+    /// Keep the camera at the center of the zone horizontally.
+    pub lock_x: bool,
+    /// Keep the camera at the center of the zone vertically.
+    pub lock_y: bool,
+    /// Projection scale while in the zone. Values above 1 show more of the
+    /// level.
+    pub zoom: f32,
+    /// Area the view is kept inside of, if any.
+    pub bounds: Option<Rect>,
+}
 
 #[derive(Default, Component)]
 pub struct PlayerStart {
@@ -13,27 +53,57 @@ pub struct PlayerStart {
 #[derive(Component)]
 pub struct Teleporter {
     pub target: Entity,
+    /// Axis the player crosses the teleporter along; [`Vec2::X`] for a wall
+    /// teleporter, or [`Vec2::Y`] for a floor or ceiling one.
+    pub axis: Vec2,
+    /// Change of epoch when going through the teleporter.
+    pub epoch_delta: i32,
+    /// Direction the player exits the target teleporter toward. If `None`, the
+    /// player keeps going in the direction it crossed this teleporter.
+    pub exit_dir: Option<Vec2>,
+    /// Time during which the teleporter stays inactive after being used, to
+    /// prevent the player from bouncing back and forth between teleporters.
+    pub cooldown: Duration,
+    /// Time until which the teleporter is inactive.
+    pub ready_time: Duration,
 }
 
 impl Default for Teleporter {
     fn default() -> Self {
         Self {
             target: Entity::PLACEHOLDER,
+            axis: Vec2::X,
+            epoch_delta: 0,
+            exit_dir: None,
+            cooldown: Teleporter::DEFAULT_COOLDOWN,
+            ready_time: Duration::ZERO,
         }
     }
 }
 
 impl Teleporter {
+    pub const DEFAULT_COOLDOWN: Duration = Duration::from_millis(400);
+
     pub fn new(target: Entity) -> Self {
-        Self { target }
+        Self {
+            target,
+            ..default()
+        }
+    }
+
+    pub fn is_ready(&self, time: Duration) -> bool {
+        time >= self.ready_time
     }
 }
 
 #[derive(Component, Reflect)]
 pub struct Player {
+    /// Factor converting the movement input into an impulse, from the
+    /// [`GameTuning`].
     pub impulse_factor: f32,
-    /// Side from which the player entered the last teleporter, to determine if
-    /// it exited on the opposite side and therefore if teleportation is needed.
+    /// Side from which the player entered the last teleporter, along the axis of
+    /// that teleporter, to determine if it exited on the opposite side and
+    /// therefore if teleportation is needed.
     pub teleporter_side: f32,
     pub life: f32,
 }
@@ -41,7 +111,7 @@ pub struct Player {
 impl Default for Player {
     fn default() -> Self {
         Self {
-            impulse_factor: 500.,
+            impulse_factor: GameTuning::default().impulse_factor,
             teleporter_side: 0.,
             life: 20.,
         }
@@ -52,6 +122,47 @@ impl Default for Player {
 pub struct PlayerController {
     pub is_grounded: bool,
     pub is_climbing: bool,
+    /// Is the player facing left? Determines the direction of projectiles.
+    pub is_facing_left: bool,
+    pub is_in_water: bool,
+    /// Is gravity pulling the player upward?
+    pub is_gravity_flipped: bool,
+    /// Is the player standing on ice?
+    pub is_on_ice: bool,
+    /// Material of the ground the player last stood on.
+    pub ground_material: GroundMaterial,
+}
+
+impl PlayerController {
+    /// Gravity scale of the player while in water. Lower than 1 so the player
+    /// sinks slowly, as if pushed back up by the water.
+    pub const WATER_GRAVITY_SCALE: f32 = 0.25;
+
+    /// Factor applied to the horizontal movement impulse while on ice, making
+    /// it hard to speed up and turn around.
+    pub const ICE_CONTROL: f32 = 0.25;
+
+    /// Direction of gravity along the Y axis relative to the default one, as
+    /// either 1 or -1. Also gives the direction of jumps.
+    pub fn gravity_sign(&self) -> f32 {
+        if self.is_gravity_flipped {
+            -1.
+        } else {
+            1.
+        }
+    }
+
+    /// Gravity scale to apply to the player based on its current state.
+    pub fn gravity_scale(&self) -> f32 {
+        let scale = if self.is_climbing {
+            0.
+        } else if self.is_in_water {
+            Self::WATER_GRAVITY_SCALE
+        } else {
+            1.
+        };
+        scale * self.gravity_sign()
+    }
 }
 
 #[derive(Component)]
@@ -60,6 +171,11 @@ pub struct PlayerLife {
     pub max_life: f32,
     pub last_dmg_time: Option<Duration>,
     pub last_dmg_dir: Vec2,
+    pub last_heal_time: Option<Duration>,
+    /// Time until which the player can't take any damage, after respawning.
+    pub invulnerable_until: Option<Duration>,
+    /// Duration of the knockback after taking damage, from the [`GameTuning`].
+    pub damage_duration: Duration,
 }
 
 impl Default for PlayerLife {
@@ -69,25 +185,59 @@ impl Default for PlayerLife {
             max_life: 20.,
             last_dmg_time: None,
             last_dmg_dir: Vec2::ZERO,
+            last_heal_time: None,
+            invulnerable_until: None,
+            damage_duration: GameTuning::default().damage_duration(),
         }
     }
 }
 
 impl PlayerLife {
-    pub const DAMAGE_DURATION: Duration = Duration::from_millis(400);
-
     pub fn damage(&mut self, time: Duration, amount: f32, dir: Vec2) {
+        if self.is_invulnerable(time) {
+            return;
+        }
         self.life = (self.life - amount).max(0.);
         self.last_dmg_time = Some(time);
         self.last_dmg_dir = dir;
     }
 
+    pub fn is_invulnerable(&self, time: Duration) -> bool {
+        self.invulnerable_until.is_some_and(|until| time < until)
+    }
+
+    /// Fraction of the maximum life below which the player is warned about
+    /// its low life.
+    pub const LOW_LIFE_RATIO: f32 = 0.25;
+
+    /// Check if the player is still alive but low on life.
+    pub fn is_low(&self) -> bool {
+        self.life > 0. && self.life < self.max_life * Self::LOW_LIFE_RATIO
+    }
+
+    pub const HEAL_PULSE_DURATION: Duration = Duration::from_millis(300);
+
+    pub fn heal(&mut self, time: Duration, amount: f32) {
+        self.life = (self.life + amount).min(self.max_life);
+        self.last_heal_time = Some(time);
+    }
+
+    /// Get the intensity in \[0:1\] of the life bar pulse after healing, if
+    /// still pulsing.
+    pub fn heal_pulse(&self, time: Duration) -> Option<f32> {
+        let delta = time.checked_sub(self.last_heal_time?)?;
+        if delta > Self::HEAL_PULSE_DURATION {
+            return None;
+        }
+        Some(1. - delta.div_duration_f32(Self::HEAL_PULSE_DURATION))
+    }
+
     pub fn damage_impulse_factor(&self, time: Duration) -> Option<f32> {
         if let Some(last_dmg_time) = self.last_dmg_time {
             if time >= last_dmg_time {
                 let delta = time - last_dmg_time;
-                if delta <= Self::DAMAGE_DURATION {
-                    let x = delta.div_duration_f32(Self::DAMAGE_DURATION).clamp(0., 1.);
+                if delta <= self.damage_duration {
+                    let x = delta.div_duration_f32(self.damage_duration).clamp(0., 1.);
                     let x2 = (1. - x) * (1. - x);
                     let ratio = 1. - x2 * x2;
                     Some(ratio.clamp(0., 1.))
@@ -146,6 +296,8 @@ pub struct Epoch {
     pub min: i32,
     pub max: i32,
     pub cur: i32,
+    /// Prevent teleporters from changing the current epoch.
+    pub locked: bool,
 }
 
 #[derive(Default, Component)]
@@ -164,8 +316,758 @@ pub struct EpochSprite {
 #[derive(Component)]
 pub struct Damage(pub f32);
 
+/// Launch the player upward at the given speed, in pixels per second, when
+/// landing on this entity.
+#[derive(Component)]
+pub struct Bounce(pub f32);
+
 #[derive(Default, Component)]
 pub struct Ladder;
 
+/// Object saving the respawn position of the player when touched.
+#[derive(Default, Component)]
+pub struct CheckpointFlag;
+
+/// Region showing a tutorial hint when the player enters it.
+#[derive(Component)]
+pub struct HintZone {
+    /// Text of the hint, or the identifier of a translated message.
+    pub text: String,
+    /// Duration the hint stays visible, or `None` until dismissed.
+    pub duration: Option<Duration>,
+}
+
+/// Region inverting gravity for the player while inside it.
+#[derive(Default, Component)]
+pub struct GravityFlipZone;
+
 #[derive(Default, Component)]
 pub struct LevelEnd;
+
+#[derive(Component)]
+pub struct Enemy {
+    /// Size of the enemy body, in pixels.
+    pub size: Vec2,
+    /// Damage dealt to the player on contact.
+    pub damage: f32,
+}
+
+impl Default for Enemy {
+    fn default() -> Self {
+        Self {
+            size: Vec2::splat(16.),
+            damage: 5.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyLife {
+    pub life: f32,
+    pub max_life: f32,
+    /// Pickup dropped when the enemy dies, if any.
+    pub drop: Option<Pickup>,
+}
+
+impl Default for EnemyLife {
+    fn default() -> Self {
+        Self {
+            life: 3.,
+            max_life: 3.,
+            drop: None,
+        }
+    }
+}
+
+impl EnemyLife {
+    pub fn new(life: f32) -> Self {
+        Self {
+            life,
+            max_life: life,
+            ..default()
+        }
+    }
+}
+
+/// Tint an enemy sprite for a short time after it was hit.
+#[derive(Component)]
+pub struct HitFlash {
+    pub start_time: Duration,
+}
+
+impl HitFlash {
+    pub const DURATION: Duration = Duration::from_millis(120);
+}
+
+/// An enemy playing its death animation, before being despawned.
+#[derive(Component)]
+pub struct Dying {
+    pub start_time: Duration,
+}
+
+impl Dying {
+    pub const DURATION: Duration = Duration::from_millis(400);
+}
+
+/// Collectible item applying some effect to the player on contact.
+#[derive(Debug, Clone, Copy, Component)]
+pub enum Pickup {
+    /// Restore some amount of life.
+    Life(f32),
+    /// Collectible coin, with the Tiled object ID it was spawned from.
+    Coin(u32),
+    /// Extra life.
+    OneUp,
+    /// Timed power-up effect.
+    PowerUp(PowerUp, Duration),
+}
+
+#[derive(Component)]
+pub struct Patrol {
+    /// Walking speed, in pixels per second.
+    pub speed: f32,
+    /// Current walking direction, either -1 (left) or +1 (right).
+    pub dir: f32,
+}
+
+impl Default for Patrol {
+    fn default() -> Self {
+        Self {
+            speed: 30.,
+            dir: 1.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Chaser {
+    /// Maximum flying speed, in pixels per second.
+    pub speed: f32,
+    /// Maximum change of velocity, in pixels per second squared.
+    pub acceleration: f32,
+    /// Distance under which the enemy detects and starts chasing the player.
+    pub aggro_range: f32,
+}
+
+impl Default for Chaser {
+    fn default() -> Self {
+        Self {
+            speed: 50.,
+            acceleration: 100.,
+            aggro_range: 96.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Turret {
+    /// Delay between two consecutive shots.
+    pub fire_interval: Duration,
+    pub last_shot_time: Option<Duration>,
+    /// Distance under which the turret fires at the player.
+    pub range: f32,
+    /// Speed of the fired projectiles, in pixels per second.
+    pub projectile_speed: f32,
+}
+
+impl Default for Turret {
+    fn default() -> Self {
+        Self {
+            fire_interval: Duration::from_millis(1500),
+            last_shot_time: None,
+            range: 160.,
+            projectile_speed: 120.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PlayerWeapon {
+    /// Minimum delay between two consecutive shots.
+    pub cooldown: Duration,
+    pub last_shot_time: Option<Duration>,
+}
+
+impl Default for PlayerWeapon {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_millis(500),
+            last_shot_time: None,
+        }
+    }
+}
+
+impl PlayerWeapon {
+    /// Get the ratio of the cooldown elapsed since the last shot, in \[0:1\].
+    /// The weapon can fire again once the ratio reaches 1.
+    pub fn cooldown_ratio(&self, time: Duration) -> f32 {
+        let Some(last_shot_time) = self.last_shot_time else {
+            return 1.;
+        };
+        let delta = time.saturating_sub(last_shot_time);
+        delta.div_duration_f32(self.cooldown).clamp(0., 1.)
+    }
+
+    pub fn can_fire(&self, time: Duration) -> bool {
+        self.cooldown_ratio(time) >= 1.
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileOwner {
+    Player,
+    Enemy,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    pub owner: ProjectileOwner,
+    /// Damage dealt to the entity hit.
+    pub damage: f32,
+    pub spawn_time: Duration,
+    pub lifetime: Duration,
+}
+
+/// Something destroyed when hit by a projectile.
+#[derive(Default, Component)]
+pub struct Breakable;
+
+/// Breakable which explodes when destroyed, like a barrel.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Explosive {
+    /// Radius of the explosion, in pixels.
+    pub radius: f32,
+    /// Damage dealt at the center of the explosion.
+    pub damage: f32,
+}
+
+/// Single action of a boss attack pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BossAction {
+    /// Do nothing.
+    Wait,
+    /// Fire a single projectile toward the player.
+    Fire,
+    /// Fire a fan of projectiles centered on the player direction.
+    Spread(u32),
+    /// Run toward the player at the given speed, in pixels per second.
+    Charge(f32),
+    /// Explode around the boss, with the given radius in pixels.
+    Explode(f32),
+}
+
+/// Timed step of a boss attack pattern.
+#[derive(Debug, Clone, Copy)]
+pub struct BossStep {
+    pub action: BossAction,
+    pub duration: Duration,
+}
+
+#[derive(Component)]
+pub struct Boss {
+    /// Looping attack pattern of each phase. Phases are evenly distributed over
+    /// the boss life, the first one starting at full life.
+    pub phases: Vec<Vec<BossStep>>,
+    /// Original position, where the boss waits for the player.
+    pub start: Vec3,
+    /// Has the player entered the boss arena yet?
+    pub is_active: bool,
+    pub phase: usize,
+    pub step: usize,
+    pub step_start_time: Duration,
+    /// Horizontal direction of the current charge, if any.
+    pub charge_dir: f32,
+}
+
+impl Boss {
+    pub fn new(phases: Vec<Vec<BossStep>>, start: Vec3) -> Self {
+        Self {
+            phases,
+            start,
+            is_active: false,
+            phase: 0,
+            step: 0,
+            step_start_time: Duration::ZERO,
+            charge_dir: 0.,
+        }
+    }
+
+    /// Go back to waiting for the player, from the first step of the first phase.
+    pub fn reset(&mut self) {
+        self.is_active = false;
+        self.phase = 0;
+        self.step = 0;
+        self.step_start_time = Duration::ZERO;
+        self.charge_dir = 0.;
+    }
+
+    /// Get the phase index corresponding to the given life ratio in \[0:1\].
+    pub fn phase_for_life(&self, ratio: f32) -> usize {
+        let count = self.phases.len();
+        let phase = ((1. - ratio.clamp(0., 1.)) * count as f32) as usize;
+        phase.min(count.saturating_sub(1))
+    }
+
+    pub fn current_step(&self) -> Option<&BossStep> {
+        self.phases.get(self.phase)?.get(self.step)
+    }
+}
+
+/// Area activating the boss fight when the player enters it.
+#[derive(Default, Component)]
+pub struct BossArena;
+
+/// Wall closing the boss arena during the fight.
+#[derive(Default, Component)]
+pub struct ArenaWall;
+
+/// Disabled until all bosses of the level are defeated.
+#[derive(Default, Component)]
+pub struct BossLocked;
+
+/// Interactable object toggling some target entities when activated.
+#[derive(Default, Component)]
+pub struct Switch {
+    pub targets: Vec<Entity>,
+    /// Activate when the player touches the switch, instead of when using the
+    /// interact key while overlapping it.
+    pub on_touch: bool,
+    pub is_on: bool,
+}
+
+/// Entity which can be enabled or disabled by a [`Switch`]. Disabled entities
+/// are hidden and their colliders are disabled.
+#[derive(Component)]
+pub struct Toggleable {
+    pub enabled: bool,
+}
+
+/// Region continuously pushing dynamic bodies inside it.
+#[derive(Component)]
+pub struct WindZone {
+    /// Force applied to bodies inside the zone.
+    pub force: Vec2,
+    /// Size of the zone, in pixels.
+    pub size: Vec2,
+}
+
+/// Region of water slowing down and floating the bodies inside it.
+#[derive(Component)]
+pub struct WaterZone {
+    /// Size of the zone, in pixels.
+    pub size: Vec2,
+    /// Can the player drown when staying too long in this water?
+    pub drowning: bool,
+}
+
+/// Breath of the player, running out when underwater in a drowning zone.
+#[derive(Component)]
+pub struct PlayerBreath {
+    pub breath: Duration,
+    pub max_breath: Duration,
+    /// Time the player last took drowning damage.
+    pub last_drown_time: Option<Duration>,
+}
+
+impl Default for PlayerBreath {
+    fn default() -> Self {
+        Self {
+            breath: Duration::from_secs(8),
+            max_breath: Duration::from_secs(8),
+            last_drown_time: None,
+        }
+    }
+}
+
+impl PlayerBreath {
+    /// Interval between two drowning damages once out of breath.
+    pub const DROWN_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn ratio(&self) -> f32 {
+        self.breath.div_duration_f32(self.max_breath).clamp(0., 1.)
+    }
+}
+
+/// Motion phase of a [`Crusher`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CrusherPhase {
+    /// Waiting at the start position before slamming.
+    #[default]
+    Waiting,
+    /// Moving fast toward the end position.
+    Slamming,
+    /// Waiting at the end position before rising back.
+    Holding,
+    /// Moving slowly back to the start position.
+    Rising,
+}
+
+/// Kinematic hazard periodically slamming along a path, crushing the player
+/// caught between it and a wall.
+///
+/// The path starts at the crusher position, then follows the points of the
+/// Tiled polyline its `path` property references, or goes straight to the
+/// referenced object if not a polyline.
+#[derive(Component)]
+pub struct Crusher {
+    /// Points of the path, in world space. The crusher rests at the first one,
+    /// and slams through the others up to the last one.
+    pub points: Vec<Vec2>,
+    /// Index of the point currently moved toward.
+    pub target: usize,
+    /// Speed toward the end position, in pixels per second.
+    pub slam_speed: f32,
+    /// Speed back to the start position, in pixels per second.
+    pub rise_speed: f32,
+    /// Delay at the start position before slamming.
+    pub wait: Duration,
+    /// Delay at the end position before rising back.
+    pub hold: Duration,
+    /// Damage dealt to a crushed player. If `None`, crushing kills instantly.
+    pub damage: Option<f32>,
+    pub phase: CrusherPhase,
+    pub phase_start_time: Duration,
+}
+
+impl Default for Crusher {
+    fn default() -> Self {
+        Self {
+            points: vec![],
+            target: 0,
+            slam_speed: 300.,
+            rise_speed: 40.,
+            wait: Duration::from_millis(1500),
+            hold: Duration::from_millis(500),
+            damage: None,
+            phase: CrusherPhase::Waiting,
+            phase_start_time: Duration::ZERO,
+        }
+    }
+}
+
+/// State of a [`FallingPlatform`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FallingPlatformState {
+    /// Solid and static, waiting for the player to stand on it.
+    #[default]
+    Idle,
+    /// Shaking as a warning before falling.
+    Shaking,
+    /// Falling down, until respawning at its original position.
+    Falling,
+}
+
+/// Platform falling shortly after the player stands on it, then respawning.
+#[derive(Component)]
+pub struct FallingPlatform {
+    /// Original position, where the platform respawns.
+    pub start: Vec3,
+    /// Delay between the player standing on the platform and the platform
+    /// falling, while it shakes.
+    pub falls_after: Duration,
+    /// Delay between the platform starting to fall and it respawning.
+    pub respawn_after: Duration,
+    pub state: FallingPlatformState,
+    pub state_start_time: Duration,
+}
+
+/// Platform periodically appearing and disappearing. All platforms share the
+/// same global clock, and can be offset from each other by their phase.
+#[derive(Component)]
+pub struct TimedPlatform {
+    /// Duration of a full appear/disappear cycle.
+    pub period: Duration,
+    /// Offset of the cycle relative to the global clock.
+    pub phase: Duration,
+    /// Fraction of the period during which the platform is solid, in \[0:1\].
+    pub on_ratio: f32,
+}
+
+impl TimedPlatform {
+    /// Duration of the warning flash before the platform disappears.
+    pub const WARNING_DURATION: Duration = Duration::from_millis(500);
+
+    /// Time elapsed since the platform last appeared.
+    fn time_in_cycle(&self, time: Duration) -> Duration {
+        let period = self.period.as_secs_f32().max(0.001);
+        Duration::from_secs_f32((time + self.phase).as_secs_f32() % period)
+    }
+
+    fn on_duration(&self) -> Duration {
+        self.period.mul_f32(self.on_ratio.clamp(0., 1.))
+    }
+
+    pub fn is_on(&self, time: Duration) -> bool {
+        self.time_in_cycle(time) < self.on_duration()
+    }
+
+    /// Is the platform about to disappear?
+    pub fn is_warning(&self, time: Duration) -> bool {
+        let t = self.time_in_cycle(time);
+        let on_duration = self.on_duration();
+        t < on_duration && t + Self::WARNING_DURATION >= on_duration
+    }
+}
+
+/// How a [`PathFollower`] continues once reaching the end of its path.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PathMode {
+    /// Go from the last point straight back to the first one.
+    #[default]
+    Loop,
+    /// Go back along the path in reverse order.
+    PingPong,
+}
+
+/// Entity moving at constant speed along a path.
+#[derive(Component)]
+pub struct PathFollower {
+    /// Points of the path, in world space.
+    pub points: Vec<Vec2>,
+    /// Speed along the path, in pixels per second.
+    pub speed: f32,
+    pub mode: PathMode,
+    /// Index of the point currently moved toward.
+    pub target: usize,
+    /// Is the entity moving backward along the path, in ping-pong mode?
+    pub is_reversed: bool,
+}
+
+impl PathFollower {
+    pub fn new(points: Vec<Vec2>, speed: f32, mode: PathMode) -> Self {
+        Self {
+            points,
+            speed,
+            mode,
+            target: 0,
+            is_reversed: false,
+        }
+    }
+
+    /// Move the target to the next point of the path.
+    pub fn advance(&mut self) {
+        let count = self.points.len();
+        if count < 2 {
+            return;
+        }
+        match self.mode {
+            PathMode::Loop => self.target = (self.target + 1) % count,
+            PathMode::PingPong => {
+                if self.is_reversed && self.target == 0 {
+                    self.is_reversed = false;
+                } else if !self.is_reversed && self.target + 1 == count {
+                    self.is_reversed = true;
+                }
+                if self.is_reversed {
+                    self.target -= 1;
+                } else {
+                    self.target += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Continuous rotation of an entity, in radians per second.
+#[derive(Component)]
+pub struct Spin(pub f32);
+
+/// Tile layer hiding a secret area, fading out while the player is inside it.
+#[derive(Component)]
+pub struct SecretLayer {
+    /// Tiled ID of the layer.
+    pub id: u32,
+    /// Current opacity of the layer.
+    pub alpha: f32,
+}
+
+/// Region covered by a [`SecretLayer`], revealing it when the player enters.
+#[derive(Component)]
+pub struct SecretArea {
+    /// Tiled ID of the secret layer.
+    pub layer: u32,
+}
+
+/// Timed effect granted to the player by a power-up pickup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerUp {
+    /// Move faster.
+    Speed,
+    /// Ignore all damage.
+    Shield,
+    /// Deal twice as much damage to enemies.
+    DoubleDamage,
+    /// Slow down the whole world.
+    SlowTime,
+}
+
+impl PowerUp {
+    /// Default duration of the effect.
+    pub fn default_duration(&self) -> Duration {
+        match self {
+            PowerUp::Speed => Duration::from_secs(8),
+            PowerUp::Shield => Duration::from_secs(6),
+            PowerUp::DoubleDamage => Duration::from_secs(10),
+            PowerUp::SlowTime => Duration::from_secs(5),
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            PowerUp::Speed => Color::srgb(0.2, 1., 1.),
+            PowerUp::Shield => Color::srgb(0.9, 0.9, 0.9),
+            PowerUp::DoubleDamage => Color::srgb(1., 0.4, 0.1),
+            PowerUp::SlowTime => Color::srgb(0.7, 0.3, 1.),
+        }
+    }
+}
+
+/// Power-up effects currently active on the player, with their remaining
+/// duration in real time.
+#[derive(Debug, Default, Component)]
+pub struct ActiveEffects {
+    pub remaining: Vec<(PowerUp, Duration)>,
+}
+
+impl ActiveEffects {
+    /// Factor applied to the player movement impulse.
+    pub const SPEED_FACTOR: f32 = 1.5;
+
+    /// Relative speed of the world while time is slowed down.
+    pub const SLOW_TIME_SPEED: f32 = 0.5;
+
+    /// Activate an effect, or extend it if the given duration is longer than
+    /// the remaining one.
+    pub fn add(&mut self, power_up: PowerUp, duration: Duration) {
+        match self.remaining.iter_mut().find(|(p, _)| *p == power_up) {
+            Some((_, remaining)) => *remaining = (*remaining).max(duration),
+            None => self.remaining.push((power_up, duration)),
+        }
+    }
+
+    pub fn has(&self, power_up: PowerUp) -> bool {
+        self.remaining.iter().any(|(p, _)| *p == power_up)
+    }
+
+    pub fn remaining(&self, power_up: PowerUp) -> Option<Duration> {
+        self.remaining
+            .iter()
+            .find(|(p, _)| *p == power_up)
+            .map(|(_, remaining)| *remaining)
+    }
+
+    /// Advance all effects, removing the expired ones.
+    pub fn tick(&mut self, delta: Duration) {
+        for (_, remaining) in &mut self.remaining {
+            *remaining = remaining.saturating_sub(delta);
+        }
+        self.remaining.retain(|(_, remaining)| !remaining.is_zero());
+    }
+
+    pub fn speed_factor(&self) -> f32 {
+        if self.has(PowerUp::Speed) {
+            Self::SPEED_FACTOR
+        } else {
+            1.
+        }
+    }
+
+    pub fn damage_factor(&self) -> f32 {
+        if self.has(PowerUp::DoubleDamage) {
+            2.
+        } else {
+            1.
+        }
+    }
+}
+
+/// Entity the player can interact with when close enough, like an NPC to
+/// talk to or a lever to pull.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Interactable {
+    /// Identifier of the message naming the interaction in the prompt.
+    pub verb: &'static str,
+    /// Distance under which the player can interact with the entity.
+    pub radius: f32,
+}
+
+/// Non-player character the player can talk to.
+#[derive(Component)]
+pub struct Npc {
+    /// Name displayed in the dialogue box, or the identifier of a translated
+    /// message.
+    pub name: String,
+    /// Pages of the dialogue, each either some text or the identifier of a
+    /// translated message.
+    pub pages: Vec<String>,
+}
+
+/// Entity belonging to the currently loaded level, despawned when unloading it.
+#[derive(Default, Component)]
+pub struct LevelEntity;
+
+/// Point light lifting the darkness of dark levels around it.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct LightSource {
+    /// Distance at which the light fades out completely, in pixels.
+    pub radius: f32,
+    /// Light level at the center, where 1 fully lifts the darkness.
+    pub intensity: f32,
+}
+
+/// Slippery ground. Its collider has no friction.
+#[derive(Default, Component)]
+pub struct Ice;
+
+/// Looping sound played from an entity, getting louder as the camera gets
+/// closer and panned toward the side of the screen the entity is on.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct SoundEmitter {
+    pub sound: EmitterSound,
+    /// Distance from the camera at which the sound fades out completely, in
+    /// pixels.
+    pub radius: f32,
+    /// Volume right next to the emitter.
+    pub volume: f32,
+}
+
+/// Kind of looping sound of a [`SoundEmitter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmitterSound {
+    Saw,
+    Fire,
+    Water,
+}
+
+/// Region of the level with a looping environmental sound, fading in as the
+/// player approaches it and at full volume inside it.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct AmbienceRegion {
+    pub sound: AmbienceSound,
+    /// Half the size of the region, which is centered on its entity.
+    pub half_size: Vec2,
+    /// Distance from the edge of the region over which the sound fades out,
+    /// in pixels.
+    pub fade_distance: f32,
+    /// Volume inside the region.
+    pub volume: f32,
+}
+
+/// Kind of looping sound of an [`AmbienceRegion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmbienceSound {
+    Drips,
+    Wind,
+    Machinery,
+}
+
+/// Material of the ground, selecting the footstep sounds of the player
+/// walking on it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub enum GroundMaterial {
+    #[default]
+    Stone,
+    Grass,
+    Metal,
+    Ice,
+}
@@ -0,0 +1,108 @@
+use bevy::{
+    diagnostic::{
+        DiagnosticPath, DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+    },
+    prelude::*,
+};
+use bevy_keith::Canvas;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    layout::UiLayout,
+    locale::{tr, Localizer},
+    AppState, UiRes,
+};
+
+/// Performance overlay, toggled with F3.
+#[derive(Debug, Default, Resource)]
+struct DiagnosticsOverlay {
+    is_visible: bool,
+}
+
+#[derive(Default)]
+pub struct DiagnosticsPlugin;
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((FrameTimeDiagnosticsPlugin, EntityCountDiagnosticsPlugin))
+            .init_resource::<DiagnosticsOverlay>()
+            .add_systems(Update, toggle_overlay)
+            .add_systems(
+                Update,
+                ui_diagnostics
+                    .after(crate::hud::main_ui)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn toggle_overlay(keyboard: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<DiagnosticsOverlay>) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        overlay.is_visible = !overlay.is_visible;
+    }
+}
+
+fn ui_diagnostics(
+    overlay: Res<DiagnosticsOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    q_bodies: Query<(), With<RigidBody>>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    if !overlay.is_visible {
+        return;
+    }
+
+    let smoothed = |path: &DiagnosticPath| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or(0.)
+    };
+    let fps = smoothed(&FrameTimeDiagnosticsPlugin::FPS);
+    let frame_time = smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME);
+    let entities = smoothed(&EntityCountDiagnosticsPlugin::ENTITY_COUNT);
+    let lines = [
+        tr!(loc, "diagnostics-fps", fps = format!("{:.0}", fps)),
+        tr!(
+            loc,
+            "diagnostics-frame",
+            time = format!("{:.2}", frame_time)
+        ),
+        tr!(
+            loc,
+            "diagnostics-entities",
+            count = format!("{:.0}", entities)
+        ),
+        tr!(loc, "diagnostics-bodies", count = q_bodies.iter().count()),
+    ];
+
+    let mut canvas = q_canvas.single_mut();
+    let mut ctx = canvas.render_context();
+
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.7));
+    let top_right = layout.top_right();
+    ctx.fill(
+        Rect::new(
+            top_right.x - 180.,
+            top_right.y + 60.,
+            top_right.x - 10.,
+            top_right.y + 150.,
+        ),
+        &brush,
+    );
+    for (index, line) in lines.into_iter().enumerate() {
+        let txt = ctx
+            .new_layout(line)
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::srgb(0.6, 1., 0.6))
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(150., 20.))
+            .build();
+        let offset = Vec2::new(-95., 72. + index as f32 * 20.);
+        ctx.draw_text(txt, top_right + offset);
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    interact::{find_target, InteractionTarget},
+    locale::Localizer,
+    replay::PlayerActions,
+    AppState, Npc,
+};
+
+/// Speed at which the dialogue text appears, in characters per second.
+const CHARS_PER_SECOND: f32 = 40.;
+
+/// Dialogue currently displayed, if any. While open, the dialogue receives all
+/// gameplay inputs.
+#[derive(Debug, Default, Resource)]
+pub struct Dialogue {
+    pub speaker: String,
+    pub pages: Vec<String>,
+    pub page: usize,
+    pub page_start_time: Duration,
+}
+
+impl Dialogue {
+    pub fn is_open(&self) -> bool {
+        !self.pages.is_empty()
+    }
+
+    fn visible_chars(&self, time: Duration) -> usize {
+        let delta = time.saturating_sub(self.page_start_time);
+        (delta.as_secs_f32() * CHARS_PER_SECOND) as usize
+    }
+
+    /// Get the part of the current page already typed out.
+    pub fn visible_text(&self, time: Duration) -> &str {
+        let Some(page) = self.pages.get(self.page) else {
+            return "";
+        };
+        match page.char_indices().nth(self.visible_chars(time)) {
+            Some((end, _)) => &page[..end],
+            None => page,
+        }
+    }
+
+    pub fn is_page_complete(&self, time: Duration) -> bool {
+        let Some(page) = self.pages.get(self.page) else {
+            return true;
+        };
+        self.visible_chars(time) >= page.chars().count()
+    }
+}
+
+/// Run condition for gameplay systems reading inputs, which are disabled while a
+/// dialogue is open.
+pub fn is_closed(dialogue: Res<Dialogue>) -> bool {
+    !dialogue.is_open()
+}
+
+#[derive(Default)]
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Dialogue>().add_systems(
+            Update,
+            talk_to_npcs
+                .after(find_target)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+fn talk_to_npcs(
+    time: Res<Time>,
+    actions: Res<PlayerActions>,
+    mut dialogue: ResMut<Dialogue>,
+    target: Res<InteractionTarget>,
+    q_npcs: Query<&Npc>,
+    loc: Localizer,
+) {
+    let now = time.elapsed();
+
+    if dialogue.is_open() {
+        // Only advance with the player actions, so replays stay in sync
+        if !actions.interact && !actions.jump {
+            return;
+        }
+        if !dialogue.is_page_complete(now) {
+            // Skip the typewriter effect
+            dialogue.page_start_time = Duration::ZERO;
+        } else if dialogue.page + 1 < dialogue.pages.len() {
+            dialogue.page += 1;
+            dialogue.page_start_time = now;
+        } else {
+            debug!("Dialogue with '{}' closed", dialogue.speaker);
+            dialogue.pages.clear();
+        }
+        return;
+    }
+
+    if !actions.interact {
+        return;
+    }
+    let Some(npc) = target.entity.and_then(|entity| q_npcs.get(entity).ok()) else {
+        return;
+    };
+    debug!("Dialogue with '{}' opened", npc.name);
+    dialogue.speaker = loc.text(&npc.name);
+    dialogue.pages = npc.pages.iter().map(|page| loc.text(page)).collect();
+    dialogue.page = 0;
+    dialogue.page_start_time = now;
+}
@@ -0,0 +1,347 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::{prelude::*, rapier::geometry::CollisionEventFlags};
+
+use crate::{
+    pickup::spawn_pickup, projectile::spawn_projectile, score::ScoreEvent, ActiveEffects, AppState,
+    Boss, Chaser, Damage, Dying, Enemy, EnemyLife, HitFlash, Patrol, Player, Projectile,
+    ProjectileOwner, Turret,
+};
+
+/// Vertical velocity given to the player after jumping on an enemy.
+const STOMP_BOUNCE: f32 = 150.;
+
+/// Damage dealt to an enemy when the player jumps on it.
+const STOMP_DAMAGE: f32 = 10.;
+
+/// Request to damage an enemy. All player attacks go through this event, which
+/// is the only way to reduce an [`EnemyLife`].
+#[derive(Debug, Clone, Copy, Event)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+#[derive(Default)]
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>().add_systems(
+            Update,
+            (
+                setup_enemies,
+                patrol,
+                chase,
+                fire_turrets,
+                stomp_enemies,
+                damage_enemies,
+                update_hit_flash,
+                update_dying,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Add the physics components to enemies spawned from the Tiled map.
+fn setup_enemies(
+    mut commands: Commands,
+    q_enemies: Query<(Entity, &Enemy, Has<Chaser>, Has<Turret>), Added<Enemy>>,
+) {
+    for (entity, enemy, is_flying, is_static) in &q_enemies {
+        let half_size = enemy.size / 2.;
+        let gravity_scale = if is_flying { 0. } else { 1. };
+        let rigid_body = if is_static {
+            RigidBody::Fixed
+        } else {
+            RigidBody::Dynamic
+        };
+        commands
+            .entity(entity)
+            .insert((
+                rigid_body,
+                LockedAxes::ROTATION_LOCKED,
+                Collider::cuboid(half_size.x, half_size.y),
+                Friction::coefficient(0.),
+                Velocity::zero(),
+                // Pushed by wind zones, unless static
+                ExternalForce::default(),
+                GravityScale(gravity_scale),
+            ))
+            .with_children(|parent| {
+                // Damage sensor slightly wider than the body but only covering its lower
+                // half, so that jumping on top of the enemy doesn't hurt the player.
+                parent.spawn((
+                    TransformBundle::from(Transform::from_xyz(0., -half_size.y / 2., 0.)),
+                    Collider::cuboid(half_size.x + 1., half_size.y / 2.),
+                    Sensor,
+                    Damage(enemy.damage),
+                    Name::new("EnemyDamage"),
+                ));
+            });
+    }
+}
+
+/// Walk enemies back and forth, turning around at walls and ledges.
+fn patrol(
+    physics: Res<RapierContext>,
+    mut q_enemies: Query<
+        (&Transform, &Enemy, &mut Patrol, &mut Velocity, &mut Sprite),
+        Without<Dying>,
+    >,
+) {
+    let filter = QueryFilter::only_fixed().exclude_sensors();
+    for (transform, enemy, mut patrol, mut velocity, mut sprite) in &mut q_enemies {
+        let pos = transform.translation.xy();
+        let half_size = enemy.size / 2.;
+
+        // Don't turn around while falling
+        let is_grounded = physics
+            .cast_ray(pos, Vec2::NEG_Y, half_size.y + 2., true, filter)
+            .is_some();
+        if is_grounded {
+            let ahead = Vec2::new(patrol.dir, 0.);
+            let hit_wall = physics
+                .cast_ray(pos, ahead, half_size.x + 2., true, filter)
+                .is_some();
+            let foot = pos + ahead * (half_size.x + 2.);
+            let at_ledge = physics
+                .cast_ray(foot, Vec2::NEG_Y, half_size.y + 4., true, filter)
+                .is_none();
+            if hit_wall || at_ledge {
+                patrol.dir = -patrol.dir;
+            }
+        }
+
+        let linvel = Vec2::new(patrol.dir * patrol.speed, velocity.linvel.y);
+        if linvel != velocity.linvel {
+            velocity.linvel = linvel;
+        }
+        let flip_x = patrol.dir < 0.;
+        if sprite.flip_x != flip_x {
+            sprite.flip_x = flip_x;
+        }
+    }
+}
+
+/// Steer flying enemies toward the player when it's within their detection
+/// radius, and slow them down to a hover otherwise.
+fn chase(
+    time: Res<Time>,
+    q_player: Query<&Transform, With<Player>>,
+    mut q_enemies: Query<
+        (&Transform, &Chaser, &mut Velocity, &mut Sprite),
+        (Without<Player>, Without<Dying>),
+    >,
+) {
+    let player_pos = q_player.get_single().ok().map(|t| t.translation.xy());
+    let dt = time.delta_seconds();
+    for (transform, chaser, mut velocity, mut sprite) in &mut q_enemies {
+        let to_player = player_pos.map(|p| p - transform.translation.xy());
+        let desired = match to_player {
+            Some(delta) if delta.length_squared() <= chaser.aggro_range * chaser.aggro_range => {
+                delta.normalize_or_zero() * chaser.speed
+            }
+            _ => Vec2::ZERO,
+        };
+
+        // Simple seek steering, limited by the max acceleration
+        let steering = (desired - velocity.linvel).clamp_length_max(chaser.acceleration * dt);
+        if steering != Vec2::ZERO {
+            velocity.linvel += steering;
+        }
+
+        if velocity.linvel.x.abs() > 1. {
+            let flip_x = velocity.linvel.x < 0.;
+            if sprite.flip_x != flip_x {
+                sprite.flip_x = flip_x;
+            }
+        }
+    }
+}
+
+/// Fire projectiles toward the player from turrets which have a clear line of
+/// sight to it.
+fn fire_turrets(
+    mut commands: Commands,
+    time: Res<Time>,
+    physics: Res<RapierContext>,
+    q_player: Query<&Transform, With<Player>>,
+    mut q_turrets: Query<
+        (Entity, &Transform, &Enemy, &mut Turret),
+        (Without<Player>, Without<Dying>),
+    >,
+) {
+    let Ok(player_transform) = q_player.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.xy();
+
+    for (entity, transform, enemy, mut turret) in &mut q_turrets {
+        if let Some(last_shot_time) = turret.last_shot_time {
+            if time.elapsed() < last_shot_time + turret.fire_interval {
+                continue;
+            }
+        }
+
+        let pos = transform.translation.xy();
+        let delta = player_pos - pos;
+        let dist = delta.length();
+        if dist > turret.range || dist < 1. {
+            continue;
+        }
+        let dir = delta / dist;
+
+        // Check line of sight through the static world colliders
+        let filter = QueryFilter::only_fixed()
+            .exclude_sensors()
+            .exclude_collider(entity);
+        if physics.cast_ray(pos, dir, dist, true, filter).is_some() {
+            continue;
+        }
+
+        turret.last_shot_time = Some(time.elapsed());
+        let muzzle = pos + dir * (enemy.size.max_element() / 2. + 4.);
+        spawn_projectile(
+            &mut commands,
+            muzzle.extend(transform.translation.z),
+            dir * turret.projectile_speed,
+            Projectile {
+                owner: ProjectileOwner::Enemy,
+                damage: enemy.damage,
+                spawn_time: time.elapsed(),
+                lifetime: Duration::from_secs(3),
+            },
+        );
+    }
+}
+
+/// Damage enemies the player jumps on, and bounce the player back up.
+fn stomp_enemies(
+    mut q_player: Query<(Entity, &Transform, &mut Velocity, &ActiveEffects), With<Player>>,
+    q_enemies: Query<(&Transform, &Enemy), (Without<Player>, Without<Dying>)>,
+    mut events: EventReader<CollisionEvent>,
+    mut ev_damage: EventWriter<DamageEvent>,
+) {
+    let Ok((player_entity, player_transform, mut player_velocity, effects)) =
+        q_player.get_single_mut()
+    else {
+        return;
+    };
+
+    for ev in events.read() {
+        let CollisionEvent::Started(e1, e2, flags) = ev else {
+            continue;
+        };
+
+        // Only consider solid contacts with the enemy body
+        if flags.contains(CollisionEventFlags::SENSOR) {
+            continue;
+        }
+
+        let mut e1 = *e1;
+        let mut e2 = *e2;
+        // Swap entities such that player is always #1 and enemy is always #2
+        if e2 == player_entity {
+            std::mem::swap(&mut e1, &mut e2);
+        }
+        if e1 != player_entity {
+            continue;
+        }
+        let Ok((enemy_transform, enemy)) = q_enemies.get(e2) else {
+            continue;
+        };
+
+        let dy = player_transform.translation.y - enemy_transform.translation.y;
+        if dy > enemy.size.y / 4. {
+            debug!("Player stomped enemy {:?}", e2);
+            ev_damage.send(DamageEvent {
+                target: e2,
+                amount: STOMP_DAMAGE * effects.damage_factor(),
+            });
+            player_velocity.linvel.y = STOMP_BOUNCE;
+        }
+    }
+}
+
+/// Apply damage events to enemies, flashing them on hit and killing them when
+/// they run out of life.
+fn damage_enemies(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut events: EventReader<DamageEvent>,
+    mut q_enemies: Query<(&mut EnemyLife, &Transform, Has<Boss>), Without<Dying>>,
+    mut ev_score: EventWriter<ScoreEvent>,
+) {
+    for ev in events.read() {
+        let Ok((mut enemy_life, transform, is_boss)) = q_enemies.get_mut(ev.target) else {
+            continue;
+        };
+        // Already killed by a previous event this frame
+        if enemy_life.life <= 0. {
+            continue;
+        }
+
+        enemy_life.life = (enemy_life.life - ev.amount).max(0.);
+        debug!(
+            "Enemy {:?} took {} damage, life={}/{}",
+            ev.target, ev.amount, enemy_life.life, enemy_life.max_life
+        );
+
+        let start_time = time.elapsed();
+        if enemy_life.life > 0. {
+            commands.entity(ev.target).insert(HitFlash { start_time });
+        } else {
+            // Stop interacting with the world, and remove the damage sensor
+            commands
+                .entity(ev.target)
+                .insert((Dying { start_time }, ColliderDisabled, RigidBodyDisabled))
+                .remove::<HitFlash>()
+                .despawn_descendants();
+            ev_score.send(ScoreEvent::EnemyKill { is_boss });
+            if let Some(pickup) = enemy_life.drop {
+                spawn_pickup(&mut commands, transform.translation, pickup);
+            }
+        }
+    }
+}
+
+fn update_hit_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_enemies: Query<(Entity, &HitFlash, &mut Sprite)>,
+) {
+    for (entity, flash, mut sprite) in &mut q_enemies {
+        let color = if time.elapsed() < flash.start_time + HitFlash::DURATION {
+            Color::srgb(1., 0.3, 0.3)
+        } else {
+            commands.entity(entity).remove::<HitFlash>();
+            Color::WHITE
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Play the death animation of enemies, then despawn them.
+fn update_dying(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_enemies: Query<(Entity, &Dying, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, dying, mut transform, mut sprite) in &mut q_enemies {
+        let delta = time.elapsed().saturating_sub(dying.start_time);
+        if delta >= Dying::DURATION {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // Squash and fade out
+        let ratio = delta.div_duration_f32(Dying::DURATION);
+        transform.scale = Vec3::new(1. + ratio * 0.5, 1. - ratio, 1.);
+        sprite.color = Color::srgba(1., 1., 1., 1. - ratio);
+    }
+}
@@ -0,0 +1,424 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    rng::GameRng,
+    sound::SfxChannel,
+    trigger::{TriggerEnter, TriggerKind},
+    AppState, Bounce, GravityFlipZone, Player, PlayerBreath, PlayerController, PlayerLife,
+    WaterZone, WindZone,
+};
+
+/// Duration of the squash animation of a spring after bouncing.
+const SQUASH_DURATION: Duration = Duration::from_millis(200);
+
+/// Average number of wind streaks spawned per second, per 1000 square pixels
+/// of wind zone.
+const WIND_STREAK_RATE: f32 = 2.;
+
+/// Lifetime of wind streaks.
+const WIND_STREAK_LIFETIME: Duration = Duration::from_millis(600);
+
+/// Linear damping of the player while in water, slowing down all movements.
+const WATER_DAMPING: f32 = 4.;
+
+/// Damage dealt to the player each [`PlayerBreath::DROWN_INTERVAL`] once out of
+/// breath.
+const DROWN_DAMAGE: f32 = 5.;
+
+/// Lifetime of splash droplets.
+const SPLASH_LIFETIME: Duration = Duration::from_millis(500);
+
+#[derive(Default, Resource)]
+struct EnvironmentSounds {
+    pub bounce: Handle<bevy_kira_audio::AudioSource>,
+    pub splash: Handle<bevy_kira_audio::AudioSource>,
+}
+
+/// Squash animation of a bounce pad sprite.
+#[derive(Component)]
+struct BounceSquash {
+    start_time: Duration,
+    /// Original size of the sprite.
+    size: Vec2,
+}
+
+/// Visual feedback of the wind direction inside a wind zone.
+#[derive(Component)]
+struct WindStreak {
+    velocity: Vec2,
+    spawn_time: Duration,
+}
+
+/// Water droplet thrown when the player enters or leaves water.
+#[derive(Component)]
+struct SplashDroplet {
+    velocity: Vec2,
+    spawn_time: Duration,
+}
+
+#[derive(Default)]
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EnvironmentSounds>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(
+                Update,
+                (
+                    bounce_player,
+                    squash_bounce_pads,
+                    apply_wind,
+                    spawn_wind_streaks,
+                    update_wind_streaks,
+                    flip_gravity,
+                    swim,
+                    breathe,
+                    update_splashes,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<EnvironmentSounds>) {
+    sounds.bounce = asset_server.load("select1.ogg");
+    sounds.splash = sounds.bounce.clone();
+}
+
+/// Launch the player upward when landing on a bounce pad.
+fn bounce_player(
+    mut commands: Commands,
+    time: Res<Time>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<EnvironmentSounds>,
+    mut q_player: Query<(&Transform, &mut Velocity, &mut PlayerController), With<Player>>,
+    q_bounce: Query<(&Bounce, &GlobalTransform, Option<&Sprite>)>,
+    mut ev_enter: EventReader<TriggerEnter>,
+) {
+    let Ok((player_transform, mut velocity, mut player_controller)) = q_player.get_single_mut()
+    else {
+        return;
+    };
+
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Bounce {
+            continue;
+        }
+        let Ok((bounce, bounce_transform, sprite)) = q_bounce.get(ev.trigger) else {
+            continue;
+        };
+
+        // Only bounce when landing on top of the pad
+        if player_transform.translation.y <= bounce_transform.translation().y {
+            continue;
+        }
+
+        debug!("Player bounced on {:?} at speed {}", ev.trigger, bounce.0);
+        velocity.linvel.y = bounce.0;
+        // Bouncing cancels climbing, like a regular jump
+        player_controller.is_grounded = false;
+        player_controller.is_climbing = false;
+        audio.play(sounds.bounce.clone());
+
+        if let Some(size) = sprite.and_then(|sprite| sprite.custom_size) {
+            commands.entity(ev.trigger).insert(BounceSquash {
+                start_time: time.elapsed(),
+                size,
+            });
+        }
+    }
+}
+
+fn squash_bounce_pads(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_pads: Query<(Entity, &BounceSquash, &mut Sprite)>,
+) {
+    for (entity, squash, mut sprite) in &mut q_pads {
+        let delta = time.elapsed().saturating_sub(squash.start_time);
+        let ratio = delta.div_duration_f32(SQUASH_DURATION).min(1.);
+        if ratio >= 1. {
+            commands.entity(entity).remove::<BounceSquash>();
+        }
+        // Squash down instantly then recover
+        let squash_factor = 1. - 0.4 * (1. - ratio);
+        sprite.custom_size = Some(Vec2::new(squash.size.x, squash.size.y * squash_factor));
+    }
+}
+
+/// Apply the force of wind zones to the dynamic bodies inside them, that is the
+/// player, the enemies, and the falling platforms. Patrolling enemies set their
+/// own horizontal speed each frame though, so only vertical wind moves them.
+///
+/// This uses an [`ExternalForce`], which rapier adds on top of the per-frame
+/// [`ExternalImpulse`] written by `player_input()`, so the player can still
+/// move against the wind.
+fn apply_wind(
+    physics: Res<RapierContext>,
+    q_zones: Query<(Entity, &WindZone)>,
+    mut q_bodies: Query<(Entity, &mut ExternalForce)>,
+) {
+    let mut forces = bevy::utils::HashMap::<Entity, Vec2>::new();
+    for (zone_entity, zone) in &q_zones {
+        for (e1, e2, intersecting) in physics.intersection_pairs_with(zone_entity) {
+            if !intersecting {
+                continue;
+            }
+            let other_entity = if e1 == zone_entity { e2 } else { e1 };
+            if q_bodies.contains(other_entity) {
+                *forces.entry(other_entity).or_default() += zone.force;
+            }
+        }
+    }
+
+    for (entity, mut external_force) in &mut q_bodies {
+        let force = forces.get(&entity).copied().unwrap_or(Vec2::ZERO);
+        if external_force.force != force {
+            external_force.force = force;
+        }
+    }
+}
+
+fn spawn_wind_streaks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    q_zones: Query<(&WindZone, &GlobalTransform)>,
+) {
+    for (zone, transform) in &q_zones {
+        let dir = zone.force.normalize_or_zero();
+        if dir == Vec2::ZERO {
+            continue;
+        }
+
+        let chance = WIND_STREAK_RATE * zone.size.x * zone.size.y / 1000. * time.delta_seconds();
+        if rng.gen::<f32>() >= chance {
+            continue;
+        }
+
+        let offset = (Vec2::new(rng.gen(), rng.gen()) - 0.5) * zone.size;
+        let position = transform.translation() + offset.extend(1.);
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(position)
+                    .with_rotation(Quat::from_rotation_z(dir.to_angle())),
+                sprite: Sprite {
+                    color: Color::srgba(1., 1., 1., 0.5),
+                    custom_size: Some(Vec2::new(6., 1.)),
+                    ..default()
+                },
+                ..default()
+            },
+            WindStreak {
+                velocity: dir * 80.,
+                spawn_time: time.elapsed(),
+            },
+            Name::new("WindStreak"),
+        ));
+    }
+}
+
+fn update_wind_streaks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_streaks: Query<(Entity, &WindStreak, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, streak, mut transform, mut sprite) in &mut q_streaks {
+        let delta = time.elapsed().saturating_sub(streak.spawn_time);
+        if delta >= WIND_STREAK_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation += (streak.velocity * time.delta_seconds()).extend(0.);
+        let ratio = delta.div_duration_f32(WIND_STREAK_LIFETIME);
+        // Fade in then out
+        sprite.color.set_alpha(0.5 * (1. - (2. * ratio - 1.).abs()));
+    }
+}
+
+/// Detect the player entering and leaving water, and apply buoyancy and drag
+/// while swimming.
+fn swim(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<EnvironmentSounds>,
+    physics: Res<RapierContext>,
+    mut q_player: Query<
+        (
+            Entity,
+            &Transform,
+            &mut PlayerController,
+            &mut GravityScale,
+            &mut Damping,
+        ),
+        With<Player>,
+    >,
+    q_water: Query<(), With<WaterZone>>,
+) {
+    let Ok((player_entity, transform, mut player_controller, mut gravity_scale, mut damping)) =
+        q_player.get_single_mut()
+    else {
+        return;
+    };
+
+    let is_in_water =
+        physics
+            .intersection_pairs_with(player_entity)
+            .any(|(e1, e2, intersecting)| {
+                let other_entity = if e1 == player_entity { e2 } else { e1 };
+                intersecting && q_water.contains(other_entity)
+            });
+
+    if player_controller.is_in_water != is_in_water {
+        debug!("Player in water: {}", is_in_water);
+        player_controller.is_in_water = is_in_water;
+        audio.play(sounds.splash.clone());
+        spawn_splash(
+            &mut commands,
+            &mut rng,
+            time.elapsed(),
+            transform.translation,
+        );
+        damping.linear_damping = if is_in_water { WATER_DAMPING } else { 0. };
+    }
+
+    // Also restores buoyancy after leaving a ladder underwater
+    let scale = player_controller.gravity_scale();
+    if gravity_scale.0 != scale {
+        gravity_scale.0 = scale;
+    }
+}
+
+/// Invert gravity for the player while inside a gravity flip zone, flipping its
+/// sprite upside down.
+fn flip_gravity(
+    physics: Res<RapierContext>,
+    mut q_player: Query<
+        (
+            Entity,
+            &mut PlayerController,
+            &mut GravityScale,
+            &mut Sprite,
+        ),
+        With<Player>,
+    >,
+    q_zones: Query<(), With<GravityFlipZone>>,
+) {
+    let Ok((player_entity, mut player_controller, mut gravity_scale, mut sprite)) =
+        q_player.get_single_mut()
+    else {
+        return;
+    };
+
+    let is_flipped =
+        physics
+            .intersection_pairs_with(player_entity)
+            .any(|(e1, e2, intersecting)| {
+                let other_entity = if e1 == player_entity { e2 } else { e1 };
+                intersecting && q_zones.contains(other_entity)
+            });
+    if player_controller.is_gravity_flipped == is_flipped {
+        return;
+    }
+
+    debug!("Player gravity flipped: {}", is_flipped);
+    player_controller.is_gravity_flipped = is_flipped;
+    gravity_scale.0 = player_controller.gravity_scale();
+    sprite.flip_y = is_flipped;
+}
+
+fn spawn_splash(commands: &mut Commands, rng: &mut GameRng, time: Duration, position: Vec3) {
+    for _ in 0..8 {
+        let angle = std::f32::consts::FRAC_PI_2 + (rng.gen::<f32>() - 0.5) * 2.;
+        let speed = 40. + rng.gen::<f32>() * 40.;
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(position + Vec3::Z),
+                sprite: Sprite {
+                    color: Color::srgba(0.5, 0.7, 1., 0.8),
+                    custom_size: Some(Vec2::splat(2.)),
+                    ..default()
+                },
+                ..default()
+            },
+            SplashDroplet {
+                velocity: Vec2::from_angle(angle) * speed,
+                spawn_time: time,
+            },
+            Name::new("SplashDroplet"),
+        ));
+    }
+}
+
+fn update_splashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_droplets: Query<(Entity, &mut SplashDroplet, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut droplet, mut transform, mut sprite) in &mut q_droplets {
+        let delta = time.elapsed().saturating_sub(droplet.spawn_time);
+        if delta >= SPLASH_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        droplet.velocity.y -= 300. * dt;
+        transform.translation += (droplet.velocity * dt).extend(0.);
+        let ratio = delta.div_duration_f32(SPLASH_LIFETIME);
+        sprite.color.set_alpha(0.8 * (1. - ratio));
+    }
+}
+
+/// Drain the player breath while in drowning water, and hurt the player once
+/// out of breath. Breath recovers outside of such water.
+fn breathe(
+    time: Res<Time>,
+    physics: Res<RapierContext>,
+    mut q_player: Query<(Entity, &mut PlayerBreath, &mut PlayerLife), With<Player>>,
+    q_water: Query<&WaterZone>,
+) {
+    let Ok((player_entity, mut breath, mut player_life)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    let is_drowning =
+        physics
+            .intersection_pairs_with(player_entity)
+            .any(|(e1, e2, intersecting)| {
+                let other_entity = if e1 == player_entity { e2 } else { e1 };
+                intersecting && q_water.get(other_entity).is_ok_and(|water| water.drowning)
+            });
+
+    if !is_drowning {
+        if breath.breath < breath.max_breath {
+            // Recover twice as fast as breath is lost
+            breath.breath = (breath.breath + time.delta() * 2).min(breath.max_breath);
+        }
+        breath.last_drown_time = None;
+        return;
+    }
+
+    breath.breath = breath.breath.saturating_sub(time.delta());
+    if !breath.breath.is_zero() {
+        return;
+    }
+
+    let now = time.elapsed();
+    if breath
+        .last_drown_time
+        .is_some_and(|last| now < last + PlayerBreath::DROWN_INTERVAL)
+    {
+        return;
+    }
+    breath.last_drown_time = Some(now);
+    debug!("Player drowning");
+    player_life.damage(now, DROWN_DAMAGE, Vec2::ZERO);
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::{TileTextureIndex, TileVisible};
+
+use crate::{AppState, Epoch, EpochSprite};
+
+#[derive(Default)]
+pub struct EpochPlugin;
+
+impl Plugin for EpochPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_epoch)
+            .add_systems(PostUpdate, apply_epoch.run_if(in_state(AppState::InGame)));
+    }
+}
+
+fn spawn_epoch(mut commands: Commands) {
+    commands.spawn(Epoch::default());
+}
+
+/// Show the tiles of each [`EpochSprite`] matching the current epoch, and hide
+/// the others.
+fn apply_epoch(
+    epoch: Query<&Epoch, Changed<Epoch>>,
+    mut q_epoch_sprites: Query<(&EpochSprite, &mut TileTextureIndex, &mut TileVisible)>,
+) {
+    let Ok(epoch) = epoch.get_single() else {
+        return;
+    };
+
+    for (epoch_sprite, mut tile_tex_id, mut tile_visible) in &mut q_epoch_sprites {
+        let tile_epoch = epoch.cur + epoch_sprite.delta;
+        if tile_epoch >= epoch_sprite.first && tile_epoch <= epoch_sprite.last {
+            if !tile_visible.0 {
+                tile_visible.0 = true;
+            }
+
+            let new_id = epoch_sprite.base as u32 + (tile_epoch - epoch_sprite.first) as u32;
+            if new_id != tile_tex_id.0 {
+                trace!(
+                    "Sprite #{}: epoch={} tile_epoch={} in [{},{}] => visible=true, new_id={}",
+                    tile_tex_id.0,
+                    epoch.cur,
+                    tile_epoch,
+                    epoch_sprite.first,
+                    epoch_sprite.last,
+                    new_id
+                );
+                tile_tex_id.0 = new_id;
+            }
+        } else {
+            if tile_visible.0 {
+                trace!(
+                    "Sprite #{}: epoch={} tile_epoch={} out of [{},{}] => visible=false",
+                    tile_tex_id.0,
+                    epoch.cur,
+                    tile_epoch,
+                    epoch_sprite.first,
+                    epoch_sprite.last
+                );
+                tile_visible.0 = false;
+            }
+        }
+    }
+}
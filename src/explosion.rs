@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    enemy::DamageEvent, rng::GameRng, sound::SfxChannel, AppState, Breakable, Enemy, Explosive,
+    Player, PlayerLife,
+};
+
+/// Speed given to the bodies at the center of an explosion, in pixels per
+/// second.
+const EXPLOSION_SPEED: f32 = 250.;
+
+/// Lifetime of the explosion particles.
+const PARTICLE_LIFETIME: Duration = Duration::from_millis(400);
+
+/// Duration of the camera shake after an explosion.
+const SHAKE_DURATION: Duration = Duration::from_millis(300);
+
+/// Request to make something explode, damaging and pushing everything within
+/// its radius and destroying breakables.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct ExplosionEvent {
+    pub position: Vec2,
+    /// Radius of the explosion, in pixels.
+    pub radius: f32,
+    /// Damage dealt at the center of the explosion, decreasing with distance.
+    pub damage: f32,
+    /// Entity causing the explosion, which is not affected by it.
+    pub source: Option<Entity>,
+}
+
+/// Camera shake, fading out over time.
+#[derive(Debug, Default, Resource)]
+pub struct CameraShake {
+    /// Maximum camera offset, in pixels.
+    pub strength: f32,
+    pub start_time: Duration,
+}
+
+impl CameraShake {
+    /// Start shaking, unless already shaking more.
+    pub fn add(&mut self, time: Duration, strength: f32) {
+        if strength >= self.current_strength(time) {
+            self.strength = strength;
+            self.start_time = time;
+        }
+    }
+
+    fn current_strength(&self, time: Duration) -> f32 {
+        let delta = time.saturating_sub(self.start_time);
+        if delta >= SHAKE_DURATION {
+            0.
+        } else {
+            self.strength * (1. - delta.div_duration_f32(SHAKE_DURATION))
+        }
+    }
+
+    /// Random camera offset for the current time.
+    pub fn offset(&self, time: Duration, rng: &mut GameRng) -> Vec2 {
+        let strength = self.current_strength(time);
+        if strength <= 0. {
+            return Vec2::ZERO;
+        }
+        (Vec2::new(rng.gen(), rng.gen()) - 0.5) * 2. * strength
+    }
+}
+
+#[derive(Default, Resource)]
+struct ExplosionSounds {
+    pub explosion: Handle<bevy_kira_audio::AudioSource>,
+}
+
+#[derive(Component)]
+struct ExplosionParticle {
+    velocity: Vec2,
+    spawn_time: Duration,
+}
+
+#[derive(Default)]
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExplosionSounds>()
+            .init_resource::<CameraShake>()
+            .add_event::<ExplosionEvent>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(
+                Update,
+                (explode, update_particles).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<ExplosionSounds>) {
+    sounds.explosion = asset_server.load("select1.ogg");
+}
+
+/// Apply the explosions, damaging and pushing the bodies around them.
+/// Explosives destroyed by an explosion explode in turn on the next frame.
+fn explode(
+    mut commands: Commands,
+    time: Res<Time>,
+    physics: Res<RapierContext>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<ExplosionSounds>,
+    mut shake: ResMut<CameraShake>,
+    mut rng: ResMut<GameRng>,
+    mut events: ResMut<Events<ExplosionEvent>>,
+    mut q_player: Query<&mut PlayerLife, With<Player>>,
+    mut q_bodies: Query<(&RigidBody, &GlobalTransform, &mut Velocity)>,
+    q_enemies: Query<(), With<Enemy>>,
+    q_breakables: Query<(&GlobalTransform, Option<&Explosive>), With<Breakable>>,
+    q_parents: Query<&Parent>,
+    mut ev_damage: EventWriter<DamageEvent>,
+) {
+    let explosions: Vec<ExplosionEvent> = events.drain().collect();
+    for explosion in explosions {
+        debug!("Explosion at {:?}", explosion.position);
+        audio.play(sounds.explosion.clone());
+        shake.add(time.elapsed(), (explosion.radius / 8.).min(6.));
+        spawn_particles(&mut commands, &mut rng, time.elapsed(), explosion);
+
+        // Find all entities with a collider inside the explosion, or their
+        // parent body for child colliders.
+        let mut hits = HashSet::new();
+        physics.intersections_with_shape(
+            explosion.position,
+            0.,
+            &Collider::ball(explosion.radius),
+            QueryFilter::default(),
+            |entity| {
+                hits.insert(entity);
+                hits.extend(q_parents.iter_ancestors(entity));
+                true
+            },
+        );
+        if let Some(source) = explosion.source {
+            hits.remove(&source);
+        }
+
+        for entity in hits {
+            if let Ok((transform, explosive)) = q_breakables.get(entity) {
+                commands.entity(entity).despawn_recursive();
+                if let Some(explosive) = explosive {
+                    events.send(ExplosionEvent {
+                        position: transform.translation().xy(),
+                        radius: explosive.radius,
+                        damage: explosive.damage,
+                        source: Some(entity),
+                    });
+                }
+                continue;
+            }
+
+            let Ok((rigid_body, transform, mut velocity)) = q_bodies.get_mut(entity) else {
+                continue;
+            };
+            let delta = transform.translation().xy() - explosion.position;
+            let dist = delta.length();
+            let dir = if dist > 0. { delta / dist } else { Vec2::Y };
+            let falloff = 1. - (dist / explosion.radius).min(1.) * 0.5;
+            let damage = explosion.damage * falloff;
+
+            if let Ok(mut player_life) = q_player.get_mut(entity) {
+                player_life.damage(time.elapsed(), damage, dir);
+            } else if q_enemies.contains(entity) {
+                ev_damage.send(DamageEvent {
+                    target: entity,
+                    amount: damage,
+                });
+            }
+            if *rigid_body == RigidBody::Dynamic {
+                velocity.linvel += dir * EXPLOSION_SPEED * falloff;
+            }
+        }
+    }
+}
+
+fn spawn_particles(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    time: Duration,
+    explosion: ExplosionEvent,
+) {
+    let count = (explosion.radius / 2.).clamp(8., 32.) as u32;
+    for _ in 0..count {
+        let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+        let speed = explosion.radius * (1. + rng.gen::<f32>() * 2.);
+        let color = if rng.gen::<bool>() {
+            Color::srgb(1., 0.8, 0.2)
+        } else {
+            Color::srgb(1., 0.4, 0.1)
+        };
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(explosion.position.extend(6.)),
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(3.)),
+                    ..default()
+                },
+                ..default()
+            },
+            ExplosionParticle {
+                velocity: Vec2::from_angle(angle) * speed,
+                spawn_time: time,
+            },
+            Name::new("ExplosionParticle"),
+        ));
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_particles: Query<(Entity, &mut ExplosionParticle, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in &mut q_particles {
+        let delta = time.elapsed().saturating_sub(particle.spawn_time);
+        if delta >= PARTICLE_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // Slow down quickly, like smoke
+        particle.velocity *= 1. - (6. * dt).min(1.);
+        transform.translation += (particle.velocity * dt).extend(0.);
+        let ratio = delta.div_duration_f32(PARTICLE_LIFETIME);
+        transform.scale = Vec3::splat(1. - ratio * 0.5);
+        sprite.color.set_alpha(1. - ratio);
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_keith::Canvas;
+
+use crate::{checkpoint::RespawnEvent, layout::UiLayout, level::LoadLevelEvent, AppState};
+
+/// Duration of each of the fade out and fade in.
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+/// Duration of the fade out before quitting, slower to let the music fade out
+/// with the screen.
+const EXIT_FADE_DURATION: Duration = Duration::from_millis(500);
+
+/// Action run once the screen is fully black, before fading back in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeAction {
+    SetState(AppState),
+    LoadLevel(usize),
+    /// Respawn the player at the last checkpoint, returning to the game if
+    /// needed.
+    Respawn,
+    /// Quit the game.
+    Exit,
+}
+
+impl FadeAction {
+    /// Duration of the fade out before running the action.
+    pub fn duration(&self) -> Duration {
+        match self {
+            FadeAction::Exit => EXIT_FADE_DURATION,
+            _ => FADE_DURATION,
+        }
+    }
+}
+
+/// Request to fade the screen to black, run an action, then fade back in.
+/// Ignored while already fading out for another action.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct FadeEvent(pub FadeAction);
+
+/// Fade of the screen to and from black.
+#[derive(Debug, Default, Resource)]
+pub struct ScreenFade {
+    /// Action waiting for the screen to be black, while fading out.
+    action: Option<FadeAction>,
+    /// Opacity of the black screen, in \[0:1\].
+    alpha: f32,
+}
+
+impl ScreenFade {
+    /// Check if the screen is fading out or back in.
+    pub fn is_fading(&self) -> bool {
+        self.action.is_some() || self.alpha > 0.
+    }
+}
+
+#[derive(Default)]
+pub struct FadePlugin;
+
+impl Plugin for FadePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenFade>()
+            .add_event::<FadeEvent>()
+            .add_systems(Update, update_fade)
+            // Draw after the UI of all states, which clear the canvas
+            .add_systems(PostUpdate, draw_fade);
+    }
+}
+
+fn update_fade(
+    // Keep fading while the game time is paused
+    time: Res<Time<bevy::time::Real>>,
+    app_state: Res<State<AppState>>,
+    mut fade: ResMut<ScreenFade>,
+    mut events: EventReader<FadeEvent>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut ev_load_level: EventWriter<LoadLevelEvent>,
+    mut ev_respawn: EventWriter<RespawnEvent>,
+    mut ev_app_exit: EventWriter<AppExit>,
+) {
+    for FadeEvent(action) in events.read() {
+        if fade.action.is_none() {
+            debug!("Fading out for {:?}", action);
+            fade.action = Some(*action);
+        }
+    }
+
+    let Some(action) = fade.action else {
+        let step = time.delta_seconds() / FADE_DURATION.as_secs_f32();
+        fade.alpha = (fade.alpha - step).max(0.);
+        return;
+    };
+    let step = time.delta_seconds() / action.duration().as_secs_f32();
+    fade.alpha = (fade.alpha + step).min(1.);
+    if fade.alpha < 1. {
+        return;
+    }
+
+    match action {
+        FadeAction::SetState(state) => next_app_state.set(state),
+        FadeAction::LoadLevel(index) => {
+            ev_load_level.send(LoadLevelEvent(index));
+        }
+        FadeAction::Respawn => {
+            ev_respawn.send(RespawnEvent);
+            if *app_state.get() != AppState::InGame {
+                next_app_state.set(AppState::InGame);
+            }
+        }
+        FadeAction::Exit => {
+            ev_app_exit.send(AppExit::Success);
+        }
+    }
+    fade.action = None;
+}
+
+pub fn draw_fade(fade: Res<ScreenFade>, mut q_canvas: Query<&mut Canvas>, layout: Res<UiLayout>) {
+    if fade.alpha <= 0. {
+        return;
+    }
+    let Ok(mut canvas) = q_canvas.get_single_mut() else {
+        return;
+    };
+    let mut ctx = canvas.render_context();
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., fade.alpha));
+    ctx.fill(layout.screen_rect(), &brush);
+}
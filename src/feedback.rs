@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_keith::Canvas;
+use bevy_kira_audio::prelude::*;
+
+use crate::{layout::UiLayout, sound::SfxChannel, AppState, MainCamera, Player, PlayerLife};
+
+/// Thickness of the red screen border flashed when taking damage.
+const FLASH_THICKNESS: f32 = 24.;
+
+/// Number of bands of the low life vignette, fading towards the center.
+const VIGNETTE_BANDS: u32 = 6;
+
+/// Thickness of each band of the low life vignette.
+const VIGNETTE_BAND_THICKNESS: f32 = 12.;
+
+/// Duration of a heartbeat of the low life vignette.
+const HEARTBEAT_PERIOD: Duration = Duration::from_millis(900);
+
+/// Opacity of the grey overlay washing out the colors of the world while low
+/// on life.
+const WASH_OUT_ALPHA: f32 = 0.3;
+
+/// Speed at which the grey overlay fades in and out, in opacity per second.
+const WASH_OUT_SPEED: f32 = 1.;
+
+/// Depth of the grey overlay, above the world but below the darkness overlay.
+const OVERLAY_DEPTH: f32 = 499.;
+
+#[derive(Default, Resource)]
+struct FeedbackSounds {
+    pub heartbeat: Handle<bevy_kira_audio::AudioSource>,
+}
+
+/// Instance of the heartbeat loop playing while low on life.
+#[derive(Default, Resource)]
+struct HeartbeatLoop(Option<Handle<AudioInstance>>);
+
+impl HeartbeatLoop {
+    fn stop(&mut self, instances: &mut Assets<AudioInstance>) {
+        if let Some(handle) = self.0.take() {
+            if let Some(instance) = instances.get_mut(&handle) {
+                instance.stop(AudioTween::default());
+            }
+        }
+    }
+}
+
+/// Translucent grey sprite covering the camera view, faded in while low on
+/// life to make the world look desaturated.
+#[derive(Component)]
+struct WashOutOverlay;
+
+#[derive(Default)]
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FeedbackSounds>()
+            .init_resource::<HeartbeatLoop>()
+            .add_systems(Startup, (load_sounds, spawn_overlay))
+            .add_systems(
+                Update,
+                (
+                    tint_damaged_player,
+                    update_heartbeat_sound,
+                    ui_damage_feedback.after(crate::hud::main_ui),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                PostUpdate,
+                update_wash_out
+                    .after(crate::camera::update_camera)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), stop_heartbeat_sound);
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<FeedbackSounds>) {
+    sounds.heartbeat = asset_server.load("select1.ogg");
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::srgba(0.5, 0.5, 0.5, 0.),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        WashOutOverlay,
+        Name::new("WashOutOverlay"),
+    ));
+}
+
+/// Intensity in \[0:1\] of the damage feedback, fading out over
+/// [`PlayerLife::damage_duration`].
+fn damage_intensity(player_life: &PlayerLife, time: Duration) -> f32 {
+    player_life
+        .damage_impulse_factor(time)
+        .map(|ratio| 1. - ratio)
+        .unwrap_or(0.)
+}
+
+/// Intensity in \[0:1\] of a double heartbeat, repeating every
+/// [`HEARTBEAT_PERIOD`].
+pub fn heartbeat(time: Duration) -> f32 {
+    let t = (time.as_secs_f32() / HEARTBEAT_PERIOD.as_secs_f32()).fract();
+    let beat = |center: f32| (1. - (t - center).abs() * 10.).max(0.);
+    beat(0.1).max(beat(0.3) * 0.6)
+}
+
+/// Tint the player sprite red after taking damage, keeping the alpha used
+/// for blinking.
+fn tint_damaged_player(
+    time: Res<Time>,
+    mut q_player: Query<(&PlayerLife, &mut Sprite), With<Player>>,
+) {
+    let Ok((player_life, mut sprite)) = q_player.get_single_mut() else {
+        return;
+    };
+    let tint = damage_intensity(player_life, time.elapsed());
+    let color = Color::srgba(1., 1. - tint * 0.7, 1. - tint * 0.7, sprite.color.alpha());
+    if sprite.color != color {
+        sprite.color = color;
+    }
+}
+
+/// Play a heartbeat loop while low on life, and stop it once healed.
+fn update_heartbeat_sound(
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<FeedbackSounds>,
+    mut heartbeat: ResMut<HeartbeatLoop>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+    q_player: Query<&PlayerLife, With<Player>>,
+) {
+    let is_low = q_player
+        .get_single()
+        .is_ok_and(|player_life| player_life.is_low());
+    if is_low && heartbeat.0.is_none() {
+        let handle = audio
+            .play(sounds.heartbeat.clone())
+            .looped()
+            .with_playback_rate(0.5)
+            .handle();
+        heartbeat.0 = Some(handle);
+    } else if !is_low {
+        heartbeat.stop(&mut instances);
+    }
+}
+
+fn stop_heartbeat_sound(
+    mut heartbeat: ResMut<HeartbeatLoop>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    heartbeat.stop(&mut instances);
+}
+
+/// Fade the grey overlay in while low on life, and out once healed, keeping
+/// it over the camera view.
+fn update_wash_out(
+    time: Res<Time>,
+    q_player: Query<&PlayerLife, With<Player>>,
+    q_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut q_overlay: Query<
+        (&mut Transform, &mut Sprite, &mut Visibility),
+        (With<WashOutOverlay>, Without<MainCamera>),
+    >,
+) {
+    let Ok((mut transform, mut sprite, mut visibility)) = q_overlay.get_single_mut() else {
+        return;
+    };
+    let is_low = q_player
+        .get_single()
+        .is_ok_and(|player_life| player_life.is_low());
+    let target = if is_low { WASH_OUT_ALPHA } else { 0. };
+    let alpha = sprite.color.alpha();
+    let step = WASH_OUT_SPEED * time.delta_seconds();
+    let alpha = alpha + (target - alpha).clamp(-step, step);
+    sprite.color.set_alpha(alpha);
+
+    let new_visibility = if alpha > 0. {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+
+    if let Ok((camera_transform, projection)) = q_camera.get_single() {
+        transform.translation = camera_transform.translation.xy().extend(OVERLAY_DEPTH);
+        let view_size = projection.area.size();
+        if sprite.custom_size != Some(view_size) {
+            sprite.custom_size = Some(view_size);
+        }
+    }
+}
+
+/// Flash the screen border red when taking damage, and show a beating red
+/// vignette while low on life.
+fn ui_damage_feedback(
+    time: Res<Time>,
+    mut q_canvas: Query<&mut Canvas>,
+    q_player: Query<&PlayerLife, With<Player>>,
+    layout: Res<UiLayout>,
+) {
+    let Ok(player_life) = q_player.get_single() else {
+        return;
+    };
+    let now = time.elapsed();
+
+    let mut canvas = q_canvas.single_mut();
+    let mut ctx = canvas.render_context();
+    let screen_rect = layout.screen_rect();
+
+    if player_life.is_low() {
+        let alpha = 0.15 + heartbeat(now) * 0.2;
+        for band in 0..VIGNETTE_BANDS {
+            let fade = 1. - band as f32 / VIGNETTE_BANDS as f32;
+            let color = Color::srgba(0.6, 0., 0., alpha * fade);
+            let outer = screen_rect.inflate(-(band as f32) * VIGNETTE_BAND_THICKNESS);
+            fill_border(&mut ctx, outer, VIGNETTE_BAND_THICKNESS, color);
+        }
+    }
+
+    let intensity = damage_intensity(player_life, now);
+    if intensity > 0. {
+        let color = Color::srgba(1., 0., 0., intensity * 0.6);
+        fill_border(&mut ctx, screen_rect, FLASH_THICKNESS, color);
+    }
+}
+
+/// Fill the inside border of a rectangle.
+fn fill_border(ctx: &mut bevy_keith::RenderContext, rect: Rect, thickness: f32, color: Color) {
+    let brush = ctx.solid_brush(color);
+    let inner = rect.inflate(-thickness);
+    ctx.fill(
+        Rect::new(rect.min.x, rect.min.y, rect.max.x, inner.min.y),
+        &brush,
+    );
+    ctx.fill(
+        Rect::new(rect.min.x, inner.max.y, rect.max.x, rect.max.y),
+        &brush,
+    );
+    ctx.fill(
+        Rect::new(rect.min.x, inner.min.y, inner.min.x, inner.max.y),
+        &brush,
+    );
+    ctx.fill(
+        Rect::new(inner.max.x, inner.min.y, rect.max.x, inner.max.y),
+        &brush,
+    );
+}
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::TileTextureIndex;
+use bevy_keith::Canvas;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    checkpoint::Lives,
+    fade::{FadeAction, FadeEvent},
+    layout::UiLayout,
+    level::LevelRegistry,
+    loading::LoadingAssets,
+    locale::{tr, Localizer},
+    menu::{draw_menu_cursor, MenuAction, MenuHighlight, MenuItem, MenuLayout, MenuNavigation},
+    score::{Score, ScoreEvent},
+    sound::DuckMusicEvent,
+    trigger::{TriggerEnter, TriggerKind},
+    AppState, LevelMeta, TileAnimation, UiRes,
+};
+
+const GAME_OVER_OPTIONS: [&str; 3] = [
+    "game-over-retry",
+    "game-over-restart",
+    "game-over-main-menu",
+];
+
+const GAME_OVER_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-260., 220.), 40., 520.);
+
+#[derive(Default, Resource)]
+struct GameOverMenu {
+    pub selected_index: usize,
+}
+
+#[derive(Default)]
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Color::BLACK))
+            .init_resource::<UiRes>()
+            .init_resource::<GameOverMenu>()
+            .init_state::<AppState>()
+            .add_systems(Startup, load_ui_assets)
+            // Debug
+            .add_systems(First, toggle_debug)
+            // In-game
+            .add_systems(
+                Update,
+                (animate_sprites, animate_tiles, check_victory).run_if(in_state(AppState::InGame)),
+            )
+            // Game over
+            .add_systems(OnEnter(AppState::GameOver), setup_game_over)
+            .add_systems(
+                PreUpdate,
+                game_over_inputs.run_if(in_state(AppState::GameOver)),
+            )
+            .add_systems(Update, game_over_ui.run_if(in_state(AppState::GameOver)));
+    }
+}
+
+pub fn toggle_debug(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut debug_ctx: ResMut<DebugRenderContext>,
+) {
+    if keyboard.just_pressed(KeyCode::F1) {
+        debug_ctx.enabled = !debug_ctx.enabled;
+    }
+}
+
+/// Load the font and images shared by the UI of all screens.
+fn load_ui_assets(
+    asset_server: Res<AssetServer>,
+    mut ui_res: ResMut<UiRes>,
+    mut loading: ResMut<LoadingAssets>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    ui_res.font = asset_server.load("fonts/PressStart2P-Regular.ttf");
+    loading.track(ui_res.font.clone());
+
+    ui_res.title_image = asset_server.load("title.png");
+    loading.track(ui_res.title_image.clone());
+
+    ui_res.cursor_image = asset_server.load("player1.png");
+    loading.track(ui_res.cursor_image.clone());
+    let player_layout =
+        TextureAtlasLayout::from_grid(UVec2::splat(15), 4, 1, Some(UVec2::ONE), None);
+    let player_atlas_layout = texture_atlas_layouts.add(player_layout);
+    ui_res.cursor_atlas_layout = player_atlas_layout;
+}
+
+fn animate_sprites(time: Res<Time>, mut query: Query<(&mut TileAnimation, &mut TextureAtlas)>) {
+    for (mut anim, mut atlas) in &mut query {
+        let idx = anim.tick(time.delta().as_millis() as u32) as usize;
+        if idx != atlas.index {
+            atlas.index = idx;
+        }
+    }
+}
+
+fn animate_tiles(time: Res<Time>, mut query: Query<(&mut TileAnimation, &mut TileTextureIndex)>) {
+    for (mut anim, mut tex_index) in &mut query {
+        let idx = anim.tick(time.delta().as_millis() as u32);
+        if idx != tex_index.0 {
+            tex_index.0 = idx;
+        }
+    }
+}
+
+fn check_victory(
+    mut ev_enter: EventReader<TriggerEnter>,
+    mut ev_fade: EventWriter<FadeEvent>,
+    mut ev_score: EventWriter<ScoreEvent>,
+    mut ev_duck: EventWriter<DuckMusicEvent>,
+) {
+    for ev in ev_enter.read() {
+        if ev.kind == TriggerKind::LevelEnd {
+            info!("LevelEnd!");
+            ev_score.send(ScoreEvent::LevelComplete);
+            ev_duck.send(DuckMusicEvent::LEVEL_END);
+            ev_fade.send(FadeEvent(FadeAction::SetState(AppState::Victory)));
+        }
+    }
+}
+
+fn setup_game_over(mut game_over_menu: ResMut<GameOverMenu>) {
+    game_over_menu.selected_index = 0;
+}
+
+fn game_over_inputs(
+    mut navigation: MenuNavigation,
+    mut game_over_menu: ResMut<GameOverMenu>,
+    mut lives: ResMut<Lives>,
+    level_registry: Res<LevelRegistry>,
+    mut ev_fade: EventWriter<FadeEvent>,
+) {
+    let action = navigation.navigate(
+        &mut game_over_menu.selected_index,
+        GAME_OVER_OPTIONS.len(),
+        &GAME_OVER_LAYOUT,
+    );
+    if action == Some(MenuAction::Confirm) {
+        match game_over_menu.selected_index {
+            0 => {
+                lives.reset();
+                ev_fade.send(FadeEvent(FadeAction::Respawn));
+            }
+            1 => {
+                lives.reset();
+                ev_fade.send(FadeEvent(FadeAction::LoadLevel(level_registry.current)));
+            }
+            2 => {
+                ev_fade.send(FadeEvent(FadeAction::SetState(AppState::MainMenu)));
+            }
+            _ => (),
+        }
+    }
+}
+
+fn game_over_ui(
+    ui_res: Res<UiRes>,
+    mut q_canvas: Query<&mut Canvas>,
+    level_meta: Option<Res<LevelMeta>>,
+    score: Res<Score>,
+    game_over_menu: Res<GameOverMenu>,
+    highlight: MenuHighlight,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    let top_left = layout.top_left();
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.7));
+    ctx.fill(
+        Rect::new(
+            top_left.x,
+            top_left.y - 10.,
+            top_left.x + 100.,
+            top_left.y + 35.,
+        ),
+        &brush,
+    );
+
+    // Background
+    // let brush = ctx.solid_brush(Srgba::hex("3b69ba").unwrap().into());
+    // let screen_rect = Rect::new(-480., -360., 480., 360.);
+    // ctx.fill(screen_rect, &brush);
+
+    // Level name
+    if let Some(level_meta) = &level_meta {
+        let txt = ctx
+            .new_layout(loc.text(&level_meta.name))
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::WHITE)
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(300., 20.))
+            .build();
+        ctx.draw_text(txt, Vec2::new(0., 40.));
+    }
+
+    // Game over
+    let txt = ctx
+        .new_layout(tr!(loc, "game-over-title"))
+        .font(ui_res.font.clone())
+        .font_size(32.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(300., 20.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., 90.));
+
+    // Score
+    let txt = ctx
+        .new_layout(tr!(
+            loc,
+            "game-over-score",
+            points = score.points,
+            high_score = score.high_score
+        ))
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(300., 40.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., 150.));
+
+    // Options
+    for (index, option) in GAME_OVER_OPTIONS.iter().enumerate() {
+        let item = MenuItem {
+            text: tr!(loc, option),
+            position: Vec2::new(0., GAME_OVER_LAYOUT.item_y(index)),
+            bounds: Vec2::new(500., 20.),
+            font_size: 20.,
+            color: Color::WHITE,
+        };
+        let is_focused = index == game_over_menu.selected_index;
+        highlight.draw_item(&mut ctx, &ui_res, item, is_focused);
+    }
+
+    let cursor = GAME_OVER_LAYOUT.cursor_position(game_over_menu.selected_index);
+    draw_menu_cursor(&mut ctx, &ui_res, cursor);
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    level::LevelRegistry, settings::Settings, storage, timer::LevelTimer, AppState, LevelEntity,
+    Player, TileAnimation, UiRes,
+};
+
+/// Storage key of the best runs, kept apart from the save as they're much
+/// larger.
+const GHOSTS_FILE: &str = "ghosts.ron";
+
+/// Player positions recorded each fixed tick.
+#[derive(Debug, Default, Resource)]
+pub struct GhostRuns {
+    /// Positions recorded during the current run.
+    pub current: Vec<Vec3>,
+    /// Best run of each level, by level asset path, saved between sessions.
+    pub best: BTreeMap<String, Vec<Vec3>>,
+}
+
+/// Translucent sprite replaying the best run of the current level.
+#[derive(Debug, Default, Component)]
+struct Ghost {
+    /// Index of the next recorded position to replay.
+    frame: usize,
+}
+
+#[derive(Default)]
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostRuns>()
+            .add_systems(Startup, load_best_runs)
+            .add_systems(Update, start_run.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                FixedUpdate,
+                (record_run, replay_ghost).run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnEnter(AppState::Victory), save_best_run);
+    }
+}
+
+/// Load the best runs of previous sessions, if any.
+fn load_best_runs(mut runs: ResMut<GhostRuns>) {
+    match storage::load(GHOSTS_FILE) {
+        Ok(Some(best)) => runs.best = best,
+        Ok(None) => (),
+        Err(err) => warn!("Ignoring saved ghost runs '{}': {}", GHOSTS_FILE, err),
+    }
+}
+
+/// Start a new recording when the player spawns into a level, and spawn the
+/// ghost of the best run of that level, if any.
+fn start_run(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    ui_res: Res<UiRes>,
+    registry: Res<LevelRegistry>,
+    mut runs: ResMut<GhostRuns>,
+    q_player: Query<(), Added<Player>>,
+    q_ghosts: Query<Entity, With<Ghost>>,
+) {
+    if q_player.is_empty() {
+        return;
+    }
+
+    runs.current.clear();
+    for entity in &q_ghosts {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !settings.show_ghost {
+        return;
+    }
+    let Some(start) = registry
+        .current_path()
+        .and_then(|path| runs.best.get(path))
+        .and_then(|run| run.first())
+    else {
+        return;
+    };
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(*start),
+            texture: ui_res.cursor_image.clone(),
+            sprite: Sprite {
+                color: Color::srgba(1., 1., 1., 0.4),
+                ..default()
+            },
+            ..default()
+        },
+        TextureAtlas {
+            layout: ui_res.cursor_atlas_layout.clone(),
+            index: 0,
+        },
+        TileAnimation::uniform(0, 2, 100),
+        Ghost::default(),
+        LevelEntity,
+        Name::new("Ghost"),
+    ));
+}
+
+fn record_run(
+    level_timer: Res<LevelTimer>,
+    mut runs: ResMut<GhostRuns>,
+    q_player: Query<&Transform, With<Player>>,
+) {
+    if !level_timer.is_running {
+        return;
+    }
+    let Ok(transform) = q_player.get_single() else {
+        return;
+    };
+    // Draw the ghost just behind the player
+    let position = transform.translation - Vec3::Z * 0.1;
+    runs.current.push(position);
+}
+
+fn replay_ghost(
+    mut commands: Commands,
+    runs: Res<GhostRuns>,
+    registry: Res<LevelRegistry>,
+    mut q_ghosts: Query<(Entity, &mut Ghost, &mut Transform)>,
+) {
+    let Some(run) = registry.current_path().and_then(|path| runs.best.get(path)) else {
+        return;
+    };
+    for (entity, mut ghost, mut transform) in &mut q_ghosts {
+        let Some(position) = run.get(ghost.frame) else {
+            // The ghost reached the level end
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        transform.translation = *position;
+        ghost.frame += 1;
+    }
+}
+
+/// Keep the run just completed if it's faster than the best one of the level,
+/// and save it for the next sessions.
+fn save_best_run(registry: Res<LevelRegistry>, mut runs: ResMut<GhostRuns>) {
+    let Some(path) = registry.current_path() else {
+        return;
+    };
+    let is_best = match runs.best.get(path) {
+        Some(best) => runs.current.len() < best.len(),
+        None => true,
+    };
+    if !is_best || runs.current.is_empty() {
+        return;
+    }
+    debug!(
+        "New best ghost run for '{}' ({} ticks)",
+        path,
+        runs.current.len()
+    );
+    let run = std::mem::take(&mut runs.current);
+    runs.best.insert(path.to_string(), run);
+    if let Err(err) = storage::save(GHOSTS_FILE, &runs.best) {
+        warn!("Failed to save ghost runs to '{}': {}", GHOSTS_FILE, err);
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{AppState, Crusher, CrusherPhase, Player, PlayerLife, Spin};
+
+/// Penetration depth of the player into a crusher above which the player is
+/// considered caught between the crusher and a wall, in pixels. A free player
+/// is simply pushed away by the crusher, so never overlaps it that much.
+const CRUSH_DEPTH: f32 = 2.;
+
+#[derive(Default)]
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            ((move_crushers, crush_player).chain(), spin).run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Run the slam cycle of crushers.
+fn move_crushers(time: Res<Time>, mut q_crushers: Query<(&mut Crusher, &mut Transform)>) {
+    let now = time.elapsed();
+    let dt = time.delta_seconds();
+    for (mut crusher, mut transform) in &mut q_crushers {
+        if crusher.points.len() < 2 {
+            continue;
+        }
+        let last = crusher.points.len() - 1;
+        let elapsed = now.saturating_sub(crusher.phase_start_time);
+        let (speed, is_slamming) = match crusher.phase {
+            CrusherPhase::Waiting => {
+                if elapsed >= crusher.wait {
+                    crusher.phase = CrusherPhase::Slamming;
+                    crusher.phase_start_time = now;
+                    crusher.target = 1;
+                }
+                continue;
+            }
+            CrusherPhase::Holding => {
+                if elapsed >= crusher.hold {
+                    crusher.phase = CrusherPhase::Rising;
+                    crusher.phase_start_time = now;
+                    crusher.target = last - 1;
+                }
+                continue;
+            }
+            CrusherPhase::Slamming => (crusher.slam_speed, true),
+            CrusherPhase::Rising => (crusher.rise_speed, false),
+        };
+
+        let mut pos = transform.translation.xy();
+        let mut step = speed * dt;
+        // Pass through as many points as the step covers, stopping at either end
+        loop {
+            let target = crusher.points[crusher.target];
+            let dist = pos.distance(target);
+            if dist > step {
+                pos += (target - pos) / dist * step;
+                break;
+            }
+            pos = target;
+            step -= dist;
+            if is_slamming && crusher.target < last {
+                crusher.target += 1;
+            } else if !is_slamming && crusher.target > 0 {
+                crusher.target -= 1;
+            } else {
+                crusher.phase = if is_slamming {
+                    CrusherPhase::Holding
+                } else {
+                    CrusherPhase::Waiting
+                };
+                crusher.phase_start_time = now;
+                break;
+            }
+        }
+        transform.translation = pos.extend(transform.translation.z);
+    }
+}
+
+/// Damage or kill the player when caught between a crusher and a wall.
+fn crush_player(
+    time: Res<Time>,
+    physics: Res<RapierContext>,
+    mut q_player: Query<(Entity, &mut PlayerLife), With<Player>>,
+    q_crushers: Query<(Entity, &Crusher)>,
+) {
+    let Ok((player_entity, mut player_life)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    // Give the player some time to escape between two hits
+    if player_life.damage_impulse_factor(time.elapsed()).is_some() {
+        return;
+    }
+
+    for (crusher_entity, crusher) in &q_crushers {
+        let Some(contact) = physics.contact_pair(player_entity, crusher_entity) else {
+            continue;
+        };
+        let is_crushed = contact
+            .manifolds()
+            .any(|m| m.points().any(|p| p.dist() < -CRUSH_DEPTH));
+        if !is_crushed {
+            continue;
+        }
+
+        debug!("Player crushed by {:?}", crusher_entity);
+        let amount = crusher.damage.unwrap_or(player_life.life);
+        player_life.damage(time.elapsed(), amount, Vec2::ZERO);
+        break;
+    }
+}
+
+fn spin(time: Res<Time>, mut q_spinners: Query<(&Spin, &mut Transform)>) {
+    for (spin, mut transform) in &mut q_spinners {
+        transform.rotate_z(spin.0 * time.delta_seconds());
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_keith::{Canvas, ShapeExt};
+
+use crate::{
+    input::{key_name, Action, InputMap},
+    interact::{find_target, InteractionTarget},
+    layout::UiLayout,
+    locale::{tr, Localizer},
+    replay::PlayerActions,
+    trigger::{TriggerEnter, TriggerKind},
+    AppState, HintZone, UiRes,
+};
+
+/// Duration of the fade in and out of the hint box.
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+/// Hint currently displayed.
+#[derive(Debug)]
+struct ActiveHint {
+    text: String,
+    start_time: Duration,
+    /// Time at which the hint starts fading out, if timed.
+    end_time: Option<Duration>,
+}
+
+/// Tutorial hints of the current run.
+#[derive(Debug, Default, Resource)]
+struct Hints {
+    /// Text of the hints already shown, each shown at most once per run.
+    shown: HashSet<String>,
+    current: Option<ActiveHint>,
+}
+
+#[derive(Default)]
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hints>()
+            .add_systems(OnEnter(AppState::MainMenu), reset_hints)
+            .add_systems(
+                Update,
+                (
+                    show_hints,
+                    dismiss_hints.after(find_target),
+                    ui_hint.after(crate::hud::main_ui),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Start a new run when going back to the main menu.
+fn reset_hints(mut hints: ResMut<Hints>) {
+    *hints = default();
+}
+
+/// Show the hint of any zone the player is inside, unless already shown
+/// during this run.
+fn show_hints(
+    time: Res<Time>,
+    q_zones: Query<&HintZone>,
+    mut ev_enter: EventReader<TriggerEnter>,
+    mut hints: ResMut<Hints>,
+    loc: Localizer,
+) {
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Hint {
+            continue;
+        }
+        let Ok(zone) = q_zones.get(ev.trigger) else {
+            continue;
+        };
+        if !hints.shown.insert(zone.text.clone()) {
+            continue;
+        }
+        debug!("Showing hint '{}'", zone.text);
+        let now = time.elapsed();
+        hints.current = Some(ActiveHint {
+            text: loc.text(&zone.text),
+            start_time: now,
+            end_time: zone.duration.map(|duration| now + duration),
+        });
+    }
+}
+
+/// Hide the current hint once timed out, or when dismissed with the interact
+/// action while there's nothing to interact with.
+fn dismiss_hints(
+    time: Res<Time>,
+    actions: Res<PlayerActions>,
+    target: Res<InteractionTarget>,
+    mut hints: ResMut<Hints>,
+) {
+    let now = time.elapsed();
+    let Some(hint) = hints.current.as_mut() else {
+        return;
+    };
+    if let Some(end_time) = hint.end_time {
+        if now >= end_time + FADE_DURATION {
+            hints.current = None;
+        }
+    } else if target.entity.is_none() && actions.interact {
+        hint.end_time = Some(now);
+    }
+}
+
+fn ui_hint(
+    time: Res<Time>,
+    hints: Res<Hints>,
+    input_map: Res<InputMap>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    layout: Res<UiLayout>,
+    loc: Localizer,
+) {
+    let Some(hint) = &hints.current else {
+        return;
+    };
+
+    let now = time.elapsed();
+    let fade_in = now
+        .saturating_sub(hint.start_time)
+        .div_duration_f32(FADE_DURATION);
+    let fade_out = hint.end_time.map_or(1., |end_time| {
+        1. - now.saturating_sub(end_time).div_duration_f32(FADE_DURATION)
+    });
+    let alpha = fade_in.min(fade_out).clamp(0., 1.);
+
+    let mut canvas = q_canvas.single_mut();
+    let mut ctx = canvas.render_context();
+
+    let center = layout.top_center() + Vec2::new(0., 130.);
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.85 * alpha));
+    let border_brush = ctx.solid_brush(Color::srgba(1., 0.85, 0., alpha));
+    ctx.fill(Rect::from_center_size(center, Vec2::new(520., 70.)), &brush)
+        .border(&border_brush, 2.);
+
+    let txt = ctx
+        .new_layout(hint.text.clone())
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::srgba(1., 1., 1., alpha))
+        .alignment(JustifyText::Center)
+        .bounds(Vec2::new(480., 40.))
+        .build();
+    ctx.draw_text(txt, center + Vec2::new(0., -8.));
+
+    if hint.end_time.is_none() {
+        let txt = ctx
+            .new_layout(tr!(
+                loc,
+                "hint-dismiss",
+                key = key_name(input_map.binding(Action::Interact).key)
+            ))
+            .font(ui_res.font.clone())
+            .font_size(12.)
+            .color(Color::srgba(0.7, 0.7, 0.7, alpha))
+            .alignment(JustifyText::Right)
+            .bounds(Vec2::new(480., 14.))
+            .build();
+        ctx.draw_text(txt, center + Vec2::new(0., 22.));
+    }
+}
@@ -0,0 +1,565 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_keith::{Canvas, RenderContext, ShapeExt};
+
+use crate::{
+    checkpoint::Lives,
+    dialogue::Dialogue,
+    feedback::heartbeat,
+    layout::UiLayout,
+    locale::{tr, Localizer},
+    pickup::Collectibles,
+    score::Score,
+    settings::{HealthStyle, Settings},
+    stats::RunStats,
+    timer::{format_time, LevelTimer},
+    ActiveEffects, AppState, Boss, Dying, EnemyLife, Epoch, LevelMeta, PlayerBreath, PlayerLife,
+    PlayerWeapon, UiRes,
+};
+
+/// Duration of the pop animation of a HUD counter when its value changes.
+const POP_DURATION: Duration = Duration::from_millis(250);
+
+/// Maximum number of epoch pips displayed.
+const MAX_EPOCH_PIPS: i32 = 10;
+
+/// Amount of life in each segment of the segmented life display.
+const LIFE_PER_SEGMENT: f32 = 4.;
+
+/// Width of each character of the HUD text drawn with a fixed advance.
+const MONOSPACE_ADVANCE: f32 = 10.;
+
+/// Value displayed by a HUD counter, and when it last changed.
+#[derive(Debug, Default)]
+struct Counter {
+    value: i32,
+    change_time: Option<Duration>,
+}
+
+impl Counter {
+    /// Update the value, starting the pop animation if it changed.
+    fn update(&mut self, value: i32, time: Duration) {
+        if value != self.value {
+            self.value = value;
+            self.change_time = Some(time);
+        }
+    }
+
+    /// Set the value without animating it.
+    fn reset(&mut self, value: i32) {
+        self.value = value;
+        self.change_time = None;
+    }
+
+    /// Scale of the counter, briefly larger after its value changed.
+    fn scale(&self, time: Duration) -> f32 {
+        let Some(change_time) = self.change_time else {
+            return 1.;
+        };
+        let delta = time.saturating_sub(change_time);
+        if delta >= POP_DURATION {
+            return 1.;
+        }
+        let ratio = delta.div_duration_f32(POP_DURATION);
+        1. + (ratio * std::f32::consts::PI).sin() * 0.5
+    }
+}
+
+#[derive(Default, Resource)]
+struct HudCounters {
+    coins: Counter,
+    epoch: Counter,
+    deaths: Counter,
+}
+
+#[derive(Default)]
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudCounters>()
+            .add_systems(OnEnter(AppState::InGame), reset_counters)
+            .add_systems(
+                Update,
+                (main_ui, ui_counters.after(main_ui)).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Sync the counters with the game when entering it, so they don't pop for
+/// values which changed outside of it.
+fn reset_counters(
+    collectibles: Res<Collectibles>,
+    stats: Res<RunStats>,
+    q_epoch: Query<&Epoch>,
+    mut counters: ResMut<HudCounters>,
+) {
+    counters.coins.reset(collectibles.level_coins as i32);
+    counters.deaths.reset(stats.deaths as i32);
+    if let Ok(epoch) = q_epoch.get_single() {
+        counters.epoch.reset(epoch.cur);
+    }
+}
+
+/// Draw the coins collected and the current epoch next to the life bar, and
+/// the deaths of the run in the top right corner, popping when they change.
+fn ui_counters(
+    time: Res<Time>,
+    collectibles: Res<Collectibles>,
+    stats: Res<RunStats>,
+    q_epoch: Query<&Epoch>,
+    mut counters: ResMut<HudCounters>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    layout: Res<UiLayout>,
+) {
+    let now = time.elapsed();
+    let origin = layout.top_left() + Vec2::new(180., 30.);
+
+    let mut canvas = q_canvas.single_mut();
+    let mut ctx = canvas.render_context();
+
+    // Coins
+    counters.coins.update(collectibles.level_coins as i32, now);
+    let scale = counters.coins.scale(now);
+    let coin_color = Color::srgb(1., 0.85, 0.);
+    let brush = ctx.solid_brush(coin_color);
+    let border_brush = ctx.solid_brush(Color::WHITE);
+    ctx.fill(
+        Rect::from_center_size(origin, Vec2::splat(12. * scale)),
+        &brush,
+    )
+    .border(&border_brush, 1.);
+    let txt = ctx
+        .new_layout(format!("x{}", counters.coins.value))
+        .font(ui_res.font.clone())
+        .font_size(16. * scale)
+        .color(coin_color)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(60., 20.))
+        .build();
+    ctx.draw_text(txt, origin + Vec2::new(40., 0.));
+
+    // Deaths, once the player died at least once, with a tombstone icon
+    counters.deaths.update(stats.deaths as i32, now);
+    if counters.deaths.value > 0 {
+        let scale = counters.deaths.scale(now);
+        let center = layout.top_right() + Vec2::new(-120., 30.);
+        let stone_brush = ctx.solid_brush(Color::srgb(0.6, 0.6, 0.6));
+        let cross_brush = ctx.solid_brush(Color::BLACK);
+        ctx.fill(
+            Rect::from_center_size(center, Vec2::new(10., 12.) * scale),
+            &stone_brush,
+        );
+        ctx.fill(
+            Rect::from_center_size(center, Vec2::new(2., 8.) * scale),
+            &cross_brush,
+        );
+        ctx.fill(
+            Rect::from_center_size(center - Vec2::Y * scale, Vec2::new(6., 2.) * scale),
+            &cross_brush,
+        );
+        let txt = ctx
+            .new_layout(format!("x{}", counters.deaths.value))
+            .font(ui_res.font.clone())
+            .font_size(16. * scale)
+            .color(Color::WHITE)
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(60., 20.))
+            .build();
+        ctx.draw_text(txt, center + Vec2::new(40., 0.));
+    }
+
+    // Epochs, with the current one highlighted
+    let Ok(epoch) = q_epoch.get_single() else {
+        return;
+    };
+    counters.epoch.update(epoch.cur, now);
+    let scale = counters.epoch.scale(now);
+    let pip_color = if epoch.locked {
+        Color::srgb(0.5, 0.5, 0.5)
+    } else {
+        Color::srgb(0.8, 0.6, 1.)
+    };
+    let empty_brush = ctx.solid_brush(Color::BLACK);
+    let pip_brush = ctx.solid_brush(pip_color);
+    let last = epoch.max.min(epoch.min + MAX_EPOCH_PIPS - 1);
+    for (index, value) in (epoch.min..=last).enumerate() {
+        let center = origin + Vec2::new(90. + index as f32 * 14., 0.);
+        if value == epoch.cur {
+            let r = Rect::from_center_size(center, Vec2::splat(10. * scale));
+            ctx.fill(r, &pip_brush).border(&pip_brush, 1.);
+        } else {
+            let r = Rect::from_center_size(center, Vec2::splat(8.));
+            ctx.fill(r, &empty_brush).border(&pip_brush, 1.);
+        }
+    }
+}
+
+/// Draw a line of 16px HUD text centered on a position, with each character
+/// in a cell of the same width so changing digits don't make the text jitter.
+fn draw_monospace_text(
+    ctx: &mut RenderContext,
+    ui_res: &UiRes,
+    text: &str,
+    color: Color,
+    center: Vec2,
+) {
+    let count = text.chars().count();
+    let start_x = center.x - (count as f32 - 1.) * MONOSPACE_ADVANCE / 2.;
+    for (index, c) in text.chars().enumerate() {
+        if c == ' ' {
+            continue;
+        }
+        let txt = ctx
+            .new_layout(c.to_string())
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(color)
+            .alignment(JustifyText::Center)
+            .bounds(Vec2::new(MONOSPACE_ADVANCE, 20.))
+            .build();
+        let x = start_x + index as f32 * MONOSPACE_ADVANCE;
+        ctx.draw_text(txt, Vec2::new(x, center.y));
+    }
+}
+
+/// Intensity in \[0:1\] of the beating of the life display while low on life.
+fn low_life_warning(player_life: &PlayerLife, time: Duration) -> f32 {
+    if player_life.is_low() {
+        heartbeat(time)
+    } else {
+        0.
+    }
+}
+
+/// Border color of the life display, green when healed and red when low on
+/// life.
+fn life_border_color(pulse: f32, warning: f32) -> Color {
+    Color::srgb(1. - pulse, 1. - warning, 1. - pulse.max(warning))
+}
+
+/// Draw the player life as a continuous bar below a top-left position, which
+/// pulses when healed.
+fn draw_life_bar(ctx: &mut RenderContext, player_life: &PlayerLife, time: Duration, origin: Vec2) {
+    let mut r = Rect::new(
+        origin.x + 10.,
+        origin.y + 40.,
+        origin.x + 160.,
+        origin.y + 20.,
+    );
+
+    // Briefly pulse the life bar after healing, and beat while low on life
+    let pulse = player_life.heal_pulse(time).unwrap_or(0.);
+    let warning = low_life_warning(player_life, time);
+    r = r.inflate(pulse.max(warning) * 3.);
+
+    let brush = ctx.solid_brush(Color::BLACK);
+    let border_brush = ctx.solid_brush(life_border_color(pulse, warning));
+    ctx.fill(r, &brush).border(&border_brush, 2.);
+
+    let brush = ctx.solid_brush(Color::srgb(1., 0., 0.));
+    let mut r = r.inflate(-3.);
+    r.max.x = r.min.x + (r.width() / player_life.max_life * player_life.life);
+    ctx.fill(r, &brush);
+}
+
+/// Draw the player life as a row of segments below a top-left position, which
+/// can be half full. The segments shake and flash when damaged, and pulse when
+/// healed or low.
+fn draw_life_segments(
+    ctx: &mut RenderContext,
+    player_life: &PlayerLife,
+    time: Duration,
+    origin: Vec2,
+) {
+    let count = (player_life.max_life / LIFE_PER_SEGMENT).ceil() as usize;
+    // Round up so the display never looks empty while still alive
+    let halves = (player_life.life / (LIFE_PER_SEGMENT / 2.)).ceil() as usize;
+
+    let pulse = player_life.heal_pulse(time).unwrap_or(0.);
+    let warning = low_life_warning(player_life, time);
+    let shake = player_life
+        .damage_impulse_factor(time)
+        .map(|ratio| 1. - ratio)
+        .unwrap_or(0.);
+    let offset_x = (time.as_secs_f32() * 60.).sin() * shake * 3.;
+
+    let back_brush = ctx.solid_brush(Color::BLACK);
+    let border_brush = ctx.solid_brush(life_border_color(pulse, warning));
+    let fill_brush = ctx.solid_brush(Color::srgb(1., shake * 0.8, shake * 0.8));
+    for index in 0..count {
+        let x = origin.x + 10. + index as f32 * 26. + offset_x;
+        let r =
+            Rect::new(x, origin.y + 20., x + 20., origin.y + 40.).inflate(pulse.max(warning) * 2.);
+        ctx.fill(r, &back_brush).border(&border_brush, 2.);
+
+        let filled_halves = halves.saturating_sub(index * 2).min(2);
+        if filled_halves > 0 {
+            let mut r = r.inflate(-3.);
+            r.max.x = r.min.x + r.width() * filled_halves as f32 / 2.;
+            ctx.fill(r, &fill_brush);
+        }
+    }
+}
+
+pub fn main_ui(
+    time: Res<Time>,
+    mut q_canvas: Query<&mut Canvas>,
+    q_player: Query<&PlayerLife>,
+    q_weapon: Query<&PlayerWeapon>,
+    q_breath: Query<&PlayerBreath>,
+    q_bosses: Query<(&Boss, &EnemyLife), Without<Dying>>,
+    collectibles: Res<Collectibles>,
+    lives: Res<Lives>,
+    score: Res<Score>,
+    level_timer: Res<LevelTimer>,
+    level_meta: Option<Res<LevelMeta>>,
+    q_effects: Query<&ActiveEffects>,
+    dialogue: Res<Dialogue>,
+    //q_temp: Query<&PlayerController>,
+    (ui_res, settings, loc, layout): (Res<UiRes>, Res<Settings>, Localizer, Res<UiLayout>),
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    let top_left = layout.top_left();
+    let top_center = layout.top_center();
+    let top_right = layout.top_right();
+    let bottom_center = layout.bottom_center();
+
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.7));
+    ctx.fill(
+        Rect::new(
+            top_left.x,
+            top_left.y - 10.,
+            top_left.x + 100.,
+            top_left.y + 35.,
+        ),
+        &brush,
+    );
+
+    // // TEMP
+    // if let Ok(pc) = q_temp.get_single() {
+    //     let txt = ctx
+    //         //.new_layout("Time: 017")
+    //         .new_layout(format!(
+    //             "grounded={} climbing={}",
+    //             pc.is_grounded, pc.is_climbing
+    //         ))
+    //         .font(ui_res.font.clone())
+    //         .font_size(16.)
+    //         .color(Color::WHITE)
+    //         .alignment(JustifyText::Left)
+    //         .bounds(Vec2::new(100., 20.))
+    //         .build();
+    //     ctx.draw_text(txt, Vec2::new(-430., -340.));
+    // }
+
+    if let Ok(player_life) = q_player.get_single() {
+        match settings.health_style {
+            HealthStyle::Bar => draw_life_bar(&mut ctx, player_life, time.elapsed(), top_left),
+            HealthStyle::Segments => {
+                draw_life_segments(&mut ctx, player_life, time.elapsed(), top_left)
+            }
+        }
+    }
+
+    // Lives
+    let brush = ctx.solid_brush(Color::srgb(0.3, 0.6, 1.));
+    let border_brush = ctx.solid_brush(Color::WHITE);
+    for index in 0..lives.count.min(10) {
+        let x = top_left.x + 10. + index as f32 * 14.;
+        let r = Rect::new(x, top_left.y + 4., x + 10., top_left.y + 14.);
+        ctx.fill(r, &brush).border(&border_brush, 1.);
+    }
+
+    // Boss life
+    for (boss, enemy_life) in &q_bosses {
+        if !boss.is_active {
+            continue;
+        }
+        let r = Rect::new(
+            top_center.x - 200.,
+            top_center.y + 20.,
+            top_center.x + 200.,
+            top_center.y + 32.,
+        );
+
+        let brush = ctx.solid_brush(Color::BLACK);
+        let border_brush = ctx.solid_brush(Color::WHITE);
+        ctx.fill(r, &brush).border(&border_brush, 2.);
+
+        let brush = ctx.solid_brush(Color::srgb(0.6, 0., 0.8));
+        let mut r = r.inflate(-3.);
+        r.max.x = r.min.x + (r.width() / enemy_life.max_life * enemy_life.life);
+        ctx.fill(r, &brush);
+    }
+
+    // Secrets
+    if collectibles.level_secrets > 0 {
+        let txt = ctx
+            .new_layout(tr!(
+                loc,
+                "hud-secrets",
+                found = collectibles.secrets.len(),
+                total = collectibles.level_secrets
+            ))
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::srgb(0.8, 0.6, 1.))
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(200., 20.))
+            .build();
+        ctx.draw_text(txt, top_right + Vec2::new(-100., 50.));
+    }
+
+    // Score, with the combo multiplier while it lasts
+    let txt = ctx
+        .new_layout(tr!(loc, "hud-score", points = score.points))
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(300., 20.))
+        .build();
+    ctx.draw_text(txt, top_right + Vec2::new(-180., 10.));
+    if let Some(ratio) = score.combo_ratio(time.elapsed()) {
+        if score.multiplier() > 1 {
+            let txt = ctx
+                .new_layout(format!("x{}", score.multiplier()))
+                .font(ui_res.font.clone())
+                .font_size(16.)
+                .color(Color::srgba(1., 0.5, 0.2, ratio))
+                .alignment(JustifyText::Left)
+                .bounds(Vec2::new(60., 20.))
+                .build();
+            ctx.draw_text(txt, top_right + Vec2::new(-40., 10.));
+        }
+    }
+
+    // Level timer, turning red once over the par time, unless hidden
+    if settings.show_timer {
+        let par_time = level_meta.as_ref().and_then(|meta| meta.par_time);
+        let (text, color) = match par_time {
+            Some(par_time) => (
+                format!(
+                    "{} / {}",
+                    format_time(level_timer.elapsed),
+                    format_time(par_time)
+                ),
+                if level_timer.elapsed <= par_time {
+                    Color::WHITE
+                } else {
+                    Color::srgb(1., 0.3, 0.3)
+                },
+            ),
+            None => (format_time(level_timer.elapsed), Color::WHITE),
+        };
+        draw_monospace_text(
+            &mut ctx,
+            &ui_res,
+            &text,
+            color,
+            top_center + Vec2::new(0., 50.),
+        );
+    }
+
+    // Active power-ups, with their remaining time
+    if let Ok(effects) = q_effects.get_single() {
+        for (index, (power_up, remaining)) in effects.remaining.iter().enumerate() {
+            let x = top_left.x + 10. + index as f32 * 60.;
+            let r = Rect::new(x, top_left.y + 62., x + 12., top_left.y + 74.);
+            let brush = ctx.solid_brush(power_up.color());
+            ctx.fill(r, &brush);
+            let txt = ctx
+                .new_layout(format!("{}", remaining.as_secs() + 1))
+                .font(ui_res.font.clone())
+                .font_size(12.)
+                .color(Color::WHITE)
+                .alignment(JustifyText::Left)
+                .bounds(Vec2::new(40., 12.))
+                .build();
+            ctx.draw_text(txt, Vec2::new(x + 36., top_left.y + 68.));
+        }
+    }
+
+    // Breath, only while holding it
+    if let Ok(breath) = q_breath.get_single() {
+        let ratio = breath.ratio();
+        if ratio < 1. {
+            let mut r = Rect::new(
+                top_left.x + 10.,
+                top_left.y + 52.,
+                top_left.x + 160.,
+                top_left.y + 56.,
+            );
+            r.max.x = r.min.x + r.width() * ratio;
+            let brush = ctx.solid_brush(Color::srgb(0.3, 0.6, 1.));
+            ctx.fill(r, &brush);
+        }
+    }
+
+    // Weapon cooldown
+    if let Ok(weapon) = q_weapon.get_single() {
+        let ratio = weapon.cooldown_ratio(time.elapsed());
+        let mut r = Rect::new(
+            top_left.x + 10.,
+            top_left.y + 44.,
+            top_left.x + 160.,
+            top_left.y + 48.,
+        );
+        r.max.x = r.min.x + r.width() * ratio;
+        let color = if ratio >= 1. {
+            Color::srgb(1., 1., 0.)
+        } else {
+            Color::srgb(0.5, 0.5, 0.5)
+        };
+        let brush = ctx.solid_brush(color);
+        ctx.fill(r, &brush);
+    }
+
+    // Dialogue box
+    if dialogue.is_open() {
+        let r = Rect::new(
+            bottom_center.x - 400.,
+            bottom_center.y - 160.,
+            bottom_center.x + 400.,
+            bottom_center.y - 20.,
+        );
+        let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.85));
+        let border_brush = ctx.solid_brush(Color::WHITE);
+        ctx.fill(r, &brush).border(&border_brush, 2.);
+
+        let txt = ctx
+            .new_layout(dialogue.speaker.clone())
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::srgb(1., 0.85, 0.))
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(760., 20.))
+            .build();
+        ctx.draw_text(txt, bottom_center + Vec2::new(0., -140.));
+
+        let txt = ctx
+            .new_layout(dialogue.visible_text(time.elapsed()).to_string())
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::WHITE)
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(760., 80.))
+            .build();
+        ctx.draw_text(txt, bottom_center + Vec2::new(0., -85.));
+
+        // Blinking marker once the page is fully typed out
+        if dialogue.is_page_complete(time.elapsed()) && (time.elapsed().as_millis() / 400) % 2 == 1
+        {
+            let brush = ctx.solid_brush(Color::WHITE);
+            let marker = bottom_center + Vec2::new(380., -35.);
+            ctx.fill(Rect::from_center_size(marker, Vec2::splat(10.)), &brush);
+        }
+    }
+}
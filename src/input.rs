@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Logical player action, bound to a keyboard key and a gamepad button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    Jump,
+    Fire,
+    Interact,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Jump,
+        Action::Fire,
+        Action::Interact,
+    ];
+
+    /// Identifier of the display name of the action, to look up with
+    /// [`tr!`](crate::locale::tr).
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Left => "action-left",
+            Action::Right => "action-right",
+            Action::Up => "action-up",
+            Action::Down => "action-down",
+            Action::Jump => "action-jump",
+            Action::Fire => "action-fire",
+            Action::Interact => "action-interact",
+        }
+    }
+
+    /// Identifier of the action in the saved bindings, which must never
+    /// change once released.
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Jump => "jump",
+            Action::Fire => "fire",
+            Action::Interact => "interact",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Keyboard key and gamepad button bound to an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub key: KeyCode,
+    pub button: GamepadButtonType,
+}
+
+impl Binding {
+    const fn new(key: KeyCode, button: GamepadButtonType) -> Self {
+        Self { key, button }
+    }
+}
+
+/// Bindings of all the player actions.
+#[derive(Debug, Clone, PartialEq, Eq, Resource, Serialize, Deserialize)]
+#[serde(from = "SavedBindings", into = "SavedBindings")]
+pub struct InputMap {
+    /// Binding of each action, indexed by [`Action`].
+    bindings: [Binding; Action::ALL.len()],
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: [
+                Binding::new(KeyCode::KeyA, GamepadButtonType::DPadLeft),
+                Binding::new(KeyCode::KeyD, GamepadButtonType::DPadRight),
+                Binding::new(KeyCode::KeyW, GamepadButtonType::DPadUp),
+                Binding::new(KeyCode::KeyS, GamepadButtonType::DPadDown),
+                Binding::new(KeyCode::Space, GamepadButtonType::South),
+                Binding::new(KeyCode::KeyF, GamepadButtonType::West),
+                Binding::new(KeyCode::KeyE, GamepadButtonType::North),
+            ],
+        }
+    }
+}
+
+impl InputMap {
+    pub fn binding(&self, action: Action) -> Binding {
+        self.bindings[action.index()]
+    }
+
+    /// Bind a key to an action. If another action was already bound to that key,
+    /// it gets the previous key of this action instead, and is returned.
+    pub fn bind_key(&mut self, action: Action, key: KeyCode) -> Option<Action> {
+        let previous = self.bindings[action.index()].key;
+        let conflict = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.binding(*other).key == key);
+        if let Some(other) = conflict {
+            self.bindings[other.index()].key = previous;
+        }
+        self.bindings[action.index()].key = key;
+        conflict
+    }
+
+    /// Bind a gamepad button to an action. If another action was already bound
+    /// to that button, it gets the previous button of this action instead, and
+    /// is returned.
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) -> Option<Action> {
+        let previous = self.bindings[action.index()].button;
+        let conflict = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.binding(*other).button == button);
+        if let Some(other) = conflict {
+            self.bindings[other.index()].button = previous;
+        }
+        self.bindings[action.index()].button = button;
+        conflict
+    }
+}
+
+/// Version of the format of the saved bindings, to bump with a migration in
+/// [`SavedBindings`] whenever an action is renamed or removed.
+const BINDINGS_VERSION: u32 = 1;
+
+/// Actions in the order of the first saved bindings, which were an unversioned
+/// list.
+const UNVERSIONED_ACTIONS: [Action; 7] = [
+    Action::Left,
+    Action::Right,
+    Action::Up,
+    Action::Down,
+    Action::Jump,
+    Action::Fire,
+    Action::Interact,
+];
+
+/// Saved form of the [`InputMap`], versioned so bindings saved by an older
+/// version of the game can be migrated instead of being lost.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedBindings {
+    /// Format version, `0` for unversioned bindings.
+    version: u32,
+    /// Binding of each action, by [`Action::name()`]. Actions missing, like
+    /// the ones added since the bindings were saved, keep their default
+    /// binding.
+    actions: BTreeMap<String, Binding>,
+    /// Unversioned bindings, indexed like [`UNVERSIONED_ACTIONS`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    bindings: Vec<Binding>,
+}
+
+impl From<InputMap> for SavedBindings {
+    fn from(input_map: InputMap) -> Self {
+        Self {
+            version: BINDINGS_VERSION,
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| (action.name().to_string(), input_map.binding(action)))
+                .collect(),
+            bindings: vec![],
+        }
+    }
+}
+
+impl From<SavedBindings> for InputMap {
+    fn from(mut saved: SavedBindings) -> Self {
+        if saved.version == 0 {
+            debug!("Migrating unversioned bindings");
+            saved.actions = UNVERSIONED_ACTIONS
+                .into_iter()
+                .zip(saved.bindings)
+                .map(|(action, binding)| (action.name().to_string(), binding))
+                .collect();
+        } else if saved.version > BINDINGS_VERSION {
+            warn!(
+                "Bindings saved by a newer version of the game (v{}), some may be lost",
+                saved.version
+            );
+        }
+
+        let mut input_map = InputMap::default();
+        for (name, binding) in saved.actions {
+            match Action::ALL.into_iter().find(|action| action.name() == name) {
+                Some(action) => input_map.bindings[action.index()] = binding,
+                None => warn!("Ignoring binding of unknown action '{}'", name),
+            }
+        }
+        input_map
+    }
+}
+
+/// Display name of a key, like `A` for [`KeyCode::KeyA`].
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Player action inputs, from the keyboard or any connected gamepad, through
+/// the [`InputMap`].
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    input_map: Res<'w, InputMap>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl ActionInput<'_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        let binding = self.input_map.binding(action);
+        self.keyboard.pressed(binding.key)
+            || self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, binding.button))
+            })
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        let binding = self.input_map.binding(action);
+        self.keyboard.just_pressed(binding.key)
+            || self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .just_pressed(GamepadButton::new(gamepad, binding.button))
+            })
+    }
+}
+
+#[derive(Default)]
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMap>();
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_keith::Canvas;
+
+use crate::{
+    dialogue::Dialogue,
+    input::{key_name, Action, InputMap},
+    layout::ui_scale,
+    locale::{tr, Localizer},
+    AppState, Interactable, MainCamera, Player, UiRes,
+};
+
+/// Height of the interaction prompt above the interactable, in world units.
+const PROMPT_OFFSET: f32 = 16.;
+
+/// Nearest interactable in reach of the player, if any. This is the entity
+/// the interact action applies to.
+#[derive(Debug, Default, Resource)]
+pub struct InteractionTarget {
+    pub entity: Option<Entity>,
+    /// World position of the target.
+    pub position: Vec2,
+    /// Identifier of the message naming the interaction.
+    pub verb: &'static str,
+}
+
+#[derive(Default)]
+pub struct InteractPlugin;
+
+impl Plugin for InteractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InteractionTarget>()
+            .add_systems(Update, find_target.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                Update,
+                ui_prompt
+                    .after(crate::hud::main_ui)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Find the nearest interactable in reach of the player. There's no target
+/// while a dialogue is open, since it receives the interact action.
+pub fn find_target(
+    dialogue: Res<Dialogue>,
+    q_player: Query<&Transform, With<Player>>,
+    q_interactables: Query<(Entity, &Interactable, &GlobalTransform), Without<Player>>,
+    mut target: ResMut<InteractionTarget>,
+) {
+    let player_pos = q_player
+        .get_single()
+        .ok()
+        .filter(|_| !dialogue.is_open())
+        .map(|transform| transform.translation.xy());
+    let nearest = player_pos.and_then(|player_pos| {
+        q_interactables
+            .iter()
+            .map(|(entity, interactable, transform)| {
+                let pos = transform.translation().xy();
+                (entity, interactable, pos, pos.distance(player_pos))
+            })
+            .filter(|(_, interactable, _, dist)| *dist <= interactable.radius)
+            .min_by(|a, b| a.3.total_cmp(&b.3))
+    });
+
+    let Some((entity, interactable, position, _)) = nearest else {
+        if target.entity.is_some() {
+            target.entity = None;
+        }
+        return;
+    };
+    *target = InteractionTarget {
+        entity: Some(entity),
+        position,
+        verb: interactable.verb,
+    };
+}
+
+/// Draw the interaction prompt above the current target.
+fn ui_prompt(
+    target: Res<InteractionTarget>,
+    input_map: Res<InputMap>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    loc: Localizer,
+) {
+    if target.entity.is_none() {
+        return;
+    }
+    let (Ok((camera, camera_transform)), Ok(window)) =
+        (q_camera.get_single(), q_window.get_single())
+    else {
+        return;
+    };
+
+    // Project the target into the viewport, then into the canvas which is
+    // centered on that viewport and scaled to fit the window.
+    let world_pos = (target.position + Vec2::Y * PROMPT_OFFSET).extend(0.);
+    let (Some(viewport_pos), Some(viewport_size)) = (
+        camera.world_to_viewport(camera_transform, world_pos),
+        camera.logical_viewport_size(),
+    ) else {
+        return;
+    };
+    let pos = (viewport_pos - viewport_size / 2.) / ui_scale(window.size());
+
+    let mut canvas = q_canvas.single_mut();
+    let mut ctx = canvas.render_context();
+    let key = key_name(input_map.binding(Action::Interact).key);
+    let txt = ctx
+        .new_layout(tr!(
+            loc,
+            "interact-prompt",
+            key = key,
+            action = tr!(loc, target.verb)
+        ))
+        .font(ui_res.font.clone())
+        .font_size(12.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Center)
+        .bounds(Vec2::new(160., 12.))
+        .build();
+    ctx.draw_text(txt, pos);
+}
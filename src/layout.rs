@@ -0,0 +1,95 @@
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+
+use crate::camera::CameraSettings;
+
+/// Size of the UI canvas the UI is designed for, which is scaled to fit the
+/// window.
+pub const UI_SIZE: Vec2 = Vec2::new(960., 720.);
+
+/// Scale of the UI canvas and of the game view such that [`UI_SIZE`] fits
+/// inside a window of the given size.
+pub fn ui_scale(window_size: Vec2) -> f32 {
+    (window_size / UI_SIZE).min_element()
+}
+
+/// Visible area of the UI canvas, to anchor UI elements to the edges and
+/// corners of the window.
+///
+/// The canvas always contains [`UI_SIZE`], and unless letterboxing extends
+/// beyond it along the axis where the window is wider or taller than the 4:3
+/// aspect ratio. Like the canvas, the origin is at the center and the Y axis
+/// points down.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct UiLayout {
+    size: Vec2,
+}
+
+impl Default for UiLayout {
+    fn default() -> Self {
+        Self { size: UI_SIZE }
+    }
+}
+
+impl UiLayout {
+    /// Rectangle covering the whole window.
+    pub fn screen_rect(&self) -> Rect {
+        Rect::from_center_size(Vec2::ZERO, self.size)
+    }
+
+    pub fn top_left(&self) -> Vec2 {
+        -self.size / 2.
+    }
+
+    pub fn top_center(&self) -> Vec2 {
+        Vec2::new(0., -self.size.y / 2.)
+    }
+
+    pub fn top_right(&self) -> Vec2 {
+        Vec2::new(self.size.x, -self.size.y) / 2.
+    }
+
+    pub fn bottom_center(&self) -> Vec2 {
+        Vec2::new(0., self.size.y / 2.)
+    }
+
+    pub fn bottom_right(&self) -> Vec2 {
+        self.size / 2.
+    }
+}
+
+#[derive(Default)]
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiLayout>()
+            .add_systems(PreUpdate, update_layout);
+    }
+}
+
+fn update_layout(
+    camera_settings: Res<CameraSettings>,
+    mut ev_resized: EventReader<WindowResized>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut layout: ResMut<UiLayout>,
+) {
+    if ev_resized.read().count() == 0 && !camera_settings.is_changed() {
+        return;
+    }
+    if camera_settings.letterbox {
+        layout.size = UI_SIZE;
+        return;
+    }
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    // Keep the last layout while minimized
+    let size = window.size();
+    if size.min_element() <= 0. {
+        return;
+    }
+    layout.size = size / ui_scale(size);
+}
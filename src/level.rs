@@ -0,0 +1,398 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use bevy_keith::Canvas;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::{Checkpoint, Lives},
+    fade::{FadeAction, FadeEvent},
+    layout::UiLayout,
+    loading::LoadingAssets,
+    locale::{tr, Localizer},
+    menu::{draw_menu_cursor, MenuAction, MenuHighlight, MenuItem, MenuLayout, MenuNavigation},
+    pickup::Collectibles,
+    results::{LevelResults, Rank},
+    stats::RunStats,
+    timer::{format_time, LevelTimer},
+    AppState, Epoch, LevelEntity, Player, TiledMap, TiledMapBundle, UiRes,
+};
+
+/// Level of the game, as listed in the level select menu.
+#[derive(Debug, Clone)]
+pub struct LevelInfo {
+    /// Asset path of the Tiled map.
+    pub path: String,
+    /// Display name in the level select menu.
+    pub name: String,
+    pub is_unlocked: bool,
+    pub is_completed: bool,
+    pub record: LevelRecord,
+}
+
+impl LevelInfo {
+    pub fn new(path: &str, name: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            name: name.to_string(),
+            is_unlocked: false,
+            is_completed: false,
+            record: default(),
+        }
+    }
+}
+
+/// Best results and statistics of a level, kept across runs and sessions.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelRecord {
+    pub best_time: Option<Duration>,
+    pub best_rank: Option<Rank>,
+    /// Most coins collected in a single completion.
+    pub best_coins: u32,
+    /// Most secret areas found in a single completion.
+    pub best_secrets: u32,
+    /// Number of coins in the level, known once completed.
+    pub level_coins: u32,
+    /// Number of secret areas in the level, known once completed.
+    pub level_secrets: u32,
+    /// Number of times the player died in the level, over all attempts.
+    pub deaths: u32,
+}
+
+impl LevelRecord {
+    /// Record a completion of the level, keeping the best of each result.
+    pub fn complete(&mut self, results: &LevelResults, rank: Rank) {
+        self.best_time = Some(match self.best_time {
+            Some(best) => best.min(results.time),
+            None => results.time,
+        });
+        // Ranks are declared from best to worst
+        self.best_rank = Some(self.best_rank.map_or(rank, |best| best.min(rank)));
+        self.best_coins = self.best_coins.max(results.coins);
+        self.best_secrets = self.best_secrets.max(results.secrets);
+        self.level_coins = results.level_coins;
+        self.level_secrets = results.level_secrets;
+    }
+}
+
+/// All the levels of the game, in order.
+#[derive(Debug, Resource)]
+pub struct LevelRegistry {
+    pub levels: Vec<LevelInfo>,
+    /// Index of the level currently played.
+    pub current: usize,
+}
+
+impl Default for LevelRegistry {
+    fn default() -> Self {
+        let mut levels = vec![LevelInfo::new("map1.tmx", "Level 1")];
+        levels[0].is_unlocked = true;
+        Self { levels, current: 0 }
+    }
+}
+
+impl LevelRegistry {
+    /// Index of the level following the current one. This is the level with
+    /// the given asset path if any, as set by the `next_level` map property, or
+    /// the next one in the registry otherwise.
+    pub fn next_index(&self, next_path: Option<&str>) -> Option<usize> {
+        match next_path {
+            Some(path) => {
+                let index = self.levels.iter().position(|level| level.path == path);
+                if index.is_none() {
+                    warn!("Next level '{}' is not registered", path);
+                }
+                index
+            }
+            None => (self.current + 1 < self.levels.len()).then_some(self.current + 1),
+        }
+    }
+
+    /// Asset path of the level currently played.
+    pub fn current_path(&self) -> Option<&str> {
+        self.levels
+            .get(self.current)
+            .map(|level| level.path.as_str())
+    }
+
+    /// Record of the level currently played.
+    pub fn current_record_mut(&mut self) -> Option<&mut LevelRecord> {
+        let current = self.current;
+        self.levels.get_mut(current).map(|level| &mut level.record)
+    }
+
+    /// Mark the current level as completed, and unlock the given next one.
+    pub fn complete_current(&mut self, next: Option<usize>) {
+        let current = self.current;
+        if let Some(level) = self.levels.get_mut(current) {
+            level.is_completed = true;
+        }
+        if let Some(level) = next.and_then(|next| self.levels.get_mut(next)) {
+            level.is_unlocked = true;
+        }
+    }
+}
+
+/// Request to unload the current level, if any, and load the level with the
+/// given index in the [`LevelRegistry`]. The level always starts from the
+/// beginning, even if it's the current one.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct LoadLevelEvent(pub usize);
+
+const LEVEL_SELECT_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-240., -150.), 50., 480.);
+
+#[derive(Default, Resource)]
+struct LevelSelectMenu {
+    pub selected_index: usize,
+}
+
+#[derive(Default)]
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelRegistry>()
+            .init_resource::<LevelSelectMenu>()
+            .add_event::<LoadLevelEvent>()
+            .add_systems(Update, load_level)
+            .add_systems(
+                Update,
+                restart_level
+                    .run_if(in_state(AppState::InGame).and_then(crate::dialogue::is_closed)),
+            )
+            .add_systems(
+                PreUpdate,
+                level_select_inputs.run_if(in_state(AppState::LevelSelect)),
+            )
+            .add_systems(
+                Update,
+                ui_level_select.run_if(in_state(AppState::LevelSelect)),
+            );
+    }
+}
+
+pub fn load_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut events: EventReader<LoadLevelEvent>,
+    mut registry: ResMut<LevelRegistry>,
+    mut checkpoint: ResMut<Checkpoint>,
+    mut collectibles: ResMut<Collectibles>,
+    mut level_timer: ResMut<LevelTimer>,
+    mut stats: ResMut<RunStats>,
+    q_storages: Query<&TileStorage, With<LevelEntity>>,
+    q_level: Query<Entity, (With<LevelEntity>, Without<Parent>)>,
+    q_maps: Query<Entity, With<Handle<TiledMap>>>,
+    q_player: Query<Entity, With<Player>>,
+    mut q_epoch: Query<&mut Epoch>,
+    mut loading: ResMut<LoadingAssets>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    let Some(LoadLevelEvent(index)) = events.read().last().copied() else {
+        return;
+    };
+    let Some(level) = registry.levels.get(index) else {
+        warn!("Cannot load unknown level #{}", index);
+        return;
+    };
+    info!(
+        "Loading level #{} '{}' from {}",
+        index, level.name, level.path
+    );
+
+    // Unload the current level. Tiles are not children of their layer, so are
+    // despawned separately.
+    for storage in &q_storages {
+        for tile_entity in storage.iter().flatten() {
+            commands.entity(*tile_entity).despawn_recursive();
+        }
+    }
+    for entity in q_level.iter().chain(&q_maps).chain(&q_player) {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Ok(mut epoch) = q_epoch.get_single_mut() {
+        *epoch = Epoch::default();
+    }
+    // Switching level already resets the per-level state once the map is
+    // processed, but reloading the same one needs to forget it explicitly.
+    if index == registry.current {
+        checkpoint.reset_level();
+        collectibles.reset_level();
+    }
+    level_timer.restart();
+    stats.start_level();
+
+    // Show the loading screen until the map and its textures are loaded
+    let tiled_map = asset_server.load(level.path.clone());
+    loading.track_map(tiled_map.clone());
+    loading.set_next_state(AppState::InGame);
+    app_state.set(AppState::Loading);
+
+    commands.spawn((
+        TiledMapBundle {
+            tiled_map,
+            ..default()
+        },
+        Name::new("TiledLevel"),
+    ));
+    registry.current = index;
+}
+
+/// Restart the current level from the beginning.
+fn restart_level(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<LevelRegistry>,
+    mut ev_fade: EventWriter<FadeEvent>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyR) {
+        info!("Restarting level #{}", registry.current);
+        ev_fade.send(FadeEvent(FadeAction::LoadLevel(registry.current)));
+    }
+}
+
+fn level_select_inputs(
+    mut navigation: MenuNavigation,
+    registry: Res<LevelRegistry>,
+    mut menu: ResMut<LevelSelectMenu>,
+    mut lives: ResMut<Lives>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut ev_fade: EventWriter<FadeEvent>,
+) {
+    let action = navigation.navigate(
+        &mut menu.selected_index,
+        registry.levels.len(),
+        &LEVEL_SELECT_LAYOUT,
+    );
+    if action == Some(MenuAction::Confirm) {
+        if registry
+            .levels
+            .get(menu.selected_index)
+            .is_some_and(|level| level.is_unlocked)
+        {
+            lives.reset();
+            ev_fade.send(FadeEvent(FadeAction::LoadLevel(menu.selected_index)));
+        }
+    } else if action == Some(MenuAction::Cancel) {
+        app_state.set(AppState::MainMenu);
+    }
+}
+
+fn ui_level_select(
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    registry: Res<LevelRegistry>,
+    menu: Res<LevelSelectMenu>,
+    highlight: MenuHighlight,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    // Background
+    let brush = ctx.solid_brush(Srgba::hex("3b69ba").unwrap().into());
+    ctx.fill(layout.screen_rect(), &brush);
+
+    let txt = ctx
+        .new_layout(tr!(loc, "level-select-title"))
+        .font(ui_res.font.clone())
+        .font_size(32.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(400., 40.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -250.));
+
+    for (index, level) in registry.levels.iter().enumerate() {
+        let name = loc.text(&level.name);
+        let (text, color) = if !level.is_unlocked {
+            (
+                tr!(loc, "level-select-locked", name = name),
+                Color::srgb(0.6, 0.6, 0.6),
+            )
+        } else if level.is_completed {
+            (
+                tr!(loc, "level-select-completed", name = name),
+                Color::srgb(1., 0.85, 0.),
+            )
+        } else {
+            (name, Color::WHITE)
+        };
+        let item = MenuItem {
+            text,
+            position: Vec2::new(0., LEVEL_SELECT_LAYOUT.item_y(index)),
+            bounds: Vec2::new(400., 30.),
+            font_size: 24.,
+            color,
+        };
+        highlight.draw_item(&mut ctx, &ui_res, item, index == menu.selected_index);
+    }
+
+    // Records of the selected level, once completed
+    if let Some(record) = registry
+        .levels
+        .get(menu.selected_index)
+        .map(|level| &level.record)
+    {
+        let mut lines = vec![];
+        if let Some(best_time) = record.best_time {
+            lines.push(tr!(
+                loc,
+                "level-select-best-time",
+                time = format_time(best_time)
+            ));
+        }
+        if let Some(best_rank) = record.best_rank {
+            lines.push(tr!(
+                loc,
+                "level-select-best-rank",
+                rank = best_rank.letter()
+            ));
+        }
+        if record.best_time.is_some() {
+            lines.push(tr!(
+                loc,
+                "victory-coins",
+                count = record.best_coins,
+                total = record.level_coins
+            ));
+            if record.level_secrets > 0 {
+                lines.push(tr!(
+                    loc,
+                    "hud-secrets",
+                    found = record.best_secrets,
+                    total = record.level_secrets
+                ));
+            }
+        }
+        if record.deaths > 0 {
+            lines.push(tr!(loc, "level-select-deaths", count = record.deaths));
+        }
+        let txt = ctx
+            .new_layout(lines.join("\n"))
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::srgb(0.8, 0.8, 0.8))
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(400., 100.))
+            .build();
+        ctx.draw_text(txt, layout.bottom_center() + Vec2::new(0., -180.));
+    }
+
+    let txt = ctx
+        .new_layout(tr!(loc, "level-select-help"))
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(400., 20.))
+        .build();
+    ctx.draw_text(txt, layout.bottom_center() + Vec2::new(0., -60.));
+
+    let cursor = LEVEL_SELECT_LAYOUT.cursor_position(menu.selected_index);
+    draw_menu_cursor(&mut ctx, &ui_res, cursor);
+}
@@ -0,0 +1,141 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+
+use crate::{AppState, Epoch, LevelMeta, LightSource, MainCamera};
+
+/// Resolution of the darkness overlay texture, stretched over the camera view.
+const OVERLAY_SIZE: UVec2 = UVec2::new(160, 120);
+
+/// Depth of the darkness overlay, above everything else in the world.
+const OVERLAY_DEPTH: f32 = 500.;
+
+/// Translucent black sprite covering the camera view, with the light sources
+/// carved out of it.
+#[derive(Component)]
+struct DarknessOverlay;
+
+#[derive(Default)]
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_overlay).add_systems(
+            PostUpdate,
+            update_darkness
+                .after(crate::camera::update_camera)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+fn spawn_overlay(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: OVERLAY_SIZE.x,
+            height: OVERLAY_SIZE.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Smooth out the low resolution of the overlay
+    image.sampler = ImageSampler::linear();
+
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(image),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        DarknessOverlay,
+        Name::new("DarknessOverlay"),
+    ));
+}
+
+/// Compute the darkness over the camera view from the ambient light of the
+/// current epoch and the light sources around.
+fn update_darkness(
+    mut images: ResMut<Assets<Image>>,
+    level_meta: Option<Res<LevelMeta>>,
+    q_epoch: Query<&Epoch>,
+    q_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    q_lights: Query<(&GlobalTransform, &LightSource)>,
+    mut q_overlay: Query<
+        (&Handle<Image>, &mut Transform, &mut Sprite, &mut Visibility),
+        (With<DarknessOverlay>, Without<MainCamera>),
+    >,
+) {
+    let Ok((image_handle, mut transform, mut sprite, mut visibility)) = q_overlay.get_single_mut()
+    else {
+        return;
+    };
+
+    let ambient = match (level_meta, q_epoch.get_single()) {
+        (Some(level_meta), Ok(epoch)) => level_meta.ambient_light(epoch),
+        _ => 1.,
+    };
+    let new_visibility = if ambient < 1. {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+    if ambient >= 1. {
+        return;
+    }
+
+    let Ok((camera_transform, projection)) = q_camera.get_single() else {
+        return;
+    };
+    let Some(image) = images.get_mut(image_handle) else {
+        return;
+    };
+
+    // Cover the camera view
+    let view_size = projection.area.size();
+    let view_min = camera_transform.translation.xy() - view_size / 2.;
+    transform.translation = camera_transform.translation.xy().extend(OVERLAY_DEPTH);
+    if sprite.custom_size != Some(view_size) {
+        sprite.custom_size = Some(view_size);
+    }
+
+    // Only consider the lights reaching into the view
+    let view_rect = Rect::from_corners(view_min, view_min + view_size);
+    let lights: Vec<(Vec2, &LightSource)> = q_lights
+        .iter()
+        .map(|(transform, light)| (transform.translation().xy(), light))
+        .filter(|(pos, light)| {
+            let closest = pos.clamp(view_rect.min, view_rect.max);
+            closest.distance_squared(*pos) < light.radius * light.radius
+        })
+        .collect();
+
+    let texel_size = view_size / OVERLAY_SIZE.as_vec2();
+    for y in 0..OVERLAY_SIZE.y {
+        for x in 0..OVERLAY_SIZE.x {
+            // Texture rows go down, while the world Y axis goes up
+            let pos = view_min
+                + Vec2::new(x as f32 + 0.5, (OVERLAY_SIZE.y - 1 - y) as f32 + 0.5) * texel_size;
+            let mut light_level = ambient;
+            for (light_pos, light) in &lights {
+                let ratio = light_pos.distance_squared(pos) / (light.radius * light.radius);
+                if ratio < 1. {
+                    light_level += light.intensity * (1. - ratio);
+                }
+            }
+            let alpha = ((1. - light_level).clamp(0., 1.) * 255.) as u8;
+            let index = ((y * OVERLAY_SIZE.x + x) * 4 + 3) as usize;
+            image.data[index] = alpha;
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::{asset::LoadState, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+use bevy_keith::{Canvas, ShapeExt};
+
+use crate::{
+    fade::{FadeAction, FadeEvent},
+    layout::UiLayout,
+    locale::{tr, Localizer},
+    AppState, TiledMap, UiRes,
+};
+
+/// Assets to wait for in the [`AppState::Loading`] state, and the state to go
+/// to once they're all loaded.
+#[derive(Resource)]
+pub struct LoadingAssets {
+    handles: Vec<UntypedHandle>,
+    /// Maps whose tileset textures are not tracked yet, because the map itself
+    /// is still loading.
+    pending_maps: Vec<Handle<TiledMap>>,
+    /// A tracked map failed to load, so the game can't go to the next state.
+    has_failed_map: bool,
+    next_state: AppState,
+}
+
+impl Default for LoadingAssets {
+    fn default() -> Self {
+        Self {
+            handles: vec![],
+            pending_maps: vec![],
+            has_failed_map: false,
+            next_state: AppState::MainMenu,
+        }
+    }
+}
+
+impl LoadingAssets {
+    /// Wait for an asset to be loaded before leaving the loading screen.
+    pub fn track(&mut self, handle: impl Into<UntypedHandle>) {
+        self.handles.push(handle.into());
+    }
+
+    /// Wait for a map and all its tileset textures to be loaded before leaving
+    /// the loading screen.
+    pub fn track_map(&mut self, handle: Handle<TiledMap>) {
+        self.pending_maps.push(handle.clone());
+        self.track(handle);
+    }
+
+    /// Set the state to go to once all the tracked assets are loaded.
+    pub fn set_next_state(&mut self, next_state: AppState) {
+        self.next_state = next_state;
+    }
+
+    /// Loading progress in \[0:1\], and whether all assets are done loading,
+    /// successfully or not.
+    fn progress(&self, asset_server: &AssetServer) -> (f32, bool) {
+        let done = self
+            .handles
+            .iter()
+            .filter(|handle| {
+                asset_server.is_loaded_with_dependencies(handle.id())
+                    || matches!(
+                        asset_server.get_load_state(handle.id()),
+                        Some(LoadState::Failed(_))
+                    )
+            })
+            .count();
+        let total = self.handles.len();
+        if total == 0 {
+            (1., self.pending_maps.is_empty())
+        } else {
+            (
+                done as f32 / total as f32,
+                done == total && self.pending_maps.is_empty(),
+            )
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingAssets>().add_systems(
+            Update,
+            (track_map_textures, finish_loading, ui_loading)
+                .chain()
+                .run_if(in_state(AppState::Loading)),
+        );
+    }
+}
+
+/// Track the tileset textures of the maps once loaded, to count them in the
+/// loading progress. Maps which fail to load are dropped, as they'll never have
+/// any texture.
+fn track_map_textures(
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+    maps: Res<Assets<TiledMap>>,
+) {
+    let pending_maps = std::mem::take(&mut loading.pending_maps);
+    for handle in pending_maps {
+        let Some(tiled_map) = maps.get(&handle) else {
+            if let Some(LoadState::Failed(err)) = asset_server.get_load_state(&handle) {
+                error!("Failed to load map {:?}: {}", handle.path(), err);
+                loading.has_failed_map = true;
+            } else {
+                loading.pending_maps.push(handle);
+            }
+            continue;
+        };
+        for texture in tiled_map.tilemap_textures.values() {
+            match texture {
+                TilemapTexture::Single(image) => loading.track(image.clone()),
+                #[cfg(not(feature = "atlas"))]
+                TilemapTexture::Vector(images) => {
+                    for image in images {
+                        loading.track(image.clone());
+                    }
+                }
+                #[cfg(not(feature = "atlas"))]
+                TilemapTexture::TextureContainer(image) => loading.track(image.clone()),
+            }
+        }
+    }
+}
+
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+    mut ev_fade: EventWriter<FadeEvent>,
+) {
+    let (_, is_done) = loading.progress(&asset_server);
+    if is_done && !loading.handles.is_empty() {
+        // There's no level to play without its map, so go back to the menu
+        let next_state = if loading.has_failed_map {
+            AppState::MainMenu
+        } else {
+            loading.next_state
+        };
+        debug!("Loading done, going to {:?}", next_state);
+        loading.handles.clear();
+        loading.has_failed_map = false;
+        ev_fade.send(FadeEvent(FadeAction::SetState(next_state)));
+    }
+}
+
+fn ui_loading(
+    asset_server: Res<AssetServer>,
+    loading: Res<LoadingAssets>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    let brush = ctx.solid_brush(Color::BLACK);
+    ctx.fill(layout.screen_rect(), &brush);
+
+    // The font may still be loading, in which case only the bar shows
+    let txt = ctx
+        .new_layout(tr!(loc, "loading"))
+        .font(ui_res.font.clone())
+        .font_size(24.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Center)
+        .bounds(Vec2::new(400., 30.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -30.));
+
+    let (progress, _) = loading.progress(&asset_server);
+    let r = Rect::new(-200., 10., 200., 30.);
+    let brush = ctx.solid_brush(Color::BLACK);
+    let border_brush = ctx.solid_brush(Color::WHITE);
+    ctx.fill(r, &brush).border(&border_brush, 2.);
+
+    let brush = ctx.solid_brush(Color::srgb(0.3, 0.6, 1.));
+    let mut r = r.inflate(-3.);
+    r.max.x = r.min.x + r.width() * progress;
+    ctx.fill(r, &brush);
+}
@@ -0,0 +1,289 @@
+use std::fmt::Display;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypePath,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{loading::LoadingAssets, settings::Settings};
+
+/// Look up a UI message in the current language, optionally replacing its
+/// `{ $name }` placeables with the given arguments.
+///
+/// ```ignore
+/// tr!(loc, "menu-new-game");
+/// tr!(loc, "hud-coins", count = collectibles.level_coins);
+/// ```
+macro_rules! tr {
+    ($loc:expr, $id:expr) => {
+        $loc.get($id)
+    };
+    ($loc:expr, $id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $loc.format(
+            $id,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+/// English messages, built into the game so text is available before the
+/// language files are loaded, and for messages missing from a translation.
+const FALLBACK_MESSAGES: &str = include_str!("../assets/lang/en.ftl");
+
+/// Language of the UI text and of the level content.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    /// Name of the language, in that language.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "Français",
+        }
+    }
+
+    /// Asset path of the language file.
+    fn path(&self) -> &'static str {
+        match self {
+            Language::English => "lang/en.ftl",
+            Language::French => "lang/fr.ftl",
+        }
+    }
+}
+
+/// Messages of a language file, by identifier.
+///
+/// Language files use a subset of the [Fluent](https://projectfluent.org)
+/// syntax: one `id = value` message per line, `#` comments, and values
+/// continued on the following indented lines. Values can contain
+/// `{ $name }` placeables, replaced by the arguments passed to [`tr!`].
+#[derive(Debug, Default, Asset, TypePath)]
+pub struct Translation {
+    messages: HashMap<String, String>,
+}
+
+impl Translation {
+    fn parse(source: &str) -> Result<Self, TranslationLoaderError> {
+        let mut messages: HashMap<String, String> = HashMap::default();
+        let mut current: Option<String> = None;
+        for (index, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            // Indented line continuing the value of the previous message
+            if line.starts_with(char::is_whitespace) {
+                let value = current
+                    .as_ref()
+                    .and_then(|id| messages.get_mut(id))
+                    .ok_or(TranslationLoaderError::Syntax { line: index + 1 })?;
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+                continue;
+            }
+
+            let Some((id, value)) = line.split_once('=') else {
+                return Err(TranslationLoaderError::Syntax { line: index + 1 });
+            };
+            let id = id.trim();
+            if id.is_empty()
+                || !id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(TranslationLoaderError::Syntax { line: index + 1 });
+            }
+            messages.insert(id.to_string(), value.trim().to_string());
+            current = Some(id.to_string());
+        }
+        Ok(Self { messages })
+    }
+
+    fn get(&self, id: &str) -> Option<&str> {
+        self.messages.get(id).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TranslationLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load language file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file is not valid UTF-8
+    #[error("Language file is not valid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    /// A line is neither a message, a comment, nor the continuation of a value
+    #[error("Invalid syntax on line {line} of language file")]
+    Syntax { line: usize },
+}
+
+#[derive(Default)]
+struct TranslationLoader;
+
+impl AssetLoader for TranslationLoader {
+    type Asset = Translation;
+    type Settings = ();
+    type Error = TranslationLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Translation::parse(std::str::from_utf8(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ftl"]
+    }
+}
+
+/// Current language, and the loaded language files.
+#[derive(Resource)]
+pub struct Locale {
+    language: Language,
+    translations: HashMap<Language, Handle<Translation>>,
+    fallback: Translation,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            language: Language::default(),
+            translations: HashMap::default(),
+            fallback: Translation::parse(FALLBACK_MESSAGES)
+                .expect("Invalid built-in English language file"),
+        }
+    }
+}
+
+/// Lookup of the messages in the current language, falling back to English
+/// for the messages not translated yet.
+#[derive(SystemParam)]
+pub struct Localizer<'w> {
+    locale: Res<'w, Locale>,
+    translations: Res<'w, Assets<Translation>>,
+}
+
+impl Localizer<'_> {
+    fn message(&self, id: &str) -> Option<&str> {
+        self.locale
+            .translations
+            .get(&self.locale.language)
+            .and_then(|handle| self.translations.get(handle))
+            .and_then(|translation| translation.get(id))
+            .or_else(|| self.locale.fallback.get(id))
+    }
+
+    /// Get a message in the current language. Unknown messages are displayed
+    /// as their identifier, to spot them easily.
+    pub fn get(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    /// Get a message in the current language, with its placeables replaced by
+    /// the given arguments. Prefer the [`tr!`] macro.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        match self.message(id) {
+            Some(message) => substitute(message, args),
+            None => id.to_string(),
+        }
+    }
+
+    /// Translate some text authored in a Tiled map, like a level name or a
+    /// dialogue line. Text which is the identifier of a message is replaced
+    /// by that message, while any other text is displayed as is.
+    pub fn text(&self, text: &str) -> String {
+        match self.message(text) {
+            Some(message) => substitute(message, &[]),
+            None => text.to_string(),
+        }
+    }
+}
+
+/// Replace the `{ $name }` placeables of a message with the value of the
+/// matching arguments. Quoted literals like `{ "{" }` are unquoted, and
+/// unknown placeables are left untouched.
+fn substitute(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let placeable = &rest[start..start + len + 1];
+        let expr = placeable[1..placeable.len() - 1].trim();
+        if let Some(name) = expr.strip_prefix('$') {
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => result.push_str(&value.to_string()),
+                None => result.push_str(placeable),
+            }
+        } else if let Some(literal) = expr
+            .strip_prefix('"')
+            .and_then(|expr| expr.strip_suffix('"'))
+        {
+            result.push_str(literal);
+        } else {
+            result.push_str(placeable);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[derive(Default)]
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Translation>()
+            .register_asset_loader(TranslationLoader)
+            .init_resource::<Locale>()
+            .add_systems(Startup, load_translations)
+            .add_systems(
+                Update,
+                apply_language_settings.run_if(resource_changed::<Settings>),
+            );
+    }
+}
+
+fn load_translations(
+    asset_server: Res<AssetServer>,
+    mut locale: ResMut<Locale>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    for language in Language::ALL {
+        let handle: Handle<Translation> = asset_server.load(language.path());
+        loading.track(handle.clone());
+        locale.translations.insert(language, handle);
+    }
+}
+
+fn apply_language_settings(settings: Res<Settings>, mut locale: ResMut<Locale>) {
+    if locale.language != settings.language {
+        debug!("Language {:?} -> {:?}", locale.language, settings.language);
+        locale.language = settings.language;
+    }
+}
@@ -0,0 +1,598 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension},
+    },
+    time::Real,
+    window::PrimaryWindow,
+};
+use bevy_keith::{Canvas, RenderContext};
+use bevy_kira_audio::prelude::*;
+
+use crate::{
+    checkpoint::Lives,
+    fade::{FadeAction, FadeEvent},
+    layout::{ui_scale, UiLayout},
+    locale::{tr, Localizer},
+    save::SaveGame,
+    settings::SettingsMenu,
+    sound::SfxChannel,
+    AppState, UiRes,
+};
+
+/// Duration of the highlight animation when an item gets the focus.
+const FOCUS_DURATION: Duration = Duration::from_millis(200);
+
+/// Period of the scale pulse of the focused item, once highlighted.
+const PULSE_PERIOD: Duration = Duration::from_millis(1200);
+
+/// Color the focused item shifts to.
+const FOCUS_COLOR: Color = Color::srgb(1., 0.85, 0.);
+
+#[derive(Default, Resource)]
+struct MenuSounds {
+    pub navigate: Handle<bevy_kira_audio::AudioSource>,
+    pub confirm: Handle<bevy_kira_audio::AudioSource>,
+    pub cancel: Handle<bevy_kira_audio::AudioSource>,
+}
+
+/// Time the focus last moved to another menu item, in real time such that menus
+/// animate while the game is paused.
+#[derive(Debug, Default, Resource)]
+pub struct MenuFocus {
+    since: Duration,
+}
+
+const MAIN_MENU_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-180., 150.), 45., 360.);
+
+#[derive(Default, Resource)]
+struct MainMenu {
+    pub selected_index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MainMenuOption {
+    Continue,
+    NewGame,
+    Levels,
+    Settings,
+    Exit,
+}
+
+impl MainMenuOption {
+    /// Options of the main menu, with Continue only when a save exists.
+    fn list(has_save: bool) -> Vec<MainMenuOption> {
+        let mut options = vec![];
+        if has_save {
+            options.push(MainMenuOption::Continue);
+        }
+        options.extend([
+            MainMenuOption::NewGame,
+            MainMenuOption::Levels,
+            MainMenuOption::Settings,
+            MainMenuOption::Exit,
+        ]);
+        options
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            MainMenuOption::Continue => "menu-continue",
+            MainMenuOption::NewGame => "menu-new-game",
+            MainMenuOption::Levels => "menu-levels",
+            MainMenuOption::Settings => "menu-settings",
+            MainMenuOption::Exit => "menu-exit",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuSounds>()
+            .init_resource::<MenuFocus>()
+            .init_resource::<MainMenu>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(Update, animate_menu_cursor)
+            .add_systems(OnEnter(AppState::MainMenu), setup_main_menu)
+            .add_systems(
+                PreUpdate,
+                main_menu_inputs.run_if(in_state(AppState::MainMenu)),
+            )
+            // ESC pauses the game instead while in game, and goes back from the settings
+            // menu, so ignore the press which just returned to the main menu
+            .add_systems(
+                Update,
+                close_on_esc
+                    .run_if(in_state(AppState::MainMenu))
+                    .run_if(not(state_changed::<AppState>)),
+            )
+            .add_systems(Update, ui_main_menu.run_if(in_state(AppState::MainMenu)));
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<MenuSounds>) {
+    sounds.navigate = asset_server.load("select1.ogg");
+    sounds.confirm = sounds.navigate.clone();
+    sounds.cancel = sounds.navigate.clone();
+}
+
+/// Menu navigation inputs, from the keyboard or any connected gamepad.
+#[derive(SystemParam)]
+pub struct MenuInput<'w> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl MenuInput<'_> {
+    fn just_pressed(&self, keys: &[KeyCode], button_type: GamepadButtonType) -> bool {
+        keys.iter().any(|key| self.keyboard.just_pressed(*key))
+            || self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .just_pressed(GamepadButton::new(gamepad, button_type))
+            })
+    }
+
+    pub fn up(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::KeyW, KeyCode::ArrowUp],
+            GamepadButtonType::DPadUp,
+        )
+    }
+
+    pub fn down(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::KeyS, KeyCode::ArrowDown],
+            GamepadButtonType::DPadDown,
+        )
+    }
+
+    pub fn left(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::KeyA, KeyCode::ArrowLeft],
+            GamepadButtonType::DPadLeft,
+        )
+    }
+
+    pub fn right(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::KeyD, KeyCode::ArrowRight],
+            GamepadButtonType::DPadRight,
+        )
+    }
+
+    pub fn prev_tab(&self) -> bool {
+        self.just_pressed(&[KeyCode::KeyQ], GamepadButtonType::LeftTrigger)
+    }
+
+    pub fn next_tab(&self) -> bool {
+        self.just_pressed(&[KeyCode::KeyE], GamepadButtonType::RightTrigger)
+    }
+
+    pub fn back(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::Escape, KeyCode::Backspace],
+            GamepadButtonType::East,
+        )
+    }
+
+    pub fn pause(&self) -> bool {
+        self.just_pressed(&[KeyCode::Escape], GamepadButtonType::Start)
+    }
+
+    /// Toggle muting all audio, available from anywhere.
+    pub fn mute(&self) -> bool {
+        self.just_pressed(&[KeyCode::KeyM], GamepadButtonType::Select)
+    }
+
+    pub fn confirm(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::Enter, KeyCode::NumpadEnter],
+            GamepadButtonType::South,
+        )
+    }
+}
+
+/// Action requested on a menu during a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    /// Activate the focused item.
+    Confirm,
+    /// Leave the menu, for those which can be left.
+    Cancel,
+}
+
+/// Placement on the canvas of the items of a vertical menu, to draw the cursor
+/// and pick items with the mouse.
+#[derive(Debug, Clone, Copy)]
+pub struct MenuLayout {
+    /// Position of the cursor when the first item is focused.
+    pub cursor: Vec2,
+    /// Vertical distance between two consecutive items.
+    pub spacing: f32,
+    /// Width of the area right of the cursor where the mouse picks an item.
+    pub width: f32,
+}
+
+impl MenuLayout {
+    pub const fn new(cursor: Vec2, spacing: f32, width: f32) -> Self {
+        Self {
+            cursor,
+            spacing,
+            width,
+        }
+    }
+
+    /// Vertical position of an item.
+    pub fn item_y(&self, index: usize) -> f32 {
+        self.cursor.y + index as f32 * self.spacing
+    }
+
+    /// Position of the cursor when an item is focused.
+    pub fn cursor_position(&self, index: usize) -> Vec2 {
+        Vec2::new(self.cursor.x, self.item_y(index))
+    }
+
+    /// Index of the item at a canvas position, if any.
+    fn item_at(&self, position: Vec2, len: usize) -> Option<usize> {
+        let left = self.cursor.x - self.spacing / 2.;
+        if position.x < left || position.x > self.cursor.x + self.width {
+            return None;
+        }
+        let row = ((position.y - self.cursor.y) / self.spacing).round();
+        (row >= 0. && row < len as f32).then_some(row as usize)
+    }
+}
+
+/// Navigation of a vertical menu with the keyboard, any connected gamepad, or
+/// the mouse, so all menus behave the same.
+#[derive(SystemParam)]
+pub struct MenuNavigation<'w, 's> {
+    pub input: MenuInput<'w>,
+    mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    q_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    ev_cursor_moved: EventReader<'w, 's, CursorMoved>,
+    time: Res<'w, Time<Real>>,
+    focus: ResMut<'w, MenuFocus>,
+    audio: Res<'w, AudioChannel<SfxChannel>>,
+    sounds: Res<'w, MenuSounds>,
+}
+
+impl MenuNavigation<'_, '_> {
+    /// Move the focus among the `len` items of a menu, wrapping around at both
+    /// ends, and return the action requested this frame.
+    pub fn navigate(
+        &mut self,
+        selected_index: &mut usize,
+        len: usize,
+        layout: &MenuLayout,
+    ) -> Option<MenuAction> {
+        let previous_index = *selected_index;
+        let action = self.navigate_items(selected_index, len, layout);
+        if *selected_index != previous_index {
+            self.focus.since = self.time.elapsed();
+            self.play_navigate();
+        }
+        match action {
+            Some(MenuAction::Confirm) => {
+                self.audio
+                    .play(self.sounds.confirm.clone())
+                    .with_playback_rate(1.5);
+            }
+            Some(MenuAction::Cancel) => {
+                self.audio
+                    .play(self.sounds.cancel.clone())
+                    .with_playback_rate(0.75)
+                    .with_volume(0.7);
+            }
+            None => (),
+        }
+        action
+    }
+
+    /// Play the sound of moving the focus, for menus changing a value of the
+    /// focused item too.
+    pub fn play_navigate(&self) {
+        self.audio
+            .play(self.sounds.navigate.clone())
+            .with_playback_rate(2.)
+            .with_volume(0.5);
+    }
+
+    fn navigate_items(
+        &mut self,
+        selected_index: &mut usize,
+        len: usize,
+        layout: &MenuLayout,
+    ) -> Option<MenuAction> {
+        if len > 0 {
+            *selected_index = (*selected_index).min(len - 1);
+            if self.input.up() {
+                *selected_index = (*selected_index + len - 1) % len;
+            } else if self.input.down() {
+                *selected_index = (*selected_index + 1) % len;
+            }
+        }
+
+        // Only follow the mouse when it moves, to not fight with the keyboard
+        let has_moved = self.ev_cursor_moved.read().count() > 0;
+        let hovered = self
+            .cursor_position()
+            .and_then(|position| layout.item_at(position, len));
+        if let Some(index) = hovered {
+            if has_moved {
+                *selected_index = index;
+            }
+        }
+
+        if self.input.confirm() {
+            Some(MenuAction::Confirm)
+        } else if let Some(index) =
+            hovered.filter(|_| self.mouse_buttons.just_pressed(MouseButton::Left))
+        {
+            *selected_index = index;
+            Some(MenuAction::Confirm)
+        } else if self.input.back() || self.mouse_buttons.just_pressed(MouseButton::Right) {
+            Some(MenuAction::Cancel)
+        } else {
+            None
+        }
+    }
+
+    /// Position of the mouse cursor on the canvas, whose origin is at the center
+    /// of the window and which is scaled to fit the window.
+    fn cursor_position(&self) -> Option<Vec2> {
+        let window = self.q_window.get_single().ok()?;
+        let position = window.cursor_position()?;
+        Some((position - window.size() / 2.) / ui_scale(window.size()))
+    }
+}
+
+/// Text of a menu item, left-aligned in `bounds` centered on `position` like
+/// the other UI text.
+pub struct MenuItem {
+    pub text: String,
+    pub position: Vec2,
+    pub bounds: Vec2,
+    pub font_size: f32,
+    pub color: Color,
+}
+
+/// Drawing of the menu items, with an animated highlight of the focused one
+/// such that all menus look the same.
+#[derive(SystemParam)]
+pub struct MenuHighlight<'w> {
+    time: Res<'w, Time<Real>>,
+    focus: Res<'w, MenuFocus>,
+}
+
+impl MenuHighlight<'_> {
+    /// Draw a menu item. When focused, it shifts to the highlight color and
+    /// pops while an underline sweeps below it, then keeps pulsing slowly.
+    pub fn draw_item(
+        &self,
+        ctx: &mut RenderContext,
+        ui_res: &UiRes,
+        item: MenuItem,
+        is_focused: bool,
+    ) {
+        let (scale, color, underline) = if is_focused {
+            let delta = self.time.elapsed().saturating_sub(self.focus.since);
+            let ratio = delta.div_duration_f32(FOCUS_DURATION).min(1.);
+            let pop = (ratio * std::f32::consts::PI).sin() * 0.15;
+            let pulse = (delta.div_duration_f32(PULSE_PERIOD) * std::f32::consts::TAU).sin() * 0.04;
+            let color = item.color.mix(&FOCUS_COLOR, ratio);
+            // Ease out the underline sweep
+            (1. + pop + pulse * ratio, color, 1. - (1. - ratio).powi(2))
+        } else {
+            (1., item.color, 0.)
+        };
+
+        let font_size = item.font_size * scale;
+        let txt = ctx
+            .new_layout(item.text.clone())
+            .font(ui_res.font.clone())
+            .font_size(font_size)
+            .color(color)
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(item.bounds.x, item.bounds.y.max(font_size)))
+            .build();
+        ctx.draw_text(txt, item.position);
+
+        if underline > 0. {
+            // The UI font is monospace, with an advance equal to its size
+            let width = item.text.chars().count() as f32 * item.font_size;
+            let width = width.min(item.bounds.x) * underline;
+            let left = item.position.x - item.bounds.x / 2.;
+            let y = item.position.y + item.font_size / 2. + 6.;
+            let brush = ctx.solid_brush(color);
+            ctx.fill(Rect::new(left, y, left + width, y + 2.), &brush);
+        }
+    }
+}
+
+fn close_on_esc(mut ev_fade: EventWriter<FadeEvent>, input: Res<ButtonInput<KeyCode>>) {
+    if input.just_pressed(KeyCode::Escape) {
+        ev_fade.send(FadeEvent(FadeAction::Exit));
+    }
+}
+
+fn setup_main_menu() {}
+
+fn main_menu_inputs(
+    mut navigation: MenuNavigation,
+    mut main_menu: ResMut<MainMenu>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut ev_fade: EventWriter<FadeEvent>,
+    mut lives: ResMut<Lives>,
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut save: ResMut<SaveGame>,
+) {
+    let options = MainMenuOption::list(save.data.is_some());
+    let action = navigation.navigate(
+        &mut main_menu.selected_index,
+        options.len(),
+        &MAIN_MENU_LAYOUT,
+    );
+    if action != Some(MenuAction::Confirm) {
+        return;
+    }
+    match options[main_menu.selected_index] {
+        MainMenuOption::Continue => {
+            if let Some(level) = save.continue_game() {
+                ev_fade.send(FadeEvent(FadeAction::LoadLevel(level)));
+            }
+        }
+        MainMenuOption::NewGame => {
+            lives.reset();
+            ev_fade.send(FadeEvent(FadeAction::LoadLevel(0)));
+        }
+        MainMenuOption::Levels => app_state.set(AppState::LevelSelect),
+        MainMenuOption::Settings => {
+            settings_menu.open(AppState::MainMenu);
+            app_state.set(AppState::Settings);
+        }
+        MainMenuOption::Exit => {
+            ev_fade.send(FadeEvent(FadeAction::Exit));
+        }
+    }
+}
+
+fn ui_main_menu(
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    main_menu: Res<MainMenu>,
+    save: Res<SaveGame>,
+    highlight: MenuHighlight,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    // Background
+    let brush = ctx.solid_brush(Srgba::hex("3b69ba").unwrap().into());
+    ctx.fill(layout.screen_rect(), &brush);
+
+    // Title
+    let title_rect = Rect::new(-408., -130., 408., 130.);
+    let brush = ctx.solid_brush(Color::WHITE);
+    ctx.fill(title_rect, &brush);
+    ctx.draw_image(
+        title_rect,
+        ui_res.title_image.clone(),
+        bevy_keith::ImageScaling::Uniform(2.),
+    );
+
+    let options = MainMenuOption::list(save.data.is_some());
+    for (index, option) in options.iter().enumerate() {
+        let item = MenuItem {
+            text: tr!(loc, option.label()),
+            position: Vec2::new(0., MAIN_MENU_LAYOUT.item_y(index)),
+            bounds: Vec2::new(300., 20.),
+            font_size: 32.,
+            color: Color::WHITE,
+        };
+        highlight.draw_item(&mut ctx, &ui_res, item, index == main_menu.selected_index);
+    }
+
+    // commands.spawn((
+    //     SpriteBundle {
+    //         transform: Transform::from_xyz(player_start.position.x, player_start.position.y, 4.),
+    //         texture: ui_res.cursor_image.clone(),
+    //         ..default()
+    //     },
+    //     TextureAtlas {
+    //         layout: ui_res.cursor_atlas_layout.clone(),
+    //         index: 0,
+    //     },
+    //     TileAnimation::uniform(0, 2, 100),
+    //     Name::new("StartMenuCursor"),
+    // ));
+
+    let cursor = MAIN_MENU_LAYOUT.cursor_position(main_menu.selected_index);
+    draw_menu_cursor(&mut ctx, &ui_res, cursor);
+}
+
+/// Draw the cursor pointing at the selected option of a menu.
+pub fn draw_menu_cursor(ctx: &mut RenderContext, ui_res: &UiRes, position: Vec2) {
+    let cursor_rect = Rect::from_center_size(position, Vec2::splat(48.));
+    match ui_res.cursor_frames.get(ui_res.cursor_frame) {
+        Some(frame) => ctx.draw_image(
+            cursor_rect,
+            frame.clone(),
+            bevy_keith::ImageScaling::Uniform(3.),
+        ),
+        None => ctx.draw_image(
+            cursor_rect,
+            ui_res.cursor_image.clone(),
+            bevy_keith::ImageScaling::Uniform(1.),
+        ),
+    }
+}
+
+/// Animate the menu cursor like the player sprite, in real time such that it
+/// keeps moving while the game is paused.
+fn animate_menu_cursor(
+    time: Res<Time<Real>>,
+    mut ui_res: ResMut<UiRes>,
+    mut images: ResMut<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    if ui_res.cursor_frames.is_empty() {
+        let (Some(image), Some(layout)) = (
+            images.get(&ui_res.cursor_image),
+            layouts.get(&ui_res.cursor_atlas_layout),
+        ) else {
+            return;
+        };
+        // Only the first two frames, like the idle animation of the player
+        let frames: Vec<Image> = layout
+            .textures
+            .iter()
+            .take(2)
+            .map(|rect| slice_image(image, *rect))
+            .collect();
+        ui_res.cursor_frames = frames.into_iter().map(|frame| images.add(frame)).collect();
+    }
+
+    if ui_res.cursor_frames.is_empty() {
+        return;
+    }
+    let frame = (time.elapsed().as_millis() / 100) as usize % ui_res.cursor_frames.len();
+    if ui_res.cursor_frame != frame {
+        ui_res.cursor_frame = frame;
+    }
+}
+
+/// Copy a rectangle of an image into a new image.
+fn slice_image(image: &Image, rect: URect) -> Image {
+    let format = image.texture_descriptor.format;
+    let pixel_size = format.block_copy_size(None).unwrap_or(4) as usize;
+    let row_size = rect.width() as usize * pixel_size;
+    let mut data = Vec::with_capacity(row_size * rect.height() as usize);
+    for y in rect.min.y..rect.max.y {
+        let start = (y * image.width() + rect.min.x) as usize * pixel_size;
+        data.extend_from_slice(&image.data[start..start + row_size]);
+    }
+    Image::new(
+        Extent3d {
+            width: rect.width(),
+            height: rect.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::default(),
+    )
+}
@@ -0,0 +1,325 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_kira_audio::{prelude::*, AudioSource};
+
+use crate::{
+    fade::{FadeAction, FadeEvent},
+    loading::LoadingAssets,
+    sound::MusicChannel,
+    AppState, Enemy, Epoch, LevelMeta, Player, PlayerLife,
+};
+
+/// Duration of the crossfade between two music tracks.
+const CROSSFADE_DURATION: Duration = Duration::from_millis(1500);
+
+/// Duration of the fade in or out of a layer of the gameplay music.
+const LAYER_FADE_DURATION: Duration = Duration::from_millis(2000);
+
+/// Distance from the player under which enemies make the gameplay intense,
+/// in pixels.
+const INTENSITY_RADIUS: f32 = 128.;
+
+/// Background music track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicTrack {
+    Menu,
+    Gameplay,
+    GameOver,
+    Victory,
+}
+
+impl MusicTrack {
+    const ALL: [MusicTrack; 4] = [
+        MusicTrack::Menu,
+        MusicTrack::Gameplay,
+        MusicTrack::GameOver,
+        MusicTrack::Victory,
+    ];
+
+    /// Track played in the given state, or `None` to keep the current one.
+    fn for_state(state: AppState) -> Option<MusicTrack> {
+        match state {
+            AppState::Loading | AppState::Settings => None,
+            AppState::MainMenu | AppState::LevelSelect => Some(MusicTrack::Menu),
+            AppState::InGame | AppState::Paused => Some(MusicTrack::Gameplay),
+            AppState::GameOver => Some(MusicTrack::GameOver),
+            AppState::Victory => Some(MusicTrack::Victory),
+        }
+    }
+
+    /// Playback rate of the track, telling tracks apart while they share the
+    /// same placeholder music.
+    fn playback_rate(&self) -> f64 {
+        match self {
+            MusicTrack::Menu => 0.9,
+            MusicTrack::Gameplay => 1.,
+            MusicTrack::GameOver => 0.7,
+            MusicTrack::Victory => 1.15,
+        }
+    }
+}
+
+/// Layers of the gameplay music, stems looping along the main track which
+/// fade in and out over it to adapt to the gameplay.
+///
+/// All stems have the same length as the main track and start playing in the
+/// same frame as it, so they stay in sync however long they loop.
+struct MusicLayers {
+    /// Stem of each epoch, indexed from the first epoch of the level.
+    epochs: Vec<Handle<AudioInstance>>,
+    /// Stem played while the gameplay is intense, if any.
+    intensity: Option<Handle<AudioInstance>>,
+    /// Index of the epoch stem currently audible, if any.
+    epoch_index: Option<usize>,
+    is_intense: bool,
+}
+
+impl MusicLayers {
+    fn handles(&self) -> impl Iterator<Item = &Handle<AudioInstance>> {
+        self.epochs.iter().chain(&self.intensity)
+    }
+}
+
+/// Music currently playing.
+struct CurrentMusic {
+    track: MusicTrack,
+    source: Handle<AudioSource>,
+    instance: Handle<AudioInstance>,
+    layers: Option<MusicLayers>,
+}
+
+/// Background music, crossfading between tracks when switching from one to
+/// another.
+#[derive(Default, Resource)]
+pub struct MusicManager {
+    /// Default music of each track.
+    tracks: HashMap<MusicTrack, Handle<AudioSource>>,
+    /// Stems of the epoch layers of the default gameplay music. Later epochs
+    /// use the last one.
+    epoch_stems: Vec<Handle<AudioSource>>,
+    /// Stem of the intensity layer of the default gameplay music, if any.
+    intensity_stem: Option<Handle<AudioSource>>,
+    /// Music of the current level, replacing the default gameplay music.
+    level_music: Option<Handle<AudioSource>>,
+    current: Option<CurrentMusic>,
+}
+
+impl MusicManager {
+    /// Track currently playing, if any.
+    pub fn current_track(&self) -> Option<MusicTrack> {
+        self.current.as_ref().map(|current| current.track)
+    }
+
+    /// Music played for the given track.
+    fn source(&self, track: MusicTrack) -> Option<&Handle<AudioSource>> {
+        match (track, &self.level_music) {
+            (MusicTrack::Gameplay, Some(level_music)) => Some(level_music),
+            _ => self.tracks.get(&track),
+        }
+    }
+
+    /// Crossfade to the given track, unless already playing.
+    pub fn play(
+        &mut self,
+        track: MusicTrack,
+        music: &AudioChannel<MusicChannel>,
+        audio_instances: &mut Assets<AudioInstance>,
+    ) {
+        let Some(source) = self.source(track).cloned() else {
+            return;
+        };
+        if let Some(current) = &self.current {
+            if current.track == track && current.source == source {
+                return;
+            }
+        }
+        debug!("Crossfading music to {:?}", track);
+        self.fade_out(CROSSFADE_DURATION, audio_instances);
+        // Level music plays as authored, only default tracks are told apart
+        let playback_rate = if self.level_music.is_some() && track == MusicTrack::Gameplay {
+            1.
+        } else {
+            track.playback_rate()
+        };
+        let instance = music
+            .play(source.clone())
+            .looped()
+            .with_playback_rate(playback_rate)
+            .fade_in(AudioTween::linear(CROSSFADE_DURATION))
+            .handle();
+        // Only the default gameplay music has stems to layer over it, and plays
+        // alone until they exist
+        let has_stems = !self.epoch_stems.is_empty() || self.intensity_stem.is_some();
+        let has_layers = track == MusicTrack::Gameplay && self.level_music.is_none() && has_stems;
+        let layers = has_layers.then(|| {
+            let mut play_stem = |stem: &Handle<AudioSource>| {
+                music.play(stem.clone()).looped().with_volume(0.).handle()
+            };
+            MusicLayers {
+                epochs: self.epoch_stems.iter().map(&mut play_stem).collect(),
+                intensity: self.intensity_stem.as_ref().map(&mut play_stem),
+                epoch_index: None,
+                is_intense: false,
+            }
+        });
+        self.current = Some(CurrentMusic {
+            track,
+            source,
+            instance,
+            layers,
+        });
+    }
+
+    /// Fade in the layers matching the given epoch and intensity of the
+    /// gameplay, and fade out the others.
+    pub fn set_layers(
+        &mut self,
+        epoch_index: usize,
+        is_intense: bool,
+        audio_instances: &mut Assets<AudioInstance>,
+    ) {
+        let Some(layers) = self.current.as_mut().and_then(|c| c.layers.as_mut()) else {
+            return;
+        };
+        let epoch_index = epoch_index.min(layers.epochs.len().saturating_sub(1));
+        let tween = AudioTween::linear(LAYER_FADE_DURATION);
+        let mut fade = |handle: &Handle<AudioInstance>, volume: f64| {
+            if let Some(instance) = audio_instances.get_mut(handle) {
+                instance.set_volume(volume, tween);
+            }
+        };
+        if layers.epoch_index != Some(epoch_index) {
+            debug!("Fading in music layer of epoch #{}", epoch_index);
+            if let Some(previous) = layers.epoch_index.and_then(|i| layers.epochs.get(i)) {
+                fade(previous, 0.);
+            }
+            if let Some(current) = layers.epochs.get(epoch_index) {
+                fade(current, 1.);
+            }
+            layers.epoch_index = Some(epoch_index);
+        }
+        if layers.is_intense != is_intense {
+            if let Some(intensity) = &layers.intensity {
+                fade(intensity, if is_intense { 1. } else { 0. });
+            }
+            layers.is_intense = is_intense;
+        }
+    }
+
+    /// Fade out the current track, if any, over the given duration.
+    pub fn fade_out(&mut self, duration: Duration, audio_instances: &mut Assets<AudioInstance>) {
+        let Some(current) = self.current.take() else {
+            return;
+        };
+        let layers = current.layers.iter().flat_map(MusicLayers::handles);
+        for handle in std::iter::once(&current.instance).chain(layers) {
+            if let Some(instance) = audio_instances.get_mut(handle) {
+                instance.stop(AudioTween::linear(duration));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicManager>()
+            .add_systems(Startup, load_music)
+            .add_systems(
+                Update,
+                (
+                    select_level_music.run_if(resource_exists_and_changed::<LevelMeta>),
+                    play_state_music.run_if(state_changed::<AppState>),
+                    update_music_layers.run_if(in_state(AppState::InGame)),
+                    fade_out_on_exit,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn load_music(
+    asset_server: Res<AssetServer>,
+    mut manager: ResMut<MusicManager>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    // Single placeholder music for all tracks until dedicated ones are made.
+    // The gameplay music has no stems yet, so doesn't adapt to the gameplay.
+    let bgm: Handle<AudioSource> = asset_server.load("bgm1.ogg");
+    loading.track(bgm.clone());
+    manager.tracks = MusicTrack::ALL
+        .into_iter()
+        .map(|track| (track, bgm.clone()))
+        .collect();
+}
+
+/// Adapt the layers of the gameplay music to the current epoch, and to the
+/// intensity of the gameplay, which is high with enemies close to the player
+/// or with low life.
+fn update_music_layers(
+    q_epoch: Query<&Epoch>,
+    q_player: Query<(&Transform, &PlayerLife), With<Player>>,
+    q_enemies: Query<&GlobalTransform, With<Enemy>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut manager: ResMut<MusicManager>,
+) {
+    let (Ok(epoch), Ok((player_transform, player_life))) =
+        (q_epoch.get_single(), q_player.get_single())
+    else {
+        return;
+    };
+    let player_pos = player_transform.translation.xy();
+    let is_intense = player_life.is_low()
+        || q_enemies
+            .iter()
+            .any(|transform| transform.translation().xy().distance(player_pos) < INTENSITY_RADIUS);
+    let epoch_index = (epoch.cur - epoch.min).max(0) as usize;
+    manager.set_layers(epoch_index, is_intense, &mut audio_instances);
+}
+
+/// Use the music declared by the loaded level for gameplay, or the default
+/// gameplay music if it has none, crossfading right away when in game.
+fn select_level_music(
+    asset_server: Res<AssetServer>,
+    level_meta: Res<LevelMeta>,
+    music: Res<AudioChannel<MusicChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut manager: ResMut<MusicManager>,
+) {
+    manager.level_music = level_meta
+        .music
+        .as_ref()
+        .map(|path| asset_server.load(path));
+    if manager.current_track() == Some(MusicTrack::Gameplay) {
+        manager.play(MusicTrack::Gameplay, &music, &mut audio_instances);
+    }
+}
+
+/// Fade out the music along with the screen before quitting, instead of
+/// cutting it abruptly.
+fn fade_out_on_exit(
+    mut ev_fade: EventReader<FadeEvent>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut manager: ResMut<MusicManager>,
+) {
+    for FadeEvent(action) in ev_fade.read() {
+        if *action == FadeAction::Exit {
+            manager.fade_out(action.duration(), &mut audio_instances);
+        }
+    }
+}
+
+/// Crossfade to the track of the new state.
+fn play_state_music(
+    state: Res<State<AppState>>,
+    music: Res<AudioChannel<MusicChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut manager: ResMut<MusicManager>,
+) {
+    if let Some(track) = MusicTrack::for_state(*state.get()) {
+        manager.play(track, &music, &mut audio_instances);
+    }
+}
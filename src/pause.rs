@@ -0,0 +1,270 @@
+use bevy::prelude::*;
+use bevy_keith::{Canvas, ShapeExt};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    fade::{FadeAction, FadeEvent},
+    layout::UiLayout,
+    level::LevelRegistry,
+    locale::{tr, Localizer},
+    menu::{
+        draw_menu_cursor, MenuAction, MenuHighlight, MenuInput, MenuItem, MenuLayout,
+        MenuNavigation,
+    },
+    settings::SettingsMenu,
+    AppState, UiRes,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+    Exit,
+}
+
+impl PauseOption {
+    const ALL: [PauseOption; 5] = [
+        PauseOption::Resume,
+        PauseOption::Restart,
+        PauseOption::Settings,
+        PauseOption::Quit,
+        PauseOption::Exit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PauseOption::Resume => "pause-resume",
+            PauseOption::Restart => "pause-restart",
+            PauseOption::Settings => "pause-settings",
+            PauseOption::Quit => "pause-quit",
+            PauseOption::Exit => "pause-exit",
+        }
+    }
+
+    /// Whether the option loses the progress of the current level, and needs
+    /// to be confirmed.
+    fn needs_confirmation(&self) -> bool {
+        matches!(self, PauseOption::Quit | PauseOption::Exit)
+    }
+}
+
+/// Options of the quit confirmation dialog, with the safe one first so it's
+/// the one selected by default.
+const CONFIRM_OPTIONS: [&str; 2] = ["confirm-no", "confirm-yes"];
+
+const CONFIRM_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-100., 30.), 40., 200.);
+
+const PAUSE_MENU_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-200., -50.), 50., 400.);
+
+#[derive(Default, Resource)]
+struct PauseMenu {
+    pub selected_index: usize,
+    /// Option waiting for the player to confirm it, while the confirmation
+    /// dialog is open.
+    pub confirm: Option<PauseOption>,
+    pub confirm_index: usize,
+}
+
+#[derive(Default)]
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_systems(OnEnter(AppState::Paused), pause)
+            // Stay frozen while in the settings menu opened from the pause menu
+            .add_systems(OnEnter(AppState::InGame), resume)
+            .add_systems(
+                PreUpdate,
+                (
+                    toggle_pause
+                        .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Paused))),
+                    pause_menu_inputs.run_if(in_state(AppState::Paused)),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, ui_pause_menu.run_if(in_state(AppState::Paused)));
+    }
+}
+
+/// Freeze the physics simulation and the game time.
+fn pause(mut rapier_config: ResMut<RapierConfiguration>, mut virtual_time: ResMut<Time<Virtual>>) {
+    rapier_config.physics_pipeline_active = false;
+    virtual_time.pause();
+}
+
+fn resume(mut rapier_config: ResMut<RapierConfiguration>, mut virtual_time: ResMut<Time<Virtual>>) {
+    rapier_config.physics_pipeline_active = true;
+    virtual_time.unpause();
+}
+
+fn toggle_pause(
+    menu_input: MenuInput,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut menu: ResMut<PauseMenu>,
+) {
+    if !menu_input.pause() {
+        return;
+    }
+    match app_state.get() {
+        AppState::InGame => {
+            menu.selected_index = 0;
+            menu.confirm = None;
+            next_app_state.set(AppState::Paused);
+        }
+        // Let the confirmation dialog handle the key to close itself
+        AppState::Paused if menu.confirm.is_none() => next_app_state.set(AppState::InGame),
+        _ => (),
+    }
+}
+
+fn pause_menu_inputs(
+    mut navigation: MenuNavigation,
+    registry: Res<LevelRegistry>,
+    mut menu: ResMut<PauseMenu>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut ev_fade: EventWriter<FadeEvent>,
+) {
+    if let Some(option) = menu.confirm {
+        let action = navigation.navigate(
+            &mut menu.confirm_index,
+            CONFIRM_OPTIONS.len(),
+            &CONFIRM_LAYOUT,
+        );
+        match action {
+            Some(MenuAction::Confirm) if CONFIRM_OPTIONS[menu.confirm_index] == "confirm-yes" => {
+                menu.confirm = None;
+                match option {
+                    PauseOption::Quit => {
+                        ev_fade.send(FadeEvent(FadeAction::SetState(AppState::MainMenu)));
+                    }
+                    PauseOption::Exit => {
+                        ev_fade.send(FadeEvent(FadeAction::Exit));
+                    }
+                    _ => (),
+                }
+            }
+            Some(_) => menu.confirm = None,
+            None => (),
+        }
+        return;
+    }
+
+    let action = navigation.navigate(
+        &mut menu.selected_index,
+        PauseOption::ALL.len(),
+        &PAUSE_MENU_LAYOUT,
+    );
+    let option = PauseOption::ALL[menu.selected_index];
+    if action == Some(MenuAction::Confirm) && option.needs_confirmation() {
+        menu.confirm = Some(option);
+        menu.confirm_index = 0;
+        return;
+    }
+    match action {
+        Some(MenuAction::Confirm) => match option {
+            PauseOption::Resume => app_state.set(AppState::InGame),
+            PauseOption::Restart => {
+                ev_fade.send(FadeEvent(FadeAction::LoadLevel(registry.current)));
+            }
+            PauseOption::Settings => {
+                settings_menu.open(AppState::Paused);
+                app_state.set(AppState::Settings);
+            }
+            PauseOption::Quit | PauseOption::Exit => (),
+        },
+        Some(MenuAction::Cancel) => app_state.set(AppState::InGame),
+        None => (),
+    }
+}
+
+fn ui_pause_menu(
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    menu: Res<PauseMenu>,
+    highlight: MenuHighlight,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    // Dim the frozen game behind the menu
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.6));
+    ctx.fill(layout.screen_rect(), &brush);
+
+    let txt = ctx
+        .new_layout(tr!(loc, "pause-title"))
+        .font(ui_res.font.clone())
+        .font_size(32.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(300., 40.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -150.));
+
+    for (index, option) in PauseOption::ALL.iter().enumerate() {
+        let item = MenuItem {
+            text: tr!(loc, option.label()),
+            position: Vec2::new(0., PAUSE_MENU_LAYOUT.item_y(index)),
+            bounds: Vec2::new(400., 30.),
+            font_size: 24.,
+            color: Color::WHITE,
+        };
+        let is_focused = menu.confirm.is_none() && index == menu.selected_index;
+        highlight.draw_item(&mut ctx, &ui_res, item, is_focused);
+    }
+
+    if menu.confirm.is_none() {
+        let cursor = PAUSE_MENU_LAYOUT.cursor_position(menu.selected_index);
+        draw_menu_cursor(&mut ctx, &ui_res, cursor);
+        return;
+    }
+
+    // Quit confirmation dialog, on top of the menu
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.6));
+    ctx.fill(layout.screen_rect(), &brush);
+    let brush = ctx.solid_brush(Color::BLACK);
+    let border_brush = ctx.solid_brush(Color::WHITE);
+    ctx.fill(Rect::new(-260., -100., 260., 100.), &brush)
+        .border(&border_brush, 2.);
+
+    let txt = ctx
+        .new_layout(tr!(loc, "confirm-quit-title"))
+        .font(ui_res.font.clone())
+        .font_size(24.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Center)
+        .bounds(Vec2::new(480., 30.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -65.));
+    let txt = ctx
+        .new_layout(tr!(loc, "confirm-quit-message"))
+        .font(ui_res.font.clone())
+        .font_size(12.)
+        .color(Color::srgb(1., 0.85, 0.))
+        .alignment(JustifyText::Center)
+        .bounds(Vec2::new(480., 20.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -30.));
+
+    for (index, option) in CONFIRM_OPTIONS.iter().enumerate() {
+        let item = MenuItem {
+            text: tr!(loc, option),
+            position: Vec2::new(0., CONFIRM_LAYOUT.item_y(index)),
+            bounds: Vec2::new(200., 20.),
+            font_size: 20.,
+            color: Color::WHITE,
+        };
+        highlight.draw_item(&mut ctx, &ui_res, item, index == menu.confirm_index);
+    }
+
+    let cursor = CONFIRM_LAYOUT.cursor_position(menu.confirm_index);
+    draw_menu_cursor(&mut ctx, &ui_res, cursor);
+}
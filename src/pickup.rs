@@ -0,0 +1,171 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    checkpoint::Lives,
+    rng::GameRng,
+    score::ScoreEvent,
+    sound::{SfxChannel, SoundVariants},
+    trigger::{TriggerEnter, TriggerKind},
+    ActiveEffects, AppState, LevelEntity, Pickup, Player, PlayerLife,
+};
+
+#[derive(Default, Resource)]
+struct PickupSounds {
+    pub life: Handle<bevy_kira_audio::AudioSource>,
+    pub coin: SoundVariants,
+    pub one_up: Handle<bevy_kira_audio::AudioSource>,
+    pub power_up: Handle<bevy_kira_audio::AudioSource>,
+}
+
+/// Coins collected by the player, in the current level and overall.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct Collectibles {
+    /// Name of the level the per-level counts refer to.
+    pub level: String,
+    pub level_coins: u32,
+    pub total_coins: u32,
+    /// Number of coins in the current level, collected or not.
+    pub level_total_coins: u32,
+    /// Tiled object IDs of the coins already collected in the current level,
+    /// which are not spawned again when the level is reloaded.
+    pub collected: HashSet<u32>,
+    /// Number of secret areas in the current level.
+    pub level_secrets: u32,
+    /// Tiled layer IDs of the secret areas found in the current level.
+    pub secrets: HashSet<u32>,
+}
+
+impl Collectibles {
+    /// Start tracking the given level, resetting the per-level counts if it
+    /// differs from the current one.
+    pub fn enter_level(&mut self, level: &str) {
+        if self.level != level {
+            self.level = level.to_string();
+            self.level_coins = 0;
+            self.collected.clear();
+            self.secrets.clear();
+        }
+    }
+
+    /// Forget everything collected in the current level, to restart it from
+    /// the beginning. Its coins are removed from the total too.
+    pub fn reset_level(&mut self) {
+        self.total_coins -= self.level_coins;
+        self.level_coins = 0;
+        self.collected.clear();
+        self.secrets.clear();
+    }
+
+    pub fn is_collected(&self, id: u32) -> bool {
+        self.collected.contains(&id)
+    }
+
+    /// Mark a secret area as found. Returns `true` if it wasn't already.
+    pub fn find_secret(&mut self, layer: u32) -> bool {
+        self.secrets.insert(layer)
+    }
+
+    pub fn collect_coin(&mut self, id: u32) {
+        if self.collected.insert(id) {
+            self.level_coins += 1;
+            self.total_coins += 1;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PickupSounds>()
+            .init_resource::<Collectibles>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(Update, collect_pickups.run_if(in_state(AppState::InGame)));
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<PickupSounds>) {
+    sounds.life = asset_server.load("select1.ogg");
+    sounds.coin = SoundVariants::new(vec![sounds.life.clone()], 1., 1.);
+    sounds.one_up = sounds.life.clone();
+    sounds.power_up = sounds.life.clone();
+}
+
+/// Spawn a pickup at the given position.
+pub fn spawn_pickup(commands: &mut Commands, position: Vec3, pickup: Pickup) -> Entity {
+    let color = match pickup {
+        Pickup::Life(_) => Color::srgb(0.2, 1., 0.2),
+        Pickup::Coin(_) => Color::srgb(1., 0.85, 0.),
+        Pickup::OneUp => Color::srgb(0.3, 0.6, 1.),
+        Pickup::PowerUp(power_up, _) => power_up.color(),
+    };
+    commands
+        .spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(position),
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(6.)),
+                    ..default()
+                },
+                ..default()
+            },
+            Collider::cuboid(3., 3.),
+            Sensor,
+            pickup,
+            LevelEntity,
+            Name::new("Pickup"),
+        ))
+        .id()
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<PickupSounds>,
+    mut rng: ResMut<GameRng>,
+    mut collectibles: ResMut<Collectibles>,
+    mut lives: ResMut<Lives>,
+    mut q_player: Query<(&mut PlayerLife, &mut ActiveEffects), With<Player>>,
+    q_pickups: Query<&Pickup>,
+    mut ev_enter: EventReader<TriggerEnter>,
+    mut ev_score: EventWriter<ScoreEvent>,
+) {
+    let Ok((mut player_life, mut effects)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Pickup {
+            continue;
+        }
+        let Ok(pickup) = q_pickups.get(ev.trigger) else {
+            continue;
+        };
+        match *pickup {
+            Pickup::Life(amount) => {
+                player_life.heal(time.elapsed(), amount);
+                audio.play(sounds.life.clone());
+            }
+            Pickup::Coin(id) => {
+                collectibles.collect_coin(id);
+                ev_score.send(ScoreEvent::Coin);
+                sounds.coin.play(&audio, &mut rng);
+            }
+            Pickup::OneUp => {
+                lives.count += 1;
+                audio.play(sounds.one_up.clone());
+            }
+            Pickup::PowerUp(power_up, duration) => {
+                debug!("Power-up {:?} for {:?}", power_up, duration);
+                effects.add(power_up, duration);
+                audio.play(sounds.power_up.clone());
+            }
+        }
+        commands.entity(ev.trigger).despawn_recursive();
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    AppState, FallingPlatform, FallingPlatformState, PathFollower, Player, TimedPlatform,
+    Toggleable,
+};
+
+/// Default delay before a falling platform respawns.
+pub const DEFAULT_RESPAWN_DELAY: Duration = Duration::from_secs(3);
+
+/// Amplitude of the shaking of a falling platform about to fall, in pixels.
+const SHAKE_AMPLITUDE: f32 = 1.;
+
+#[derive(Default)]
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                follow_paths,
+                update_falling_platforms,
+                update_timed_platforms,
+            )
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Run the state machine of falling platforms: shake once the player stands on
+/// them, then fall, then respawn.
+fn update_falling_platforms(
+    mut commands: Commands,
+    time: Res<Time>,
+    physics: Res<RapierContext>,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    mut q_platforms: Query<
+        (Entity, &mut FallingPlatform, &mut Transform, &mut Velocity),
+        Without<Player>,
+    >,
+) {
+    let now = time.elapsed();
+    let player = q_player.get_single().ok();
+    for (entity, mut platform, mut transform, mut velocity) in &mut q_platforms {
+        let elapsed = now.saturating_sub(platform.state_start_time);
+        match platform.state {
+            FallingPlatformState::Idle => {
+                let Some((player_entity, player_transform)) = player else {
+                    continue;
+                };
+                // Only trigger when standing on top, not when bumping from below
+                let is_standing = player_transform.translation.y > transform.translation.y
+                    && physics
+                        .contact_pair(player_entity, entity)
+                        .is_some_and(|contact| contact.has_any_active_contact());
+                if is_standing {
+                    debug!("Falling platform {:?} triggered", entity);
+                    platform.state = FallingPlatformState::Shaking;
+                    platform.state_start_time = now;
+                }
+            }
+            FallingPlatformState::Shaking => {
+                if elapsed >= platform.falls_after {
+                    transform.translation = platform.start;
+                    commands.entity(entity).insert(RigidBody::Dynamic);
+                    platform.state = FallingPlatformState::Falling;
+                    platform.state_start_time = now;
+                } else {
+                    let offset = (elapsed.as_secs_f32() * 60.).sin() * SHAKE_AMPLITUDE;
+                    transform.translation = platform.start + Vec3::X * offset;
+                }
+            }
+            FallingPlatformState::Falling => {
+                if elapsed >= platform.respawn_after {
+                    debug!("Falling platform {:?} respawned", entity);
+                    commands.entity(entity).insert(RigidBody::Fixed);
+                    transform.translation = platform.start;
+                    *velocity = Velocity::zero();
+                    platform.state = FallingPlatformState::Idle;
+                    platform.state_start_time = now;
+                }
+            }
+        }
+    }
+}
+
+/// Toggle timed platforms on their cycle, flashing them before they disappear.
+///
+/// The visibility and colliders are updated by the [`Toggleable`] component, like
+/// for switch targets.
+fn update_timed_platforms(
+    time: Res<Time>,
+    mut q_platforms: Query<(&TimedPlatform, &mut Toggleable, Option<&mut Sprite>)>,
+) {
+    let now = time.elapsed();
+    for (platform, mut toggleable, sprite) in &mut q_platforms {
+        let enabled = platform.is_on(now);
+        if toggleable.enabled != enabled {
+            toggleable.enabled = enabled;
+        }
+
+        let Some(mut sprite) = sprite else {
+            continue;
+        };
+        let alpha = if platform.is_warning(now) && (now.as_millis() / 80) % 2 == 1 {
+            0.3
+        } else {
+            1.
+        };
+        if sprite.color.alpha() != alpha {
+            sprite.color.set_alpha(alpha);
+        }
+    }
+}
+
+/// Move entities along their path.
+fn follow_paths(time: Res<Time>, mut q_followers: Query<(&mut PathFollower, &mut Transform)>) {
+    for (mut follower, mut transform) in &mut q_followers {
+        if follower.points.len() < 2 {
+            continue;
+        }
+        let mut pos = transform.translation.xy();
+        let mut step = follower.speed * time.delta_seconds();
+        // Pass through as many points as the step covers, but stop after a full
+        // lap in case all points are at the same position.
+        for _ in 0..follower.points.len() * 2 {
+            let target = follower.points[follower.target];
+            let dist = pos.distance(target);
+            if dist > step {
+                pos += (target - pos) / dist * step;
+                break;
+            }
+            pos = target;
+            step -= dist;
+            follower.advance();
+        }
+        let translation = pos.extend(transform.translation.z);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    camera::is_intro_over,
+    dialogue,
+    replay::{read_player_actions, PlayerActions},
+    rng::GameRng,
+    sound::{AudioAssets, SfxChannel},
+    trigger::{update_triggers, ActiveTriggers, TriggerEnter, TriggerKind},
+    tuning::GameTuning,
+    ActiveEffects, AppState, Damage, GroundMaterial, Ice, LightSource, Player, PlayerBreath,
+    PlayerController, PlayerLife, PlayerStart, PlayerWeapon, TileAnimation, UiRes,
+};
+
+/// Minimum time spent in the air for touching the ground to play the landing
+/// sound.
+const MIN_LANDING_AIR_TIME: Duration = Duration::from_millis(150);
+
+/// Interval between two ticks of the climbing sound.
+const CLIMB_TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Player>()
+            .add_systems(
+                PreUpdate,
+                (
+                    read_player_actions,
+                    player_input
+                        .run_if(dialogue::is_closed)
+                        .run_if(is_intro_over),
+                )
+                    .chain()
+                    .after(update_triggers)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (spawn_player, damage_player).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Spawn the player at the start position of the level once loaded.
+fn spawn_player(
+    mut commands: Commands,
+    q_player_start: Query<&PlayerStart, Added<PlayerStart>>,
+    ui_res: Res<UiRes>,
+) {
+    let Ok(player_start) = q_player_start.get_single() else {
+        return;
+    };
+
+    trace!("Spawning player at {:?}...", player_start.position);
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_xyz(player_start.position.x, player_start.position.y, 4.),
+            texture: ui_res.cursor_image.clone(),
+            ..default()
+        },
+        TextureAtlas {
+            layout: ui_res.cursor_atlas_layout.clone(),
+            index: 0,
+        },
+        TileAnimation::uniform(0, 2, 100),
+        RigidBody::Dynamic,
+        Ccd::enabled(),
+        ExternalImpulse::default(),
+        ExternalForce::default(),
+        ActiveEvents::COLLISION_EVENTS,
+        Collider::ball(7.5),
+        Velocity::zero(),
+        GravityScale(1.),
+        Name::new("Player"),
+        (
+            Player::default(),
+            PlayerController::default(),
+            PlayerLife::default(),
+            PlayerWeapon::default(),
+            PlayerBreath::default(),
+            ActiveEffects::default(),
+            LightSource {
+                radius: 40.,
+                intensity: 1.,
+            },
+        ),
+        Damping::default(),
+    ));
+}
+
+pub fn player_input(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    actions: Res<PlayerActions>,
+    mut player: Query<(
+        Entity,
+        &Player,
+        &PlayerLife,
+        &mut PlayerController,
+        &mut Velocity,
+        &mut GravityScale,
+        &mut ExternalImpulse,
+        &ActiveEffects,
+    )>,
+    physics: Res<RapierContext>,
+    triggers: Res<ActiveTriggers>,
+    q_ice: Query<(), With<Ice>>,
+    q_materials: Query<&GroundMaterial>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<AudioAssets>,
+    mut air_start_time: Local<Duration>,
+    mut next_climb_tick: Local<Duration>,
+) {
+    let Ok((
+        player_entity,
+        player,
+        player_life,
+        mut player_controller,
+        mut velocity,
+        mut gravity_scale,
+        mut impulse,
+        effects,
+    )) = player.get_single_mut()
+    else {
+        return;
+    };
+
+    let mut is_grounded = false;
+    let mut is_on_ice = false;
+
+    for c in physics.contact_pairs_with(player_entity) {
+        let other_entity = if c.collider1() == player_entity {
+            c.collider2()
+        } else {
+            c.collider1()
+        };
+        for m in c.manifolds() {
+            // Ground is on the ceiling when gravity is flipped
+            if m.normal().y * player_controller.gravity_sign() > 0.7 {
+                is_grounded = true;
+                is_on_ice |= q_ice.contains(other_entity);
+                let material = q_materials.get(other_entity).copied().unwrap_or_default();
+                if player_controller.ground_material != material {
+                    player_controller.ground_material = material;
+                }
+                break;
+            }
+        }
+    }
+    if player_controller.is_grounded != is_grounded {
+        player_controller.is_grounded = is_grounded;
+        // Ignore short losses of contact, like when running over tile seams
+        if !is_grounded {
+            *air_start_time = time.elapsed();
+        } else if time.elapsed().saturating_sub(*air_start_time) >= MIN_LANDING_AIR_TIME {
+            audio.play(sounds.land.clone()).with_playback_rate(0.6);
+        }
+    }
+    if player_controller.is_on_ice != is_on_ice {
+        player_controller.is_on_ice = is_on_ice;
+    }
+
+    // If not already on a ladder, check if intersecting one
+    let is_on_ladder = triggers.contains(TriggerKind::Ladder);
+    if !player_controller.is_climbing && (actions.up || actions.down) {
+        if is_on_ladder {
+            player_controller.is_climbing = true;
+            gravity_scale.0 = player_controller.gravity_scale();
+        }
+    } else if player_controller.is_climbing && !is_on_ladder {
+        // Falling from ladder
+        player_controller.is_climbing = false;
+        gravity_scale.0 = player_controller.gravity_scale();
+    }
+
+    let mut dv = Vec2::ZERO;
+    if actions.left {
+        dv.x -= 1.;
+    }
+    if actions.right {
+        dv.x += 1.;
+    }
+    if dv.x != 0. && player_controller.is_facing_left != (dv.x < 0.) {
+        player_controller.is_facing_left = dv.x < 0.;
+    }
+    if is_on_ice {
+        dv.x *= PlayerController::ICE_CONTROL;
+    }
+    if (is_grounded || player_controller.is_climbing || player_controller.is_in_water)
+        && actions.jump
+    {
+        // Swimming strokes are weaker than jumps, but can be repeated
+        let is_swimming = player_controller.is_in_water && !is_grounded;
+        let jump = if is_swimming {
+            tuning.swim_impulse
+        } else {
+            tuning.jump_impulse
+        };
+        dv.y += jump * player_controller.gravity_sign();
+        audio
+            .play(sounds.jump.clone())
+            .with_playback_rate(if is_swimming { 1.2 } else { 1.6 });
+        if player_controller.is_climbing {
+            player_controller.is_climbing = false;
+            gravity_scale.0 = player_controller.gravity_scale();
+        }
+    }
+
+    if player_controller.is_climbing {
+        let mut target_velocity = velocity.linvel;
+        let mut has_input = false;
+        if actions.up {
+            target_velocity.y += tuning.climb_speed;
+            has_input = true;
+        } else if actions.down {
+            target_velocity.y -= tuning.climb_speed;
+            has_input = true;
+        }
+        if actions.left {
+            target_velocity.x -= tuning.climb_strafe_speed;
+            has_input = true;
+        } else if actions.right {
+            target_velocity.x += tuning.climb_strafe_speed;
+            has_input = true;
+        }
+        if !has_input {
+            target_velocity = Vec2::ZERO;
+        } else if time.elapsed() >= *next_climb_tick {
+            audio
+                .play(sounds.climb.clone())
+                .with_playback_rate(2.)
+                .with_volume(0.4);
+            *next_climb_tick = time.elapsed() + CLIMB_TICK_INTERVAL;
+        }
+        let new_vel = target_velocity.clamp_length_max(tuning.max_climb_speed);
+        if new_vel != velocity.linvel {
+            velocity.linvel = new_vel;
+        }
+    }
+
+    // trace!("dv: {:?}", dv);
+
+    let mut dv = dv * player.impulse_factor * effects.speed_factor();
+
+    // If damaged, apply the (gradually fading) damage impulse
+    if let Some(ratio) = player_life.damage_impulse_factor(time.elapsed()) {
+        // warn!(
+        //     "ratio={} dv={:?} dir={:?}",
+        //     ratio,
+        //     dv,
+        //     player_life.last_dmg_dir * tuning.knockback_impulse
+        // );
+        dv = dv.lerp(
+            player_life.last_dmg_dir * tuning.knockback_impulse,
+            1. - ratio,
+        );
+        //warn!("dv={:?}", dv);
+    }
+
+    if dv != impulse.impulse {
+        impulse.impulse = dv;
+    }
+}
+
+fn damage_player(
+    time: Res<Time>,
+    mut q_player: Query<(&Transform, &mut PlayerLife)>,
+    q_damage: Query<(&Damage, &GlobalTransform), Without<PlayerLife>>,
+    mut ev_enter: EventReader<TriggerEnter>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<AudioAssets>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok((player_transform, mut player_life)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Damage {
+            continue;
+        }
+        let Ok((dmg, dmg_transform)) = q_damage.get(ev.trigger) else {
+            continue;
+        };
+        let dir =
+            (player_transform.translation.xy() - dmg_transform.translation().xy()).normalize();
+        let life = player_life.life;
+        player_life.damage(time.elapsed(), dmg.0, dir);
+        if player_life.life < life {
+            sounds.damage.play(&audio, &mut rng);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+
+use crate::{ActiveEffects, AppState, Player, PlayerLife, PowerUp};
+
+#[derive(Default)]
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_effects.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), reset_time_speed);
+    }
+}
+
+/// Count down the active effects, and apply those not handled directly by the
+/// movement and damage systems.
+fn update_effects(
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut q_player: Query<(&mut ActiveEffects, &mut PlayerLife), With<Player>>,
+) {
+    let Ok((mut effects, mut player_life)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    // Durations are in real time, so slowing time down doesn't extend them
+    effects.tick(real_time.delta());
+
+    let speed = if effects.has(PowerUp::SlowTime) {
+        ActiveEffects::SLOW_TIME_SPEED
+    } else {
+        1.
+    };
+    if virtual_time.relative_speed() != speed {
+        virtual_time.set_relative_speed(speed);
+    }
+
+    // The shield makes the player invulnerable for as long as it lasts
+    if let Some(remaining) = effects.remaining(PowerUp::Shield) {
+        let until = virtual_time.elapsed() + remaining.mul_f32(speed);
+        player_life.invulnerable_until = Some(
+            player_life
+                .invulnerable_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+}
+
+fn reset_time_speed(mut virtual_time: ResMut<Time<Virtual>>) {
+    virtual_time.set_relative_speed(1.);
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    enemy::DamageEvent, explosion::ExplosionEvent, replay::PlayerActions, ActiveEffects, AppState,
+    Breakable, Damage, Enemy, Explosive, LevelEntity, Player, PlayerController, PlayerWeapon,
+    Projectile, ProjectileOwner,
+};
+
+/// Speed of the player projectiles, in pixels per second.
+const PROJECTILE_SPEED: f32 = 200.;
+
+/// Duration of the impact effect when a projectile hits something.
+const IMPACT_DURATION: Duration = Duration::from_millis(150);
+
+/// Short-lived visual effect spawned where a projectile hit something.
+#[derive(Component)]
+struct ProjectileImpact {
+    spawn_time: Duration,
+}
+
+#[derive(Default)]
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                player_fire.run_if(crate::dialogue::is_closed),
+                expire_projectiles,
+                projectile_hits,
+                update_impacts,
+            )
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+fn player_fire(
+    mut commands: Commands,
+    time: Res<Time>,
+    actions: Res<PlayerActions>,
+    mut q_player: Query<(
+        &Transform,
+        &PlayerController,
+        &mut PlayerWeapon,
+        &ActiveEffects,
+    )>,
+) {
+    let Ok((transform, controller, mut weapon, effects)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    if !actions.fire || !weapon.can_fire(time.elapsed()) {
+        return;
+    }
+    weapon.last_shot_time = Some(time.elapsed());
+
+    let dir = if controller.is_facing_left { -1. } else { 1. };
+    // Spawn just outside the player collider
+    let position = transform.translation + Vec3::new(dir * 10., 0., 0.);
+    spawn_projectile(
+        &mut commands,
+        position,
+        Vec2::new(dir * PROJECTILE_SPEED, 0.),
+        Projectile {
+            owner: ProjectileOwner::Player,
+            damage: effects.damage_factor(),
+            spawn_time: time.elapsed(),
+            lifetime: Duration::from_secs(2),
+        },
+    );
+}
+
+/// Spawn a projectile moving at the given velocity.
+///
+/// Enemy projectiles also carry a [`Damage`] component, so they hurt the player
+/// through the same path as any other damage sensor.
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    position: Vec3,
+    velocity: Vec2,
+    projectile: Projectile,
+) -> Entity {
+    let color = match projectile.owner {
+        ProjectileOwner::Player => Color::srgb(1., 1., 0.),
+        ProjectileOwner::Enemy => Color::srgb(1., 0.3, 0.3),
+    };
+    let damage = (projectile.owner == ProjectileOwner::Enemy).then_some(Damage(projectile.damage));
+    let mut ent_cmds = commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(velocity.to_angle())),
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(4., 2.)),
+                ..default()
+            },
+            ..default()
+        },
+        RigidBody::KinematicVelocityBased,
+        Velocity::linear(velocity),
+        Collider::cuboid(2., 1.),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+        // Kinematic sensors don't detect fixed colliders by default
+        ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+        projectile,
+        LevelEntity,
+        Name::new("Projectile"),
+    ));
+    if let Some(damage) = damage {
+        ent_cmds.insert(damage);
+    }
+    ent_cmds.id()
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    q_projectiles: Query<(Entity, &Projectile)>,
+) {
+    for (entity, projectile) in &q_projectiles {
+        if time.elapsed() >= projectile.spawn_time + projectile.lifetime {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn projectile_hits(
+    mut commands: Commands,
+    time: Res<Time>,
+    q_projectiles: Query<(&Transform, &Projectile)>,
+    q_player: Query<(), With<Player>>,
+    q_sensors: Query<(), With<Sensor>>,
+    q_enemies: Query<(), With<Enemy>>,
+    q_breakables: Query<(), With<Breakable>>,
+    q_explosives: Query<(&Explosive, &GlobalTransform)>,
+    q_parents: Query<&Parent>,
+    mut events: EventReader<CollisionEvent>,
+    mut ev_damage: EventWriter<DamageEvent>,
+    mut ev_explosion: EventWriter<ExplosionEvent>,
+) {
+    let mut despawned = HashSet::new();
+    for ev in events.read() {
+        let CollisionEvent::Started(e1, e2, _) = ev else {
+            continue;
+        };
+
+        let mut e1 = *e1;
+        let mut e2 = *e2;
+        // Swap entities such that projectile is always #1
+        if q_projectiles.contains(e2) {
+            std::mem::swap(&mut e1, &mut e2);
+        }
+        let Ok((transform, projectile)) = q_projectiles.get(e1) else {
+            continue;
+        };
+
+        // Fly through the shooter's side and any trigger volume
+        let is_player = q_player.contains(e2);
+        let is_enemy = q_enemies.contains(e2);
+        let is_friendly = match projectile.owner {
+            ProjectileOwner::Player => is_player,
+            ProjectileOwner::Enemy => is_enemy,
+        };
+        if is_friendly || q_sensors.contains(e2) {
+            continue;
+        }
+        if despawned.contains(&e1) {
+            continue;
+        }
+
+        if is_enemy {
+            ev_damage.send(DamageEvent {
+                target: e2,
+                amount: projectile.damage,
+            });
+        } else if let Some(breakable) = std::iter::once(e2)
+            .chain(q_parents.iter_ancestors(e2))
+            .find(|e| q_breakables.contains(*e))
+        {
+            debug!("Projectile broke {:?}", breakable);
+            if despawned.insert(breakable) {
+                commands.entity(breakable).despawn_recursive();
+                if let Ok((explosive, transform)) = q_explosives.get(breakable) {
+                    ev_explosion.send(ExplosionEvent {
+                        position: transform.translation().xy(),
+                        radius: explosive.radius,
+                        damage: explosive.damage,
+                        source: Some(breakable),
+                    });
+                }
+            }
+        }
+
+        despawned.insert(e1);
+        commands.entity(e1).despawn_recursive();
+        commands.spawn((
+            SpriteBundle {
+                transform: *transform,
+                sprite: Sprite {
+                    color: Color::srgb(1., 0.8, 0.2),
+                    custom_size: Some(Vec2::splat(4.)),
+                    ..default()
+                },
+                ..default()
+            },
+            ProjectileImpact {
+                spawn_time: time.elapsed(),
+            },
+            Name::new("ProjectileImpact"),
+        ));
+    }
+}
+
+/// Grow and fade out impact effects, then despawn them.
+fn update_impacts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_impacts: Query<(Entity, &ProjectileImpact, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, impact, mut transform, mut sprite) in &mut q_impacts {
+        let delta = time.elapsed().saturating_sub(impact.spawn_time);
+        if delta >= IMPACT_DURATION {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let ratio = delta.div_duration_f32(IMPACT_DURATION);
+        transform.scale = Vec3::splat(1. + ratio * 2.);
+        sprite.color.set_alpha(1. - ratio);
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    input::{Action, ActionInput},
+    level::{LevelRegistry, LoadLevelEvent},
+    rng::GameRng,
+    AppState, Player,
+};
+
+/// Player actions for the current frame, read from the player inputs or from the
+/// replay being played back.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct PlayerActions {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+    /// Jump was pressed this frame.
+    pub jump: bool,
+    pub fire: bool,
+    /// Interact was pressed this frame.
+    pub interact: bool,
+}
+
+impl PlayerActions {
+    pub fn from_input(input: &ActionInput) -> Self {
+        Self {
+            left: input.pressed(Action::Left),
+            right: input.pressed(Action::Right),
+            up: input.pressed(Action::Up),
+            down: input.pressed(Action::Down),
+            jump: input.just_pressed(Action::Jump),
+            fire: input.pressed(Action::Fire),
+            interact: input.just_pressed(Action::Interact),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    Recording,
+    Playing,
+}
+
+/// A single recorded frame.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReplayFrame {
+    /// Real time elapsed since the previous frame.
+    pub delta: Duration,
+    pub actions: PlayerActions,
+}
+
+/// Per-frame player actions, recorded from the start of a level and played
+/// back from the start of that same level.
+///
+/// F2 starts or stops recording, and F4 starts or stops playing back the last
+/// recording. Both restart the current level first, with the random values
+/// seeded the same way.
+///
+/// Playback advances the time by the recorded frame durations instead of the
+/// actual ones, so physics and timers step exactly like during the recording
+/// whatever the frame rate. Playback runs as fast as the game renders, and
+/// there's no attract mode playing a replay from the main menu.
+#[derive(Debug, Default, Resource)]
+pub struct Replay {
+    pub mode: ReplayMode,
+    pub frames: Vec<ReplayFrame>,
+    /// Seed of the [`GameRng`] when the recording started.
+    pub seed: u64,
+    /// Index of the next frame to play back.
+    pub frame: usize,
+    /// Waiting for the level to restart before recording or playing back.
+    is_waiting: bool,
+}
+
+#[derive(Default)]
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerActions>()
+            .init_resource::<Replay>()
+            .add_systems(
+                PreUpdate,
+                toggle_replay
+                    .before(read_player_actions)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn toggle_replay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<LevelRegistry>,
+    mut replay: ResMut<Replay>,
+    mut rng: ResMut<GameRng>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    mut ev_load: EventWriter<LoadLevelEvent>,
+) {
+    let mode = if keyboard.just_pressed(KeyCode::F2) {
+        ReplayMode::Recording
+    } else if keyboard.just_pressed(KeyCode::F4) {
+        ReplayMode::Playing
+    } else {
+        return;
+    };
+
+    if replay.mode == mode {
+        info!("Stopped replay {:?} at frame {}", mode, replay.frames.len());
+        replay.mode = ReplayMode::Off;
+        *time_update = TimeUpdateStrategy::Automatic;
+        return;
+    }
+    if mode == ReplayMode::Playing && replay.frames.is_empty() {
+        warn!("No replay recorded");
+        return;
+    }
+
+    info!("Starting replay {:?}", mode);
+    replay.mode = mode;
+    replay.frame = 0;
+    replay.is_waiting = true;
+    if mode == ReplayMode::Recording {
+        replay.frames.clear();
+        replay.seed = rng.seed();
+        *time_update = TimeUpdateStrategy::Automatic;
+    } else {
+        // Step the restarting level with the duration of the first recorded
+        // frame, so it's the same as during the recording by the time playback starts
+        *time_update = TimeUpdateStrategy::ManualDuration(replay.frames[0].delta);
+    }
+    // Restart the random values along with the level, so they match between
+    // the recording and its playback
+    rng.reseed(replay.seed);
+    ev_load.send(LoadLevelEvent(registry.current));
+}
+
+/// Update the [`PlayerActions`] of this frame, recording them or playing them
+/// back depending on the replay mode.
+pub fn read_player_actions(
+    input: ActionInput,
+    real_time: Res<Time<Real>>,
+    mut actions: ResMut<PlayerActions>,
+    mut replay: ResMut<Replay>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    q_new_player: Query<(), Added<Player>>,
+) {
+    // Start recording or playing back once the restarted level spawned the player
+    if replay.is_waiting {
+        if q_new_player.is_empty() {
+            *actions = PlayerActions::default();
+            return;
+        }
+        replay.is_waiting = false;
+    }
+
+    let new_actions = match replay.mode {
+        ReplayMode::Off => PlayerActions::from_input(&input),
+        ReplayMode::Recording => {
+            let new_actions = PlayerActions::from_input(&input);
+            replay.frames.push(ReplayFrame {
+                delta: real_time.delta(),
+                actions: new_actions,
+            });
+            new_actions
+        }
+        ReplayMode::Playing => match replay.frames.get(replay.frame).copied() {
+            Some(frame) => {
+                replay.frame += 1;
+                // The time of this frame is already updated, so set the
+                // duration of the next one
+                if let Some(next) = replay.frames.get(replay.frame) {
+                    *time_update = TimeUpdateStrategy::ManualDuration(next.delta);
+                }
+                frame.actions
+            }
+            None => {
+                info!("Replay ended after {} frames", replay.frame);
+                replay.mode = ReplayMode::Off;
+                *time_update = TimeUpdateStrategy::Automatic;
+                PlayerActions::from_input(&input)
+            }
+        },
+    };
+    if *actions != new_actions {
+        *actions = new_actions;
+    }
+}
@@ -0,0 +1,515 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::Real};
+use bevy_keith::Canvas;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::Checkpoint,
+    fade::{FadeAction, FadeEvent},
+    level::LevelRegistry,
+    locale::{tr, Localizer},
+    menu::{draw_menu_cursor, MenuAction, MenuHighlight, MenuItem, MenuLayout, MenuNavigation},
+    pickup::Collectibles,
+    score::Score,
+    stats::RunStats,
+    timer::{format_time, LevelTimer},
+    AppState, LevelMeta, UiRes,
+};
+
+/// Results of the last completed level, shown on the victory screen.
+#[derive(Debug, Default, Resource)]
+pub struct LevelResults {
+    pub level: String,
+    pub time: Duration,
+    /// Best time before this run, if the level was already completed.
+    pub previous_best: Option<Duration>,
+    pub par_time: Option<Duration>,
+    pub coins: u32,
+    /// Number of coins in the level.
+    pub level_coins: u32,
+    pub secrets: u32,
+    pub level_secrets: u32,
+    pub deaths: u32,
+    /// Best rank before this run, if the level was already completed.
+    pub previous_rank: Option<Rank>,
+    /// Number of times the player died in the level, over all attempts.
+    pub total_deaths: u32,
+    /// Index in the [`LevelRegistry`] of the level to play next, if any.
+    pub next_level: Option<usize>,
+}
+
+impl LevelResults {
+    /// Number of lines tallied one after the other, including the rank.
+    fn tally_line_count(&self) -> u32 {
+        if self.level_secrets > 0 {
+            6
+        } else {
+            5
+        }
+    }
+
+    fn options(&self) -> Vec<VictoryOption> {
+        let mut options = vec![VictoryOption::Retry, VictoryOption::MainMenu];
+        if self.next_level.is_some() {
+            options.insert(0, VictoryOption::NextLevel);
+        }
+        options
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VictoryOption {
+    NextLevel,
+    Retry,
+    MainMenu,
+}
+
+impl VictoryOption {
+    fn label(&self) -> &'static str {
+        match self {
+            VictoryOption::NextLevel => "victory-next-level",
+            VictoryOption::Retry => "victory-retry",
+            VictoryOption::MainMenu => "victory-main-menu",
+        }
+    }
+}
+
+/// Maximum number of section times listed on the victory screen.
+const MAX_SECTIONS_SHOWN: usize = 3;
+
+const VICTORY_MENU_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-200., 120.), 50., 400.);
+
+#[derive(Default, Resource)]
+struct VictoryMenu {
+    pub selected_index: usize,
+    /// Time the results started being tallied, in real time.
+    pub start_time: Duration,
+}
+
+/// Duration of the tally of each line of the results.
+const TALLY_LINE_DURATION: Duration = Duration::from_millis(400);
+
+/// Sequence of results lines appearing and counting up one after the other.
+struct Tally {
+    elapsed: Duration,
+    line_count: u32,
+}
+
+impl Tally {
+    fn new(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            line_count: 0,
+        }
+    }
+
+    /// Progress in \[0:1\] of the tally of the next line.
+    fn next_line(&mut self) -> f32 {
+        let start = TALLY_LINE_DURATION * self.line_count;
+        self.line_count += 1;
+        self.elapsed
+            .saturating_sub(start)
+            .div_duration_f32(TALLY_LINE_DURATION)
+            .min(1.)
+    }
+
+    /// Progress of the tally of the next line, or `None` if that line didn't
+    /// start yet.
+    fn next_visible_line(&mut self) -> Option<f32> {
+        let is_visible = self.elapsed >= TALLY_LINE_DURATION * self.line_count;
+        let progress = self.next_line();
+        is_visible.then_some(progress)
+    }
+}
+
+/// Value shown while counting up to `value`.
+fn count_up(value: u32, progress: f32) -> u32 {
+    (value as f32 * progress).round() as u32
+}
+
+/// Letter rank of a completed level, from the time, the collectibles, and the
+/// deaths. Ranks are declared from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Rank {
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Rank {
+    fn from_results(results: &LevelResults) -> Self {
+        let ratio = |count: u32, total: u32| {
+            if total > 0 {
+                count as f32 / total as f32
+            } else {
+                1.
+            }
+        };
+        let time = match results.par_time {
+            Some(par_time) if results.time > par_time => par_time.div_duration_f32(results.time),
+            _ => 1.,
+        };
+        let coins = ratio(results.coins, results.level_coins);
+        let secrets = ratio(results.secrets, results.level_secrets);
+        let deaths = 1. / (1. + results.deaths as f32);
+        match (time + coins + secrets + deaths) / 4. {
+            r if r >= 0.95 => Rank::S,
+            r if r >= 0.8 => Rank::A,
+            r if r >= 0.6 => Rank::B,
+            r if r >= 0.4 => Rank::C,
+            _ => Rank::D,
+        }
+    }
+
+    pub fn letter(&self) -> &'static str {
+        match self {
+            Rank::S => "S",
+            Rank::A => "A",
+            Rank::B => "B",
+            Rank::C => "C",
+            Rank::D => "D",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Rank::S => Color::srgb(1., 0.85, 0.),
+            Rank::A => Color::srgb(0.3, 1., 0.3),
+            Rank::B => Color::srgb(0.4, 0.7, 1.),
+            Rank::C => Color::WHITE,
+            Rank::D => Color::srgb(0.6, 0.6, 0.6),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelResults>()
+            .init_resource::<VictoryMenu>()
+            .add_systems(OnEnter(AppState::Victory), record_results)
+            .add_systems(
+                PreUpdate,
+                victory_inputs.run_if(in_state(AppState::Victory)),
+            )
+            .add_systems(Update, ui_victory.run_if(in_state(AppState::Victory)));
+    }
+}
+
+/// Record the results of the level just completed, keeping the best ones in
+/// the record of the level, and unlock the next one.
+fn record_results(
+    time: Res<Time<Real>>,
+    level_meta: Option<Res<LevelMeta>>,
+    collectibles: Res<Collectibles>,
+    checkpoint: Res<Checkpoint>,
+    mut level_timer: ResMut<LevelTimer>,
+    mut stats: ResMut<RunStats>,
+    mut registry: ResMut<LevelRegistry>,
+    mut results: ResMut<LevelResults>,
+    mut menu: ResMut<VictoryMenu>,
+) {
+    let level_meta = level_meta.map(|meta| meta.clone()).unwrap_or_default();
+
+    level_timer.finish();
+    stats.split(level_timer.elapsed);
+
+    let next_level = registry.next_index(level_meta.next_level.as_deref());
+    registry.complete_current(next_level);
+    let record = registry.current_record_mut().cloned().unwrap_or_default();
+    let is_new_best = match record.best_time {
+        Some(best) => level_timer.elapsed < best,
+        None => true,
+    };
+    if is_new_best {
+        info!("New best time: {}", format_time(level_timer.elapsed));
+    }
+
+    *results = LevelResults {
+        level: level_meta.name,
+        time: level_timer.elapsed,
+        previous_best: record.best_time,
+        par_time: level_meta.par_time,
+        coins: collectibles.level_coins,
+        level_coins: collectibles.level_total_coins,
+        secrets: collectibles.secrets.len() as u32,
+        level_secrets: collectibles.level_secrets,
+        deaths: checkpoint.level_deaths,
+        previous_rank: record.best_rank,
+        total_deaths: record.deaths,
+        next_level,
+    };
+    if let Some(record) = registry.current_record_mut() {
+        record.complete(&results, Rank::from_results(&results));
+    }
+    menu.selected_index = 0;
+    menu.start_time = time.elapsed();
+}
+
+fn victory_inputs(
+    time: Res<Time<Real>>,
+    mut navigation: MenuNavigation,
+    registry: Res<LevelRegistry>,
+    results: Res<LevelResults>,
+    mut menu: ResMut<VictoryMenu>,
+    mut ev_fade: EventWriter<FadeEvent>,
+) {
+    // Confirming while tallying skips to the final results instead
+    let tally_duration = TALLY_LINE_DURATION * results.tally_line_count();
+    if time.elapsed().saturating_sub(menu.start_time) < tally_duration {
+        if navigation.input.confirm() {
+            menu.start_time = Duration::ZERO;
+        }
+        return;
+    }
+
+    let options = results.options();
+    let action = navigation.navigate(
+        &mut menu.selected_index,
+        options.len(),
+        &VICTORY_MENU_LAYOUT,
+    );
+    if action == Some(MenuAction::Confirm) {
+        match options.get(menu.selected_index) {
+            Some(VictoryOption::NextLevel) => {
+                if let Some(next_level) = results.next_level {
+                    ev_fade.send(FadeEvent(FadeAction::LoadLevel(next_level)));
+                }
+            }
+            Some(VictoryOption::Retry) => {
+                ev_fade.send(FadeEvent(FadeAction::LoadLevel(registry.current)));
+            }
+            Some(VictoryOption::MainMenu) => {
+                ev_fade.send(FadeEvent(FadeAction::SetState(AppState::MainMenu)));
+            }
+            None => (),
+        }
+    }
+}
+
+fn ui_victory(
+    time: Res<Time<Real>>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    results: Res<LevelResults>,
+    score: Res<Score>,
+    run_stats: Res<RunStats>,
+    menu: Res<VictoryMenu>,
+    highlight: MenuHighlight,
+    loc: Localizer,
+) {
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.7));
+    ctx.fill(Rect::new(-360., -300., 360., 320.), &brush);
+
+    let txt = ctx
+        .new_layout(tr!(loc, "victory-title"))
+        .font(ui_res.font.clone())
+        .font_size(32.)
+        .color(Color::srgb(1., 0.85, 0.))
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(600., 40.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -250.));
+
+    let txt = ctx
+        .new_layout(loc.text(&results.level))
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(600., 20.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -200.));
+
+    // Tally of the results, counting up one line after the other
+    let elapsed = time.elapsed().saturating_sub(menu.start_time);
+    let mut tally = Tally::new(elapsed);
+
+    let progress = tally.next_line();
+    let shown_time = results.time.mul_f32(progress);
+    let time_text = match results.par_time {
+        Some(par_time) => tr!(
+            loc,
+            "victory-time-par",
+            time = format_time(shown_time),
+            par = format_time(par_time)
+        ),
+        None => tr!(loc, "victory-time", time = format_time(shown_time)),
+    };
+    let time_color = match results.par_time {
+        Some(par_time) if shown_time > par_time => Color::srgb(1., 0.3, 0.3),
+        _ => Color::WHITE,
+    };
+    let txt = ctx
+        .new_layout(time_text)
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(time_color)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(600., 20.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -150.));
+
+    // Time compared to the previous best, once tallied
+    if progress >= 1. {
+        let (best_text, best_color) = match results.previous_best {
+            Some(best) if results.time < best => (
+                tr!(loc, "victory-new-best", best = format_time(best)),
+                Color::srgb(0.3, 1., 0.3),
+            ),
+            Some(best) => (
+                tr!(loc, "victory-best", best = format_time(best)),
+                Color::WHITE,
+            ),
+            None => (tr!(loc, "victory-first-clear"), Color::srgb(0.3, 1., 0.3)),
+        };
+        let txt = ctx
+            .new_layout(best_text)
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(best_color)
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(600., 20.))
+            .build();
+        ctx.draw_text(txt, Vec2::new(0., -120.));
+    }
+
+    // Collectibles, deaths and score
+    let mut stats = vec![];
+    if let Some(progress) = tally.next_visible_line() {
+        stats.push(tr!(
+            loc,
+            "victory-coins",
+            count = count_up(results.coins, progress),
+            total = results.level_coins
+        ));
+    }
+    if results.level_secrets > 0 {
+        if let Some(progress) = tally.next_visible_line() {
+            stats.push(tr!(
+                loc,
+                "hud-secrets",
+                found = count_up(results.secrets, progress),
+                total = results.level_secrets
+            ));
+        }
+    }
+    if let Some(progress) = tally.next_visible_line() {
+        stats.push(tr!(
+            loc,
+            "hud-deaths",
+            count = count_up(results.deaths, progress)
+        ));
+    }
+    if let Some(progress) = tally.next_visible_line() {
+        stats.push(tr!(
+            loc,
+            "hud-score",
+            points = count_up(score.points, progress)
+        ));
+    }
+    let txt = ctx
+        .new_layout(stats.join("\n"))
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(290., 120.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(-155., -20.));
+
+    // Rank, stamped once everything is tallied
+    if let Some(progress) = tally.next_visible_line() {
+        let rank = Rank::from_results(&results);
+        let txt = ctx
+            .new_layout(tr!(loc, "victory-rank"))
+            .font(ui_res.font.clone())
+            .font_size(12.)
+            .color(Color::WHITE)
+            .alignment(JustifyText::Center)
+            .bounds(Vec2::new(120., 20.))
+            .build();
+        ctx.draw_text(txt, Vec2::new(270., -250.));
+        let scale = 1. + (1. - progress) * 2.;
+        let txt = ctx
+            .new_layout(rank.letter())
+            .font(ui_res.font.clone())
+            .font_size(64. * scale)
+            .color(rank.color().with_alpha(progress))
+            .alignment(JustifyText::Center)
+            .bounds(Vec2::new(120., 64.) * scale)
+            .build();
+        ctx.draw_text(txt, Vec2::new(270., -190.));
+
+        if let Some(previous_rank) = results.previous_rank.filter(|_| progress >= 1.) {
+            let txt = ctx
+                .new_layout(tr!(loc, "victory-best-rank", rank = previous_rank.letter()))
+                .font(ui_res.font.clone())
+                .font_size(12.)
+                .color(Color::WHITE)
+                .alignment(JustifyText::Center)
+                .bounds(Vec2::new(120., 20.))
+                .build();
+            ctx.draw_text(txt, Vec2::new(270., -140.));
+        }
+    }
+
+    // Statistics of the whole run, and time of each section of the level
+    let mut lines = vec![
+        tr!(loc, "stats-run-deaths", count = run_stats.deaths),
+        tr!(loc, "stats-level-deaths", count = results.total_deaths),
+        tr!(loc, "stats-damage", amount = run_stats.damage_taken.round()),
+        tr!(loc, "stats-epoch-changes", count = run_stats.epoch_changes),
+    ];
+    if run_stats.sections.len() > 1 {
+        lines.extend(
+            run_stats
+                .sections
+                .iter()
+                .take(MAX_SECTIONS_SHOWN)
+                .enumerate()
+                .map(|(index, time)| {
+                    tr!(
+                        loc,
+                        "stats-section",
+                        index = index + 1,
+                        time = format_time(*time)
+                    )
+                }),
+        );
+    }
+    let txt = ctx
+        .new_layout(lines.join("\n"))
+        .font(ui_res.font.clone())
+        .font_size(14.)
+        .color(Color::srgb(0.8, 0.8, 0.8))
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(290., 120.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(155., -20.));
+
+    // Options
+    for (index, option) in results.options().iter().enumerate() {
+        let item = MenuItem {
+            text: tr!(loc, option.label()),
+            position: Vec2::new(0., VICTORY_MENU_LAYOUT.item_y(index)),
+            bounds: Vec2::new(300., 30.),
+            font_size: 24.,
+            color: Color::WHITE,
+        };
+        highlight.draw_item(&mut ctx, &ui_res, item, index == menu.selected_index);
+    }
+
+    let cursor = VICTORY_MENU_LAYOUT.cursor_position(menu.selected_index);
+    draw_menu_cursor(&mut ctx, &ui_res, cursor);
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+use crate::cli::StartupArgs;
+
+/// Source of all the randomness of the game, seeded so a run can be
+/// reproduced, like when playing back a replay.
+///
+/// The seed comes from the `--seed` command-line option, or is random, and is
+/// logged at startup either way.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restart the sequence of random values from the given seed.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[derive(Default)]
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = app
+            .world()
+            .get_resource::<StartupArgs>()
+            .and_then(|args| args.seed)
+            .unwrap_or_else(rand::random);
+        info!("Random seed: {}", seed);
+        app.insert_resource(GameRng::new(seed));
+    }
+}
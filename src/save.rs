@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::{Checkpoint, Lives, RespawnEvent},
+    level::{LevelRecord, LevelRegistry, LoadLevelEvent},
+    pickup::Collectibles,
+    score::Score,
+    stats::RunStats,
+    storage, AppState, LevelMeta, Player,
+};
+
+/// Storage key of the progress.
+const SAVE_FILE: &str = "save.ron";
+
+/// Progress of the game, to continue it from the main menu.
+#[derive(Debug, Clone)]
+pub struct SaveData {
+    /// Index of the level in the [`LevelRegistry`].
+    pub level: usize,
+    pub checkpoint: Checkpoint,
+    pub collectibles: Collectibles,
+    pub lives: u32,
+    pub stats: RunStats,
+}
+
+/// Last saved progress, updated as the player progresses through levels.
+#[derive(Debug, Default, Resource)]
+pub struct SaveGame {
+    pub data: Option<SaveData>,
+    /// Restore the saved progress once its level is loaded.
+    is_restoring: bool,
+    /// Move the player to the saved checkpoint once spawned.
+    is_respawning: bool,
+}
+
+impl SaveGame {
+    /// Start continuing the saved game, returning the index of the level to
+    /// load for it.
+    pub fn continue_game(&mut self) -> Option<usize> {
+        let level = self.data.as_ref()?.level;
+        self.is_restoring = true;
+        Some(level)
+    }
+}
+
+/// Progress saved to disk between sessions, as RON.
+///
+/// Levels are referenced by asset path rather than by index, so saves survive
+/// levels being added to the [`LevelRegistry`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SaveFile {
+    /// Asset path of the level to continue, if any.
+    level: Option<String>,
+    /// Name of the level, as tracked by the checkpoint and collectibles.
+    level_name: String,
+    checkpoint_position: Option<[f32; 3]>,
+    epoch: Option<i32>,
+    level_deaths: u32,
+    level_coins: u32,
+    total_coins: u32,
+    collected: Vec<u32>,
+    secrets: Vec<u32>,
+    lives: u32,
+    deaths: u32,
+    damage_taken: f32,
+    epoch_changes: u32,
+    /// Record of each level, by asset path.
+    records: BTreeMap<String, LevelRecord>,
+    /// Asset paths of the levels unlocked in the level select menu.
+    unlocked_levels: Vec<String>,
+    completed_levels: Vec<String>,
+    /// Best score of all sessions.
+    high_score: u32,
+}
+
+impl SaveFile {
+    fn new(data: &SaveData, registry: &LevelRegistry, score: &Score) -> Self {
+        Self {
+            level: registry
+                .levels
+                .get(data.level)
+                .map(|level| level.path.clone()),
+            level_name: data.checkpoint.level.clone(),
+            checkpoint_position: data.checkpoint.position.map(|pos| pos.to_array()),
+            epoch: data.checkpoint.epoch,
+            level_deaths: data.checkpoint.level_deaths,
+            level_coins: data.collectibles.level_coins,
+            total_coins: data.collectibles.total_coins,
+            collected: data.collectibles.collected.iter().copied().collect(),
+            secrets: data.collectibles.secrets.iter().copied().collect(),
+            lives: data.lives,
+            deaths: data.stats.deaths,
+            damage_taken: data.stats.damage_taken,
+            epoch_changes: data.stats.epoch_changes,
+            records: registry
+                .levels
+                .iter()
+                .map(|level| (level.path.clone(), level.record.clone()))
+                .collect(),
+            unlocked_levels: registry
+                .levels
+                .iter()
+                .filter(|level| level.is_unlocked)
+                .map(|level| level.path.clone())
+                .collect(),
+            completed_levels: registry
+                .levels
+                .iter()
+                .filter(|level| level.is_completed)
+                .map(|level| level.path.clone())
+                .collect(),
+            high_score: score.high_score,
+        }
+    }
+
+    /// Restore the records and unlocked levels in the registry, and return the
+    /// progress to continue, if its level is still registered.
+    fn apply(mut self, registry: &mut LevelRegistry) -> Option<SaveData> {
+        for level in &mut registry.levels {
+            if let Some(record) = self.records.remove(&level.path) {
+                level.record = record;
+            }
+            if self.unlocked_levels.contains(&level.path) {
+                level.is_unlocked = true;
+            }
+            if self.completed_levels.contains(&level.path) {
+                level.is_completed = true;
+            }
+        }
+
+        let path = self.level?;
+        let Some(level) = registry.levels.iter().position(|level| level.path == path) else {
+            warn!("Ignoring saved progress in unknown level '{}'", path);
+            return None;
+        };
+        Some(SaveData {
+            level,
+            checkpoint: Checkpoint {
+                level: self.level_name.clone(),
+                position: self.checkpoint_position.map(Vec3::from_array),
+                epoch: self.epoch,
+                level_deaths: self.level_deaths,
+            },
+            collectibles: Collectibles {
+                level: self.level_name,
+                level_coins: self.level_coins,
+                total_coins: self.total_coins,
+                collected: self.collected.into_iter().collect(),
+                secrets: self.secrets.into_iter().collect(),
+                ..default()
+            },
+            lives: self.lives,
+            stats: RunStats {
+                deaths: self.deaths,
+                damage_taken: self.damage_taken,
+                epoch_changes: self.epoch_changes,
+                ..default()
+            },
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveGame>()
+            .add_systems(Startup, load_save)
+            .add_systems(Update, restore_save.after(crate::level::load_level))
+            .add_systems(
+                Update,
+                (
+                    (
+                        respawn_at_checkpoint,
+                        record_save.run_if(
+                            resource_changed::<Checkpoint>
+                                .or_else(resource_changed::<Collectibles>),
+                        ),
+                    )
+                        .run_if(in_state(AppState::InGame)),
+                    write_save.run_if(
+                        resource_changed::<Checkpoint>
+                            .or_else(resource_changed::<LevelRegistry>)
+                            .or_else(
+                                in_state(AppState::GameOver).and_then(state_changed::<AppState>),
+                            ),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Load the progress saved in a previous session, if any.
+fn load_save(
+    mut save: ResMut<SaveGame>,
+    mut registry: ResMut<LevelRegistry>,
+    mut score: ResMut<Score>,
+) {
+    match storage::load::<SaveFile>(SAVE_FILE) {
+        Ok(Some(file)) => {
+            score.high_score = file.high_score;
+            save.data = file.apply(&mut registry);
+            info!("Loaded saved progress from '{}'", SAVE_FILE);
+        }
+        Ok(None) => (),
+        Err(err) => warn!("Ignoring saved progress '{}': {}", SAVE_FILE, err),
+    }
+}
+
+/// Write the progress to disk when the player reaches a checkpoint or
+/// completes a level, which unlocks the next one, and on game over to keep any
+/// new high score.
+fn write_save(save: Res<SaveGame>, registry: Res<LevelRegistry>, score: Res<Score>) {
+    // Nothing to save before the game started
+    let Some(data) = &save.data else {
+        return;
+    };
+    let file = SaveFile::new(data, &registry, &score);
+    if let Err(err) = storage::save(SAVE_FILE, &file) {
+        warn!("Failed to save progress to '{}': {}", SAVE_FILE, err);
+    }
+}
+
+/// Restore the checkpoint, collectibles, lives and stats of the saved game
+/// after its level started loading, which resets them.
+fn restore_save(
+    mut save: ResMut<SaveGame>,
+    mut checkpoint: ResMut<Checkpoint>,
+    mut collectibles: ResMut<Collectibles>,
+    mut lives: ResMut<Lives>,
+    mut stats: ResMut<RunStats>,
+    mut events: EventReader<LoadLevelEvent>,
+) {
+    let Some(LoadLevelEvent(index)) = events.read().last().copied() else {
+        return;
+    };
+    if !save.is_restoring {
+        return;
+    }
+    save.is_restoring = false;
+    let Some(data) = save.data.clone().filter(|data| data.level == index) else {
+        return;
+    };
+
+    info!("Continuing saved game in level #{}", index);
+    *checkpoint = data.checkpoint;
+    *collectibles = data.collectibles;
+    if data.lives > 0 {
+        lives.count = data.lives;
+    }
+    stats.deaths = data.stats.deaths;
+    stats.damage_taken = data.stats.damage_taken;
+    stats.epoch_changes = data.stats.epoch_changes;
+    save.is_respawning = true;
+}
+
+fn respawn_at_checkpoint(
+    mut save: ResMut<SaveGame>,
+    q_player: Query<(), Added<Player>>,
+    mut ev_respawn: EventWriter<RespawnEvent>,
+) {
+    if save.is_respawning && !q_player.is_empty() {
+        save.is_respawning = false;
+        ev_respawn.send(RespawnEvent);
+    }
+}
+
+/// Save the progress whenever the player reaches a checkpoint or collects
+/// something.
+fn record_save(
+    mut save: ResMut<SaveGame>,
+    level_meta: Option<Res<LevelMeta>>,
+    registry: Res<LevelRegistry>,
+    checkpoint: Res<Checkpoint>,
+    collectibles: Res<Collectibles>,
+    lives: Res<Lives>,
+    stats: Res<RunStats>,
+) {
+    // Wait for the level to be loaded, and for the restored progress to apply
+    if save.is_respawning || level_meta.is_none() {
+        return;
+    }
+    save.data = Some(SaveData {
+        level: registry.current,
+        checkpoint: checkpoint.clone(),
+        collectibles: collectibles.clone(),
+        lives: lives.count,
+        stats: stats.clone(),
+    });
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{timer::LevelTimer, AppState, LevelMeta};
+
+/// Base points for collecting a coin.
+const COIN_POINTS: u32 = 10;
+
+/// Base points for killing an enemy.
+const ENEMY_POINTS: u32 = 100;
+
+/// Base points for killing a boss.
+const BOSS_POINTS: u32 = 1000;
+
+/// Points per second left under the par time when completing a level.
+const TIME_BONUS_POINTS: u32 = 50;
+
+/// Maximum delay between two scoring actions to keep the combo going.
+const COMBO_WINDOW: Duration = Duration::from_secs(2);
+
+/// Maximum combo multiplier.
+const MAX_MULTIPLIER: u32 = 5;
+
+/// Scoring action of the player.
+#[derive(Debug, Clone, Copy, Event)]
+pub enum ScoreEvent {
+    Coin,
+    EnemyKill { is_boss: bool },
+    LevelComplete,
+}
+
+/// Score of the current game, with a combo multiplier rewarding quick
+/// successive actions.
+#[derive(Debug, Default, Resource)]
+pub struct Score {
+    pub points: u32,
+    /// Best score reached, kept in the save between sessions.
+    pub high_score: u32,
+    /// Number of scoring actions in the current combo.
+    pub combo: u32,
+    pub last_score_time: Option<Duration>,
+}
+
+impl Score {
+    /// Current combo multiplier, increasing every 3 actions in a row.
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.combo / 3).min(MAX_MULTIPLIER)
+    }
+
+    /// Get the remaining time ratio in \[0:1\] before the combo ends, if any.
+    pub fn combo_ratio(&self, time: Duration) -> Option<f32> {
+        if self.combo == 0 {
+            return None;
+        }
+        let delta = time.checked_sub(self.last_score_time?)?;
+        (delta < COMBO_WINDOW).then(|| 1. - delta.div_duration_f32(COMBO_WINDOW))
+    }
+
+    /// Add some base points, scaled by the current multiplier, and extend the
+    /// combo.
+    pub fn add(&mut self, time: Duration, points: u32) {
+        self.combo += 1;
+        self.last_score_time = Some(time);
+        self.points += points * self.multiplier();
+        self.high_score = self.high_score.max(self.points);
+    }
+}
+
+#[derive(Default)]
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .add_event::<ScoreEvent>()
+            // Also run on the frame the level ends, for the completion bonus
+            .add_systems(Update, update_score)
+            .add_systems(Update, decay_combo.run_if(in_state(AppState::InGame)));
+    }
+}
+
+fn update_score(
+    time: Res<Time>,
+    level_meta: Option<Res<LevelMeta>>,
+    level_timer: Res<LevelTimer>,
+    mut score: ResMut<Score>,
+    mut events: EventReader<ScoreEvent>,
+) {
+    for ev in events.read() {
+        let points = match *ev {
+            ScoreEvent::Coin => COIN_POINTS,
+            ScoreEvent::EnemyKill { is_boss: false } => ENEMY_POINTS,
+            ScoreEvent::EnemyKill { is_boss: true } => BOSS_POINTS,
+            ScoreEvent::LevelComplete => {
+                let Some(par_time) = level_meta.as_ref().and_then(|meta| meta.par_time) else {
+                    continue;
+                };
+                let time_left = par_time.saturating_sub(level_timer.elapsed);
+                time_left.as_secs() as u32 * TIME_BONUS_POINTS
+            }
+        };
+        if points > 0 {
+            score.add(time.elapsed(), points);
+            debug!(
+                "Score +{} x{} => {}",
+                points,
+                score.multiplier(),
+                score.points
+            );
+        }
+    }
+}
+
+/// End the combo once the player stops scoring for a while.
+fn decay_combo(time: Res<Time>, mut score: ResMut<Score>) {
+    if score.combo > 0 && score.combo_ratio(time.elapsed()).is_none() {
+        score.combo = 0;
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_ecs_tilemap::prelude::*;
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{pickup::Collectibles, sound::SfxChannel, AppState, Player, SecretArea, SecretLayer};
+
+/// Speed at which secret layers fade in and out, in opacity per second.
+const FADE_SPEED: f32 = 4.;
+
+#[derive(Default, Resource)]
+struct SecretSounds {
+    pub found: Handle<bevy_kira_audio::AudioSource>,
+}
+
+#[derive(Default)]
+pub struct SecretPlugin;
+
+impl Plugin for SecretPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SecretSounds>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(Update, reveal_secrets.run_if(in_state(AppState::InGame)));
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut sounds: ResMut<SecretSounds>) {
+    sounds.found = asset_server.load("select1.ogg");
+}
+
+/// Fade out secret layers while the player is inside their area, and fade them
+/// back in once it leaves.
+fn reveal_secrets(
+    time: Res<Time>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<SecretSounds>,
+    physics: Res<RapierContext>,
+    mut collectibles: ResMut<Collectibles>,
+    q_player: Query<Entity, With<Player>>,
+    q_areas: Query<&SecretArea>,
+    mut q_layers: Query<(&mut SecretLayer, &TileStorage)>,
+    mut q_tiles: Query<&mut TileColor>,
+) {
+    let Ok(player_entity) = q_player.get_single() else {
+        return;
+    };
+
+    let revealed: HashSet<u32> = physics
+        .intersection_pairs_with(player_entity)
+        .filter(|(_, _, intersecting)| *intersecting)
+        .filter_map(|(e1, e2, _)| {
+            let other_entity = if e1 == player_entity { e2 } else { e1 };
+            q_areas.get(other_entity).ok().map(|area| area.layer)
+        })
+        .collect();
+
+    for id in &revealed {
+        if collectibles.find_secret(*id) {
+            info!(
+                "Secret found! ({}/{})",
+                collectibles.secrets.len(),
+                collectibles.level_secrets
+            );
+            audio.play(sounds.found.clone());
+        }
+    }
+
+    let step = FADE_SPEED * time.delta_seconds();
+    for (mut layer, storage) in &mut q_layers {
+        let target = if revealed.contains(&layer.id) { 0. } else { 1. };
+        if layer.alpha == target {
+            continue;
+        }
+        layer.alpha = if layer.alpha < target {
+            (layer.alpha + step).min(target)
+        } else {
+            (layer.alpha - step).max(target)
+        };
+        for tile_entity in storage.iter().flatten() {
+            if let Ok(mut color) = q_tiles.get_mut(*tile_entity) {
+                color.0.set_alpha(layer.alpha);
+            }
+        }
+    }
+}
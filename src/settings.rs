@@ -0,0 +1,780 @@
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use bevy_keith::Canvas;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::CameraSettings,
+    cli::StartupArgs,
+    input::{key_name, Action, InputMap},
+    layout::UiLayout,
+    locale::{tr, Language, Localizer},
+    menu::{
+        draw_menu_cursor, MenuAction, MenuHighlight, MenuInput, MenuItem, MenuLayout,
+        MenuNavigation,
+    },
+    sound::ChannelVolumes,
+    storage, AppState, UiRes,
+};
+
+/// How the life of the player is displayed in game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthStyle {
+    /// Continuous life bar.
+    #[default]
+    Bar,
+    /// Row of segments, each worth a fixed amount of life and which can be
+    /// half full.
+    Segments,
+}
+
+impl HealthStyle {
+    fn label(&self) -> &'static str {
+        match self {
+            HealthStyle::Bar => "settings-health-bar",
+            HealthStyle::Segments => "settings-health-segments",
+        }
+    }
+}
+
+/// How the game window is displayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// Borderless window covering the whole monitor, at its resolution.
+    Borderless,
+    /// Exclusive fullscreen, at the selected resolution.
+    Fullscreen,
+}
+
+impl DisplayMode {
+    const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "settings-display-windowed",
+            DisplayMode::Borderless => "settings-display-borderless",
+            DisplayMode::Fullscreen => "settings-display-fullscreen",
+        }
+    }
+}
+
+/// Window sizes available in the windowed and fullscreen display modes.
+const RESOLUTIONS: [UVec2; 5] = [
+    UVec2::new(960, 720),
+    UVec2::new(1280, 720),
+    UVec2::new(1280, 960),
+    UVec2::new(1600, 900),
+    UVec2::new(1920, 1080),
+];
+
+/// User settings, edited from the settings menu.
+#[derive(Debug, Clone, Resource)]
+pub struct Settings {
+    pub language: Language,
+    /// Volume of all sounds, from 0 to 1.
+    pub master_volume: f32,
+    /// Volume of the background music, from 0 to 1, relative to the master one.
+    pub music_volume: f32,
+    /// Volume of the sound effects, from 0 to 1, relative to the master one.
+    pub effects_volume: f32,
+    /// Silence all audio, without changing the volumes.
+    pub muted: bool,
+    pub display_mode: DisplayMode,
+    /// Size of the window, unless in borderless mode.
+    pub resolution: UVec2,
+    /// Keep the 4:3 aspect ratio of the game, with black bars on the sides.
+    pub letterbox: bool,
+    pub vsync: bool,
+    /// Snap the camera to the texel grid of the pixel art.
+    pub pixel_snap: bool,
+    pub health_style: HealthStyle,
+    /// Show the level timer in game.
+    pub show_timer: bool,
+    /// Show the ghost of the best run when replaying a level.
+    pub show_ghost: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: Language::default(),
+            master_volume: 1.,
+            music_volume: 1.,
+            effects_volume: 1.,
+            muted: false,
+            display_mode: DisplayMode::Windowed,
+            resolution: RESOLUTIONS[0],
+            letterbox: false,
+            vsync: true,
+            pixel_snap: true,
+            health_style: HealthStyle::Bar,
+            show_timer: true,
+            show_ghost: true,
+        }
+    }
+}
+
+/// Settings saved between sessions, as RON, along with the key bindings
+/// edited from the same menu.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedSettings {
+    language: Language,
+    master_volume: f32,
+    music_volume: f32,
+    effects_volume: f32,
+    muted: bool,
+    display_mode: DisplayMode,
+    resolution: UVec2,
+    letterbox: bool,
+    vsync: bool,
+    pixel_snap: bool,
+    health_style: HealthStyle,
+    show_timer: bool,
+    show_ghost: bool,
+    bindings: InputMap,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        Self::new(&Settings::default(), &InputMap::default())
+    }
+}
+
+impl SavedSettings {
+    fn new(settings: &Settings, input_map: &InputMap) -> Self {
+        Self {
+            language: settings.language,
+            master_volume: settings.master_volume,
+            music_volume: settings.music_volume,
+            effects_volume: settings.effects_volume,
+            muted: settings.muted,
+            display_mode: settings.display_mode,
+            resolution: settings.resolution,
+            letterbox: settings.letterbox,
+            vsync: settings.vsync,
+            pixel_snap: settings.pixel_snap,
+            health_style: settings.health_style,
+            show_timer: settings.show_timer,
+            show_ghost: settings.show_ghost,
+            bindings: input_map.clone(),
+        }
+    }
+
+    fn apply(self, settings: &mut Settings, input_map: &mut InputMap) {
+        settings.language = self.language;
+        settings.master_volume = self.master_volume.clamp(0., 1.);
+        settings.music_volume = self.music_volume.clamp(0., 1.);
+        settings.effects_volume = self.effects_volume.clamp(0., 1.);
+        settings.muted = self.muted;
+        settings.display_mode = self.display_mode;
+        // Only keep resolutions the menu can select
+        if RESOLUTIONS.contains(&self.resolution) {
+            settings.resolution = self.resolution;
+        }
+        settings.letterbox = self.letterbox;
+        settings.vsync = self.vsync;
+        settings.pixel_snap = self.pixel_snap;
+        settings.health_style = self.health_style;
+        settings.show_timer = self.show_timer;
+        settings.show_ghost = self.show_ghost;
+        *input_map = self.bindings;
+    }
+}
+
+/// Storage key of the settings.
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// Load the saved settings and key bindings, or the default ones if none were
+/// saved yet.
+fn load_settings() -> (Settings, InputMap) {
+    let mut settings = Settings::default();
+    let mut input_map = InputMap::default();
+    match storage::load::<SavedSettings>(SETTINGS_FILE) {
+        Ok(Some(saved)) => saved.apply(&mut settings, &mut input_map),
+        Ok(None) => (),
+        Err(err) => warn!("Ignoring saved settings '{}': {}", SETTINGS_FILE, err),
+    }
+    (settings, input_map)
+}
+
+/// Save the settings and key bindings whenever they change, to restore them
+/// next session.
+fn save_settings(settings: Res<Settings>, input_map: Res<InputMap>) {
+    let saved = SavedSettings::new(&settings, &input_map);
+    if let Err(err) = storage::save(SETTINGS_FILE, &saved) {
+        warn!("Failed to save settings to '{}': {}", SETTINGS_FILE, err);
+    }
+}
+
+/// Volume change of each step in the settings menu.
+const VOLUME_STEP: f32 = 0.1;
+
+/// Pixels of the icon shown while the audio is muted, a speaker next to a
+/// cross.
+const MUTE_ICON: [&str; 7] = [
+    "    #       ",
+    "   ##  #   #",
+    "#####   # # ",
+    "#####    #  ",
+    "#####   # # ",
+    "   ##  #   #",
+    "    #       ",
+];
+
+/// Size of each pixel of the mute icon.
+const MUTE_ICON_PIXEL: f32 = 3.;
+
+const SETTINGS_LAYOUT: MenuLayout = MenuLayout::new(Vec2::new(-300., -150.), 50., 700.);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum SettingsTab {
+    #[default]
+    General,
+    Audio,
+    Video,
+    Controls,
+}
+
+impl SettingsTab {
+    const ALL: [SettingsTab; 4] = [
+        SettingsTab::General,
+        SettingsTab::Audio,
+        SettingsTab::Video,
+        SettingsTab::Controls,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsTab::General => "settings-tab-general",
+            SettingsTab::Audio => "settings-tab-audio",
+            SettingsTab::Video => "settings-tab-video",
+            SettingsTab::Controls => "settings-tab-controls",
+        }
+    }
+
+    fn entries(&self) -> &'static [SettingsEntry] {
+        match self {
+            SettingsTab::General => &[
+                SettingsEntry::Language,
+                SettingsEntry::ShowGhost,
+                SettingsEntry::Back,
+            ],
+            SettingsTab::Audio => &[
+                SettingsEntry::MasterVolume,
+                SettingsEntry::MusicVolume,
+                SettingsEntry::EffectsVolume,
+                SettingsEntry::Mute,
+                SettingsEntry::Back,
+            ],
+            SettingsTab::Video => &[
+                SettingsEntry::DisplayMode,
+                SettingsEntry::Resolution,
+                SettingsEntry::Letterbox,
+                SettingsEntry::Vsync,
+                SettingsEntry::PixelSnap,
+                SettingsEntry::HealthStyle,
+                SettingsEntry::ShowTimer,
+                SettingsEntry::Back,
+            ],
+            SettingsTab::Controls => &[
+                SettingsEntry::Binding(Action::Left),
+                SettingsEntry::Binding(Action::Right),
+                SettingsEntry::Binding(Action::Up),
+                SettingsEntry::Binding(Action::Down),
+                SettingsEntry::Binding(Action::Jump),
+                SettingsEntry::Binding(Action::Fire),
+                SettingsEntry::Binding(Action::Interact),
+                SettingsEntry::ResetBindings,
+                SettingsEntry::Back,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsEntry {
+    Language,
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    Mute,
+    DisplayMode,
+    Resolution,
+    Letterbox,
+    Vsync,
+    PixelSnap,
+    HealthStyle,
+    ShowTimer,
+    ShowGhost,
+    Binding(Action),
+    ResetBindings,
+    Back,
+}
+
+impl SettingsEntry {
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsEntry::Language => "settings-language",
+            SettingsEntry::MasterVolume => "settings-master-volume",
+            SettingsEntry::MusicVolume => "settings-music-volume",
+            SettingsEntry::EffectsVolume => "settings-effects-volume",
+            SettingsEntry::Mute => "settings-mute",
+            SettingsEntry::DisplayMode => "settings-display-mode",
+            SettingsEntry::Resolution => "settings-resolution",
+            SettingsEntry::Letterbox => "settings-letterbox",
+            SettingsEntry::Vsync => "settings-vsync",
+            SettingsEntry::PixelSnap => "settings-pixel-snap",
+            SettingsEntry::HealthStyle => "settings-health-style",
+            SettingsEntry::ShowTimer => "settings-show-timer",
+            SettingsEntry::ShowGhost => "settings-show-ghost",
+            SettingsEntry::Binding(action) => action.label(),
+            SettingsEntry::ResetBindings => "settings-reset-bindings",
+            SettingsEntry::Back => "settings-back",
+        }
+    }
+
+    fn value(&self, settings: &Settings, input_map: &InputMap, loc: &Localizer) -> Option<String> {
+        let format_volume = |volume: f32| format!("{}%", (volume * 100.).round());
+        let format_toggle =
+            |value: bool| tr!(loc, if value { "settings-on" } else { "settings-off" });
+        match self {
+            SettingsEntry::Language => Some(settings.language.label().to_string()),
+            SettingsEntry::MasterVolume => Some(format_volume(settings.master_volume)),
+            SettingsEntry::MusicVolume => Some(format_volume(settings.music_volume)),
+            SettingsEntry::EffectsVolume => Some(format_volume(settings.effects_volume)),
+            SettingsEntry::Mute => Some(format_toggle(settings.muted)),
+            SettingsEntry::DisplayMode => Some(tr!(loc, settings.display_mode.label())),
+            SettingsEntry::Resolution => Some(format!(
+                "{}x{}",
+                settings.resolution.x, settings.resolution.y
+            )),
+            SettingsEntry::Letterbox => Some(format_toggle(settings.letterbox)),
+            SettingsEntry::Vsync => Some(format_toggle(settings.vsync)),
+            SettingsEntry::PixelSnap => Some(format_toggle(settings.pixel_snap)),
+            SettingsEntry::HealthStyle => Some(tr!(loc, settings.health_style.label())),
+            SettingsEntry::ShowTimer => Some(format_toggle(settings.show_timer)),
+            SettingsEntry::ShowGhost => Some(format_toggle(settings.show_ghost)),
+            SettingsEntry::Binding(action) => {
+                let binding = input_map.binding(*action);
+                Some(format!("{} / {:?}", key_name(binding.key), binding.button))
+            }
+            SettingsEntry::ResetBindings | SettingsEntry::Back => None,
+        }
+    }
+
+    /// Change the value of the entry, decreasing it for a negative direction
+    /// and increasing it for a positive one. Toggles flip either way.
+    fn adjust(&self, settings: &mut Settings, args: &mut StartupArgs, dir: f32) {
+        // Changing a setting overridden from the command line ends the
+        // override, starting from the overridden value
+        match self {
+            SettingsEntry::Mute if args.mute => {
+                settings.muted = true;
+                args.mute = false;
+            }
+            SettingsEntry::DisplayMode if args.windowed => {
+                settings.display_mode = DisplayMode::Windowed;
+                args.windowed = false;
+            }
+            _ => (),
+        }
+        let step_volume = |volume: &mut f32| {
+            *volume = (*volume + dir * VOLUME_STEP).clamp(0., 1.);
+        };
+        match self {
+            SettingsEntry::Language => {
+                settings.language = cycle(&Language::ALL, settings.language, dir);
+            }
+            SettingsEntry::MasterVolume => step_volume(&mut settings.master_volume),
+            SettingsEntry::MusicVolume => step_volume(&mut settings.music_volume),
+            SettingsEntry::EffectsVolume => step_volume(&mut settings.effects_volume),
+            SettingsEntry::Mute => settings.muted = !settings.muted,
+            SettingsEntry::DisplayMode => {
+                settings.display_mode = cycle(&DisplayMode::ALL, settings.display_mode, dir);
+            }
+            SettingsEntry::Resolution => {
+                settings.resolution = cycle(&RESOLUTIONS, settings.resolution, dir);
+            }
+            SettingsEntry::Letterbox => settings.letterbox = !settings.letterbox,
+            SettingsEntry::Vsync => settings.vsync = !settings.vsync,
+            SettingsEntry::PixelSnap => settings.pixel_snap = !settings.pixel_snap,
+            SettingsEntry::HealthStyle => {
+                settings.health_style = match settings.health_style {
+                    HealthStyle::Bar => HealthStyle::Segments,
+                    HealthStyle::Segments => HealthStyle::Bar,
+                }
+            }
+            SettingsEntry::ShowTimer => settings.show_timer = !settings.show_timer,
+            SettingsEntry::ShowGhost => settings.show_ghost = !settings.show_ghost,
+            SettingsEntry::Binding(_) | SettingsEntry::ResetBindings | SettingsEntry::Back => (),
+        }
+    }
+}
+
+/// Get the value before or after the current one in a list, depending on the
+/// direction, wrapping around at both ends.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, dir: f32) -> T {
+    let count = values.len();
+    let index = values
+        .iter()
+        .position(|value| *value == current)
+        .unwrap_or_default();
+    let index = if dir < 0. {
+        (index + count - 1) % count
+    } else {
+        (index + 1) % count
+    };
+    values[index]
+}
+
+#[derive(Default, Resource)]
+pub struct SettingsMenu {
+    tab: SettingsTab,
+    selected_index: usize,
+    /// Action waiting for a key or gamepad button press to be bound to it.
+    rebinding: Option<Action>,
+    /// Feedback about the last change, like a binding conflict.
+    message: Option<String>,
+    /// State to go back to when leaving the menu.
+    return_state: AppState,
+}
+
+impl SettingsMenu {
+    /// Reset the menu before entering the [`AppState::Settings`] state from the
+    /// given one.
+    pub fn open(&mut self, return_state: AppState) {
+        *self = Self {
+            return_state,
+            ..default()
+        };
+    }
+}
+
+#[derive(Default)]
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // Load the settings right away, so audio never plays at the default
+        // volume before the saved one applies.
+        let (settings, input_map) = load_settings();
+        app.insert_resource(settings)
+            .insert_resource(input_map)
+            .init_resource::<SettingsMenu>()
+            .add_systems(
+                PreUpdate,
+                settings_inputs.run_if(in_state(AppState::Settings)),
+            )
+            .add_systems(
+                Update,
+                (
+                    (apply_audio_settings, apply_video_settings)
+                        .run_if(resource_changed::<Settings>),
+                    save_settings.run_if(
+                        resource_changed::<Settings>
+                            .or_else(resource_changed::<InputMap>)
+                            .and_then(not(resource_added::<Settings>)),
+                    ),
+                    ui_settings.run_if(in_state(AppState::Settings)),
+                    toggle_mute,
+                ),
+            )
+            .add_systems(PostUpdate, ui_mute_icon.before(crate::fade::draw_fade));
+    }
+}
+
+fn settings_inputs(
+    mut navigation: MenuNavigation,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut menu: ResMut<SettingsMenu>,
+    mut settings: ResMut<Settings>,
+    mut input_map: ResMut<InputMap>,
+    mut args: ResMut<StartupArgs>,
+    mut app_state: ResMut<NextState<AppState>>,
+    loc: Localizer,
+) {
+    // Bind the next key or gamepad button pressed, swapping bindings with any
+    // other action already using it
+    if let Some(action) = menu.rebinding {
+        let conflict = if navigation.input.pause() {
+            menu.rebinding = None;
+            return;
+        } else if let Some(key) = keyboard.get_just_pressed().next() {
+            debug!("Binding {:?} to key {:?}", action, key);
+            input_map.bind_key(action, *key)
+        } else if let Some(button) = gamepad_buttons.get_just_pressed().next() {
+            debug!("Binding {:?} to gamepad button {:?}", action, button);
+            input_map.bind_button(action, button.button_type)
+        } else {
+            return;
+        };
+        menu.rebinding = None;
+        menu.message =
+            conflict.map(|other| tr!(loc, "settings-swapped", action = tr!(loc, other.label())));
+        return;
+    }
+
+    // Switch tabs
+    let tab_index = SettingsTab::ALL
+        .iter()
+        .position(|tab| *tab == menu.tab)
+        .unwrap_or_default();
+    if navigation.input.prev_tab() && tab_index > 0 {
+        menu.tab = SettingsTab::ALL[tab_index - 1];
+        menu.selected_index = 0;
+    } else if navigation.input.next_tab() && tab_index + 1 < SettingsTab::ALL.len() {
+        menu.tab = SettingsTab::ALL[tab_index + 1];
+        menu.selected_index = 0;
+    }
+
+    let entries = menu.tab.entries();
+    let previous_index = menu.selected_index;
+    let action = navigation.navigate(&mut menu.selected_index, entries.len(), &SETTINGS_LAYOUT);
+    if menu.selected_index != previous_index {
+        menu.message = None;
+    }
+
+    let entry = entries[menu.selected_index];
+    if navigation.input.left() {
+        entry.adjust(&mut settings, &mut args, -1.);
+        navigation.play_navigate();
+    } else if navigation.input.right() {
+        entry.adjust(&mut settings, &mut args, 1.);
+        navigation.play_navigate();
+    } else if action == Some(MenuAction::Cancel) {
+        app_state.set(menu.return_state);
+    } else if action == Some(MenuAction::Confirm) {
+        menu.message = None;
+        match entry {
+            SettingsEntry::Binding(action) => menu.rebinding = Some(action),
+            SettingsEntry::ResetBindings => {
+                *input_map = InputMap::default();
+                menu.message = Some(tr!(loc, "settings-bindings-reset"));
+            }
+            SettingsEntry::Back => app_state.set(menu.return_state),
+            _ => entry.adjust(&mut settings, &mut args, 1.),
+        }
+    }
+}
+
+/// Mute or unmute all audio with a single key, unless waiting for a key to
+/// bind in the settings menu.
+fn toggle_mute(
+    input: MenuInput,
+    menu: Res<SettingsMenu>,
+    mut settings: ResMut<Settings>,
+    mut args: ResMut<StartupArgs>,
+) {
+    if input.mute() && menu.rebinding.is_none() {
+        SettingsEntry::Mute.adjust(&mut settings, &mut args, 1.);
+        debug!("Audio muted: {}", settings.muted);
+    }
+}
+
+fn apply_audio_settings(
+    settings: Res<Settings>,
+    args: Res<StartupArgs>,
+    mut volumes: ResMut<ChannelVolumes>,
+) {
+    let settings = args.apply_to(&settings);
+    volumes.music.volume = settings.master_volume * settings.music_volume;
+    volumes.sfx.volume = settings.master_volume * settings.effects_volume;
+    volumes.ambience.volume = volumes.sfx.volume;
+    volumes.music.is_muted = settings.muted;
+    volumes.sfx.is_muted = settings.muted;
+    volumes.ambience.is_muted = settings.muted;
+}
+
+/// Show a small icon in the corner of the screen while the audio is muted.
+fn ui_mute_icon(
+    settings: Res<Settings>,
+    args: Res<StartupArgs>,
+    mut q_canvas: Query<&mut Canvas>,
+    layout: Res<UiLayout>,
+) {
+    if !args.apply_to(&settings).muted {
+        return;
+    }
+    let Ok(mut canvas) = q_canvas.get_single_mut() else {
+        return;
+    };
+    let mut ctx = canvas.render_context();
+
+    let size = Vec2::new(MUTE_ICON[0].len() as f32, MUTE_ICON.len() as f32) * MUTE_ICON_PIXEL;
+    let origin = layout.bottom_right() - size - 20.;
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.5));
+    ctx.fill(Rect::from_corners(origin - 6., origin + size + 6.), &brush);
+    let brush = ctx.solid_brush(Color::WHITE);
+    for (y, row) in MUTE_ICON.iter().enumerate() {
+        for (x, _) in row.chars().enumerate().filter(|(_, c)| *c == '#') {
+            let min = origin + Vec2::new(x as f32, y as f32) * MUTE_ICON_PIXEL;
+            ctx.fill(Rect::from_corners(min, min + MUTE_ICON_PIXEL), &brush);
+        }
+    }
+}
+
+fn apply_video_settings(
+    settings: Res<Settings>,
+    args: Res<StartupArgs>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+    mut last_display: Local<Option<(DisplayMode, UVec2)>>,
+) {
+    let settings = args.apply_to(&settings);
+    if let Ok(mut window) = q_window.get_single_mut() {
+        // Only touch the window mode and size when their settings changed, so
+        // changing any other setting doesn't undo a manual resize of the window
+        let display = (settings.display_mode, settings.resolution);
+        if *last_display != Some(display) {
+            *last_display = Some(display);
+            window.mode = match settings.display_mode {
+                DisplayMode::Windowed => WindowMode::Windowed,
+                DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+                DisplayMode::Fullscreen => WindowMode::SizedFullscreen,
+            };
+            // Borderless windows always cover the monitor
+            if settings.display_mode != DisplayMode::Borderless {
+                let resolution = settings.resolution.as_vec2();
+                if window.size() != resolution {
+                    window.resolution.set(resolution.x, resolution.y);
+                }
+            }
+        }
+        window.present_mode = if settings.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+    }
+    if camera_settings.pixel_snap != settings.pixel_snap {
+        camera_settings.pixel_snap = settings.pixel_snap;
+    }
+    if camera_settings.letterbox != settings.letterbox {
+        camera_settings.letterbox = settings.letterbox;
+    }
+}
+
+fn ui_settings(
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    settings: Res<Settings>,
+    args: Res<StartupArgs>,
+    input_map: Res<InputMap>,
+    menu: Res<SettingsMenu>,
+    highlight: MenuHighlight,
+    loc: Localizer,
+    layout: Res<UiLayout>,
+) {
+    let settings = args.apply_to(&settings);
+    let mut canvas = q_canvas.single_mut();
+    canvas.clear();
+
+    let mut ctx = canvas.render_context();
+
+    // Dim the frozen game when opened from the pause menu
+    let background = if menu.return_state == AppState::Paused {
+        Color::srgba(0., 0., 0., 0.6)
+    } else {
+        Srgba::hex("3b69ba").unwrap().into()
+    };
+    let brush = ctx.solid_brush(background);
+    ctx.fill(layout.screen_rect(), &brush);
+
+    let txt = ctx
+        .new_layout(tr!(loc, "settings-title"))
+        .font(ui_res.font.clone())
+        .font_size(32.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(300., 40.))
+        .build();
+    ctx.draw_text(txt, Vec2::new(0., -300.));
+
+    // Tabs, with the current one underlined
+    for (index, tab) in SettingsTab::ALL.iter().enumerate() {
+        let x = -375. + index as f32 * 250.;
+        let color = if *tab == menu.tab {
+            Color::srgb(1., 0.85, 0.)
+        } else {
+            Color::srgb(0.6, 0.6, 0.6)
+        };
+        let txt = ctx
+            .new_layout(tr!(loc, tab.label()))
+            .font(ui_res.font.clone())
+            .font_size(24.)
+            .color(color)
+            .alignment(JustifyText::Left)
+            .bounds(Vec2::new(200., 30.))
+            .build();
+        ctx.draw_text(txt, Vec2::new(x, -230.));
+        if *tab == menu.tab {
+            let brush = ctx.solid_brush(color);
+            ctx.fill(Rect::new(x - 80., -212., x + 80., -209.), &brush);
+        }
+    }
+
+    // Entries of the current tab
+    for (index, entry) in menu.tab.entries().iter().enumerate() {
+        let y = SETTINGS_LAYOUT.item_y(index);
+        let item = MenuItem {
+            text: tr!(loc, entry.label()),
+            position: Vec2::new(-100., y),
+            bounds: Vec2::new(300., 30.),
+            font_size: 24.,
+            color: Color::WHITE,
+        };
+        highlight.draw_item(&mut ctx, &ui_res, item, index == menu.selected_index);
+
+        let value = if menu.rebinding.is_some() && index == menu.selected_index {
+            Some("...".to_string())
+        } else {
+            entry.value(&settings, &input_map, &loc)
+        };
+        if let Some(value) = value {
+            let txt = ctx
+                .new_layout(value)
+                .font(ui_res.font.clone())
+                .font_size(24.)
+                .color(Color::srgb(1., 0.85, 0.))
+                .alignment(JustifyText::Left)
+                .bounds(Vec2::new(200., 30.))
+                .build();
+            ctx.draw_text(txt, Vec2::new(200., y));
+        }
+    }
+
+    let cursor = SETTINGS_LAYOUT.cursor_position(menu.selected_index);
+    draw_menu_cursor(&mut ctx, &ui_res, cursor);
+
+    // Help
+    let help = match (menu.rebinding, &menu.message) {
+        (Some(action), _) => tr!(loc, "settings-rebinding", action = tr!(loc, action.label())),
+        (None, Some(message)) => message.clone(),
+        (None, None) => tr!(loc, "settings-help"),
+    };
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.5));
+    let bottom = layout.bottom_center();
+    let mut help_rect = layout.screen_rect();
+    help_rect.min.y = bottom.y - 60.;
+    help_rect.max.y = bottom.y - 20.;
+    ctx.fill(help_rect, &brush);
+    let txt = ctx
+        .new_layout(help)
+        .font(ui_res.font.clone())
+        .font_size(16.)
+        .color(Color::WHITE)
+        .alignment(JustifyText::Left)
+        .bounds(Vec2::new(900., 20.))
+        .build();
+    ctx.draw_text(txt, bottom + Vec2::new(0., -40.));
+}
@@ -0,0 +1,490 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use bevy::{prelude::*, utils::HashMap, window::WindowFocused};
+use bevy_kira_audio::{prelude::*, AudioSource};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    rng::GameRng, AppState, EmitterSound, GroundMaterial, MainCamera, Player, PlayerController,
+    PlayerLife, SoundEmitter,
+};
+
+/// Horizontal distance walked between two footsteps, in pixels. The faster
+/// the player walks, the faster the steps follow each other.
+const STEP_DISTANCE: f32 = 12.;
+
+/// Horizontal speed below which the player is considered standing still.
+const MIN_STEP_SPEED: f32 = 5.;
+
+/// Maximum panning away from the center for emitters on the side of the
+/// screen, where 1 fully pans to one speaker.
+const MAX_EMITTER_PAN: f64 = 0.8;
+
+/// Factor applied to the music and ambience volume while the game is paused.
+const PAUSE_DUCK: f32 = 0.3;
+
+/// Fraction of the maximum life of the player lost in a single hit above which
+/// the music is ducked.
+const HEAVY_DAMAGE_RATIO: f32 = 0.25;
+
+/// Duration over which the music ramps back up after being ducked.
+const DUCK_RAMP_DURATION: Duration = Duration::from_millis(600);
+
+/// Duration of the fade when pausing and resuming all audio on focus change.
+const FOCUS_FADE_DURATION: Duration = Duration::from_millis(200);
+
+/// Maximum relative change of the playback rate of sound variants, randomly
+/// applied each time one is played.
+const PITCH_VARIATION: f64 = 0.06;
+
+/// Maximum relative decrease of the volume of sound variants, randomly
+/// applied each time one is played.
+const VOLUME_VARIATION: f64 = 0.15;
+
+/// Audio channel of the background music.
+#[derive(Resource)]
+pub struct MusicChannel;
+
+/// Audio channel of the sound effects.
+#[derive(Resource)]
+pub struct SfxChannel;
+
+/// Audio channel of the environmental sounds of ambience regions.
+#[derive(Resource)]
+pub struct AmbienceChannel;
+
+/// Volume of an audio channel, and whether it's muted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelVolume {
+    pub volume: f32,
+    pub is_muted: bool,
+    /// Factor temporarily lowering the volume, independently of the volume
+    /// chosen by the player.
+    pub duck: f32,
+}
+
+impl Default for ChannelVolume {
+    fn default() -> Self {
+        Self {
+            volume: 1.,
+            is_muted: false,
+            duck: 1.,
+        }
+    }
+}
+
+impl ChannelVolume {
+    /// Volume actually applied to the channel.
+    fn effective(&self) -> f64 {
+        if self.is_muted {
+            0.
+        } else {
+            (self.volume * self.duck) as f64
+        }
+    }
+}
+
+/// Event briefly lowering the music, so that a sound effect marking a key
+/// moment of the gameplay reads clearly.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct DuckMusicEvent {
+    /// Factor applied to the music volume.
+    pub factor: f32,
+    /// Duration the music stays lowered before ramping back up.
+    pub hold: Duration,
+}
+
+impl DuckMusicEvent {
+    pub const HEAVY_DAMAGE: DuckMusicEvent = DuckMusicEvent {
+        factor: 0.5,
+        hold: Duration::from_millis(300),
+    };
+
+    pub const DEATH: DuckMusicEvent = DuckMusicEvent {
+        factor: 0.2,
+        hold: Duration::from_millis(1200),
+    };
+
+    pub const LEVEL_END: DuckMusicEvent = DuckMusicEvent {
+        factor: 0.3,
+        hold: Duration::from_millis(1500),
+    };
+}
+
+/// Current ducking of the music and ambience.
+#[derive(Debug, Default, Resource)]
+struct Ducking {
+    is_paused: bool,
+    /// Gameplay beat ducking the music, and the time it started.
+    beat: Option<(DuckMusicEvent, Duration)>,
+}
+
+impl Ducking {
+    /// Factor applied to the music by the current beat at the given time.
+    fn beat_factor(&self, now: Duration) -> f32 {
+        let Some((duck, start_time)) = self.beat else {
+            return 1.;
+        };
+        let elapsed = now.saturating_sub(start_time);
+        if elapsed <= duck.hold {
+            return duck.factor;
+        }
+        let ramp = (elapsed - duck.hold).div_duration_f32(DUCK_RAMP_DURATION);
+        duck.factor.lerp(1., ramp.min(1.))
+    }
+}
+
+/// Volume of the music and sound effects channels, controlled independently
+/// and applied to the channels whenever they change.
+#[derive(Debug, Default, Resource)]
+pub struct ChannelVolumes {
+    pub music: ChannelVolume,
+    pub sfx: ChannelVolume,
+    pub ambience: ChannelVolume,
+}
+
+/// Interchangeable samples of a frequently repeated sound effect, cycled
+/// through and played with a slight random change of pitch and volume to
+/// avoid an unnatural repetition.
+#[derive(Debug, Default)]
+pub struct SoundVariants {
+    samples: Vec<Handle<AudioSource>>,
+    playback_rate: f64,
+    volume: f64,
+    /// Index of the next sample to play, shared by all systems playing the
+    /// sound.
+    next: AtomicUsize,
+}
+
+impl SoundVariants {
+    pub fn new(samples: Vec<Handle<AudioSource>>, playback_rate: f64, volume: f64) -> Self {
+        Self {
+            samples,
+            playback_rate,
+            volume,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Play the next sample on the given channel.
+    pub fn play<T: Resource>(&self, channel: &AudioChannel<T>, rng: &mut GameRng) {
+        if self.samples.is_empty() {
+            return;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.samples.len();
+        let rate = 1. + (rng.gen::<f64>() * 2. - 1.) * PITCH_VARIATION;
+        let volume = 1. - rng.gen::<f64>() * VOLUME_VARIATION;
+        channel
+            .play(self.samples[index].clone())
+            .with_playback_rate(self.playback_rate * rate)
+            .with_volume(self.volume * volume);
+    }
+}
+
+/// Sound effects of the player actions, loaded once at startup.
+#[derive(Default, Resource)]
+pub struct AudioAssets {
+    pub jump: Handle<AudioSource>,
+    pub land: Handle<AudioSource>,
+    /// Tick played at regular intervals while climbing a ladder.
+    pub climb: Handle<AudioSource>,
+    pub damage: SoundVariants,
+    pub death: Handle<AudioSource>,
+    pub teleport: Handle<AudioSource>,
+    pub epoch_change: Handle<AudioSource>,
+    /// Footsteps on each ground material.
+    pub footsteps: HashMap<GroundMaterial, SoundVariants>,
+    /// Looping sounds of the sound emitters.
+    pub emitters: HashMap<EmitterSound, Handle<AudioSource>>,
+}
+
+/// Sounds currently playing for each sound emitter.
+#[derive(Default, Resource)]
+struct EmitterInstances {
+    instances: HashMap<Entity, Handle<AudioInstance>>,
+    /// Are the emitters silenced because the game is paused?
+    is_silenced: bool,
+}
+
+#[derive(Default)]
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioAssets>()
+            .init_resource::<ChannelVolumes>()
+            .init_resource::<EmitterInstances>()
+            .init_resource::<Ducking>()
+            .add_event::<DuckMusicEvent>()
+            .add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<SfxChannel>()
+            .add_audio_channel::<AmbienceChannel>()
+            .add_systems(Startup, load_audio_assets)
+            .add_systems(
+                Update,
+                (
+                    play_footsteps.after(crate::player::player_input),
+                    duck_on_damage.before(update_ducking),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_ducking,
+                    apply_channel_volumes.run_if(resource_changed::<ChannelVolumes>),
+                    update_emitters,
+                    pause_on_focus_loss,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn load_audio_assets(asset_server: Res<AssetServer>, mut assets: ResMut<AudioAssets>) {
+    // Placeholder sample for all effects, pitched differently when played,
+    // until dedicated samples are made.
+    let placeholder: Handle<AudioSource> = asset_server.load("select1.ogg");
+    *assets = AudioAssets {
+        jump: placeholder.clone(),
+        land: placeholder.clone(),
+        climb: placeholder.clone(),
+        damage: SoundVariants::new(vec![placeholder.clone()], 0.8, 1.),
+        death: placeholder.clone(),
+        teleport: placeholder.clone(),
+        epoch_change: placeholder.clone(),
+        footsteps: [
+            GroundMaterial::Stone,
+            GroundMaterial::Grass,
+            GroundMaterial::Metal,
+            GroundMaterial::Ice,
+        ]
+        .into_iter()
+        .map(|material| {
+            let rate = footstep_rate(material);
+            (
+                material,
+                SoundVariants::new(vec![placeholder.clone()], rate, 0.3),
+            )
+        })
+        .collect(),
+        emitters: [EmitterSound::Saw, EmitterSound::Fire, EmitterSound::Water]
+            .into_iter()
+            .map(|sound| (sound, placeholder.clone()))
+            .collect(),
+    };
+}
+
+/// Start the looping sound of new emitters and stop the one of despawned
+/// emitters, then update the volume and panning of each sound from the
+/// position of the emitter relative to the camera.
+fn update_emitters(
+    q_camera: Query<&GlobalTransform, With<MainCamera>>,
+    q_emitters: Query<(Entity, &SoundEmitter, &GlobalTransform)>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<AudioAssets>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut instances: ResMut<EmitterInstances>,
+) {
+    instances.instances.retain(|entity, handle| {
+        if q_emitters.contains(*entity) {
+            return true;
+        }
+        if let Some(instance) = audio_instances.get_mut(handle) {
+            instance.stop(AudioTween::default());
+        }
+        false
+    });
+
+    let Ok(camera_transform) = q_camera.get_single() else {
+        return;
+    };
+    let listener = camera_transform.translation().xy();
+
+    for (entity, emitter, transform) in &q_emitters {
+        let delta = transform.translation().xy() - listener;
+        let attenuation = (1. - delta.length() / emitter.radius).clamp(0., 1.);
+        // Quadratic falloff sounds more natural than a linear one
+        let volume = if instances.is_silenced {
+            0.
+        } else {
+            (emitter.volume * attenuation * attenuation) as f64
+        };
+        let panning = 0.5 + (delta.x / emitter.radius).clamp(-1., 1.) as f64 * MAX_EMITTER_PAN / 2.;
+
+        let handle = instances.instances.entry(entity).or_insert_with(|| {
+            let sound = sounds.emitters[&emitter.sound].clone();
+            audio
+                .play(sound)
+                .looped()
+                .with_volume(volume)
+                .with_panning(panning)
+                .with_playback_rate(emitter_rate(emitter.sound))
+                .handle()
+        });
+        if let Some(instance) = audio_instances.get_mut(handle) {
+            instance.set_volume(volume, AudioTween::default());
+            instance.set_panning(panning, AudioTween::default());
+        }
+    }
+}
+
+/// Playback rate of each emitter sound, telling them apart while they share
+/// the placeholder sample.
+fn emitter_rate(sound: EmitterSound) -> f64 {
+    match sound {
+        EmitterSound::Saw => 4.,
+        EmitterSound::Fire => 0.5,
+        EmitterSound::Water => 0.3,
+    }
+}
+
+/// Playback rate of the footsteps on each material, telling materials apart
+/// while they share the placeholder sample.
+fn footstep_rate(material: GroundMaterial) -> f64 {
+    match material {
+        GroundMaterial::Stone => 2.4,
+        GroundMaterial::Grass => 1.8,
+        GroundMaterial::Metal => 3.,
+        GroundMaterial::Ice => 3.4,
+    }
+}
+
+/// Play a footstep each time the player walks [`STEP_DISTANCE`] on the
+/// ground, with the samples of the material under the player.
+fn play_footsteps(
+    time: Res<Time>,
+    q_player: Query<(&PlayerController, &Velocity), With<Player>>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<AudioAssets>,
+    mut rng: ResMut<GameRng>,
+    mut distance: Local<f32>,
+) {
+    let Ok((controller, velocity)) = q_player.get_single() else {
+        return;
+    };
+    let speed = velocity.linvel.x.abs();
+    if !controller.is_grounded
+        || controller.is_climbing
+        || controller.is_in_water
+        || speed < MIN_STEP_SPEED
+    {
+        *distance = 0.;
+        return;
+    }
+
+    *distance += speed * time.delta_seconds();
+    if *distance < STEP_DISTANCE {
+        return;
+    }
+    *distance -= STEP_DISTANCE;
+
+    if let Some(footsteps) = sounds.footsteps.get(&controller.ground_material) {
+        footsteps.play(&audio, &mut rng);
+    }
+}
+
+/// Duck the music when the player loses a large part of their life at once,
+/// or dies.
+fn duck_on_damage(
+    q_player: Query<Ref<PlayerLife>, With<Player>>,
+    mut last_life: Local<Option<f32>>,
+    mut ev_duck: EventWriter<DuckMusicEvent>,
+) {
+    let Ok(player_life) = q_player.get_single() else {
+        *last_life = None;
+        return;
+    };
+    if !player_life.is_changed() {
+        return;
+    }
+    if let Some(last_life) = last_life.filter(|_| !player_life.is_added()) {
+        let damage = last_life - player_life.life;
+        if player_life.life <= 0. && last_life > 0. {
+            ev_duck.send(DuckMusicEvent::DEATH);
+        } else if damage >= player_life.max_life * HEAVY_DAMAGE_RATIO {
+            ev_duck.send(DuckMusicEvent::HEAVY_DAMAGE);
+        }
+    }
+    *last_life = Some(player_life.life);
+}
+
+/// Duck the music on gameplay beats, and lower it and silence the looping
+/// sound effects while the game is paused, including in the settings menu
+/// opened from the pause menu.
+fn update_ducking(
+    time: Res<Time<bevy::time::Real>>,
+    app_state: Res<State<AppState>>,
+    virtual_time: Res<Time<Virtual>>,
+    mut ev_duck: EventReader<DuckMusicEvent>,
+    mut ducking: ResMut<Ducking>,
+    mut instances: ResMut<EmitterInstances>,
+    mut volumes: ResMut<ChannelVolumes>,
+) {
+    let now = time.elapsed();
+    for ev in ev_duck.read() {
+        // Keep the deepest ducking when several beats overlap
+        if ducking.beat_factor(now) > ev.factor {
+            ducking.beat = Some((*ev, now));
+        }
+    }
+    let beat_factor = ducking.beat_factor(now);
+    if beat_factor >= 1. {
+        ducking.beat = None;
+    }
+
+    let is_paused = match app_state.get() {
+        AppState::Paused => true,
+        AppState::Settings => virtual_time.is_paused(),
+        _ => false,
+    };
+    if ducking.is_paused != is_paused {
+        ducking.is_paused = is_paused;
+        instances.is_silenced = is_paused;
+    }
+
+    let pause_factor = if is_paused { PAUSE_DUCK } else { 1. };
+    let music_duck = pause_factor * beat_factor;
+    // Avoid triggering change detection, which applies the volumes
+    if volumes.music.duck != music_duck || volumes.ambience.duck != pause_factor {
+        volumes.music.duck = music_duck;
+        volumes.ambience.duck = pause_factor;
+    }
+}
+
+/// Pause all audio while the window doesn't have the focus.
+fn pause_on_focus_loss(
+    mut ev_focused: EventReader<WindowFocused>,
+    music: Res<AudioChannel<MusicChannel>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    ambience: Res<AudioChannel<AmbienceChannel>>,
+) {
+    let Some(ev) = ev_focused.read().last() else {
+        return;
+    };
+    let tween = AudioTween::linear(FOCUS_FADE_DURATION);
+    if ev.focused {
+        music.resume().fade_in(tween);
+        sfx.resume().fade_in(tween);
+        ambience.resume().fade_in(tween);
+    } else {
+        music.pause().fade_out(tween);
+        sfx.pause().fade_out(tween);
+        ambience.pause().fade_out(tween);
+    }
+}
+
+fn apply_channel_volumes(
+    volumes: Res<ChannelVolumes>,
+    music: Res<AudioChannel<MusicChannel>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    ambience: Res<AudioChannel<AmbienceChannel>>,
+) {
+    music.set_volume(volumes.music.effective());
+    sfx.set_volume(volumes.sfx.effective());
+    ambience.set_volume(volumes.ambience.effective());
+}
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{AppState, Player, PlayerLife};
+
+/// Statistics of the current run, from starting a game until going back to
+/// the main menu.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct RunStats {
+    /// Number of times the player died.
+    pub deaths: u32,
+    /// Total amount of life lost by the player.
+    pub damage_taken: f32,
+    /// Number of times a teleporter changed the epoch.
+    pub epoch_changes: u32,
+    /// Time spent in each completed section of the current level, sections
+    /// being separated by checkpoints.
+    pub sections: Vec<Duration>,
+}
+
+impl RunStats {
+    /// Forget the sections of the previous level.
+    pub fn start_level(&mut self) {
+        self.sections.clear();
+    }
+
+    /// End the current section, given the time elapsed since the start of
+    /// the level.
+    pub fn split(&mut self, elapsed: Duration) {
+        let previous: Duration = self.sections.iter().sum();
+        self.sections.push(elapsed.saturating_sub(previous));
+    }
+}
+
+#[derive(Default)]
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(OnEnter(AppState::MainMenu), reset_stats)
+            .add_systems(Update, track_damage.run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// Start a new run when going back to the main menu.
+fn reset_stats(mut stats: ResMut<RunStats>) {
+    *stats = default();
+}
+
+/// Accumulate the life lost by the player, whatever the source of damage.
+fn track_damage(
+    q_player: Query<Ref<PlayerLife>, With<Player>>,
+    mut last_life: Local<Option<f32>>,
+    mut stats: ResMut<RunStats>,
+) {
+    let Ok(player_life) = q_player.get_single() else {
+        *last_life = None;
+        return;
+    };
+    // A new player starts a new count, whatever its life
+    if player_life.is_added() {
+        *last_life = Some(player_life.life);
+        return;
+    }
+    if !player_life.is_changed() {
+        return;
+    }
+    if let Some(last_life) = *last_life {
+        if player_life.life < last_life {
+            stats.damage_taken += last_life - player_life.life;
+        }
+    }
+    *last_life = Some(player_life.life);
+}
@@ -0,0 +1,90 @@
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// Error loading or storing persistent data.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// An [IO](std::io) Error
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Could not access storage file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The browser has no local storage, or denies access to it
+    #[cfg(target_arch = "wasm32")]
+    #[error("Local storage unavailable")]
+    Unavailable,
+    /// The browser refused to store the value, usually for lack of space
+    #[cfg(target_arch = "wasm32")]
+    #[error("Could not write to local storage: {0}")]
+    Write(String),
+    /// The stored value is not valid RON for its type
+    #[error("Invalid stored data: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    /// The value could not be converted to RON
+    #[error("Could not serialize data: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+/// Load the RON value stored under the given key, or `None` if nothing was stored
+/// yet.
+pub fn load<T: DeserializeOwned>(key: &str) -> Result<Option<T>, StorageError> {
+    let Some(contents) = read(key)? else {
+        return Ok(None);
+    };
+    Ok(Some(ron::from_str(&contents)?))
+}
+
+/// Store a value as RON under the given key, replacing any previous one.
+///
+/// Natively each key is a file. On the web there's no file system, so each
+/// key is an entry of the local storage of the browser instead, which survives
+/// between sessions.
+pub fn save<T: Serialize>(key: &str, value: &T) -> Result<(), StorageError> {
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    write(key, &contents)
+}
+
+/// Path of the file of a key, next to the executable so the game can be moved
+/// around with its data, or in the working directory if the executable can't
+/// be located.
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(key)))
+        .unwrap_or_else(|| key.into())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Result<Option<String>, StorageError> {
+    match std::fs::read_to_string(path(key)) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, contents: &str) -> Result<(), StorageError> {
+    Ok(std::fs::write(path(key), contents)?)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, StorageError> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or(StorageError::Unavailable)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Result<Option<String>, StorageError> {
+    local_storage()?
+        .get_item(key)
+        .map_err(|_| StorageError::Unavailable)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, contents: &str) -> Result<(), StorageError> {
+    local_storage()?
+        .set_item(key, contents)
+        .map_err(|err| StorageError::Write(format!("{:?}", err)))
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    interact::{find_target, InteractionTarget},
+    replay::PlayerActions,
+    trigger::{TriggerEnter, TriggerKind},
+    AppState, Switch, Toggleable,
+};
+
+#[derive(Default)]
+pub struct SwitchPlugin;
+
+impl Plugin for SwitchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                touch_switches,
+                interact_switches
+                    .after(find_target)
+                    .run_if(crate::dialogue::is_closed),
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        // Also apply the initial state of targets right after loading the map
+        .add_systems(PostUpdate, apply_toggleables);
+    }
+}
+
+/// Flip a switch and toggle all its targets.
+fn toggle_switch(
+    switch: &mut Switch,
+    sprite: Option<Mut<Sprite>>,
+    q_targets: &mut Query<&mut Toggleable>,
+) {
+    switch.is_on = !switch.is_on;
+    if let Some(mut sprite) = sprite {
+        sprite.flip_x = !sprite.flip_x;
+    }
+    for target in &switch.targets {
+        if let Ok(mut toggleable) = q_targets.get_mut(*target) {
+            toggleable.enabled = !toggleable.enabled;
+        }
+    }
+}
+
+fn touch_switches(
+    mut q_switches: Query<(&mut Switch, Option<&mut Sprite>)>,
+    mut q_targets: Query<&mut Toggleable>,
+    mut ev_enter: EventReader<TriggerEnter>,
+) {
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Switch {
+            continue;
+        }
+        let Ok((mut switch, sprite)) = q_switches.get_mut(ev.trigger) else {
+            continue;
+        };
+        if switch.on_touch {
+            debug!("Player touched switch {:?}", ev.trigger);
+            toggle_switch(&mut switch, sprite, &mut q_targets);
+        }
+    }
+}
+
+fn interact_switches(
+    actions: Res<PlayerActions>,
+    target: Res<InteractionTarget>,
+    mut q_switches: Query<(&mut Switch, Option<&mut Sprite>)>,
+    mut q_targets: Query<&mut Toggleable>,
+) {
+    if !actions.interact {
+        return;
+    }
+    let Some(entity) = target.entity else {
+        return;
+    };
+    let Ok((mut switch, sprite)) = q_switches.get_mut(entity) else {
+        return;
+    };
+    if !switch.on_touch {
+        debug!("Player used switch {:?}", entity);
+        toggle_switch(&mut switch, sprite, &mut q_targets);
+    }
+}
+
+/// Show or hide toggled entities, and enable or disable their colliders,
+/// including the ones of their children.
+fn apply_toggleables(
+    mut commands: Commands,
+    mut q_toggleables: Query<(Entity, &Toggleable, Option<&mut Visibility>), Changed<Toggleable>>,
+    q_children: Query<&Children>,
+    q_colliders: Query<(), With<Collider>>,
+) {
+    for (entity, toggleable, visibility) in &mut q_toggleables {
+        if let Some(mut visibility) = visibility {
+            *visibility = if toggleable.enabled {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+        for e in std::iter::once(entity).chain(q_children.iter_descendants(entity)) {
+            if !q_colliders.contains(e) {
+                continue;
+            }
+            if toggleable.enabled {
+                commands.entity(e).remove::<ColliderDisabled>();
+            } else {
+                commands.entity(e).insert(ColliderDisabled);
+            }
+        }
+    }
+}
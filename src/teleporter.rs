@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    rng::GameRng,
+    sound::{AudioAssets, SfxChannel},
+    stats::RunStats,
+    trigger::{TriggerEnter, TriggerExit, TriggerKind},
+    AppState, Epoch, Player, Teleporter,
+};
+
+/// Lifetime of the flash and particles spawned when teleporting.
+const EFFECT_LIFETIME: Duration = Duration::from_millis(300);
+
+/// Number of particles spawned at each end of a teleportation.
+const PARTICLE_COUNT: u32 = 12;
+
+/// Flash or particle spawned at a teleporter when the player goes through it.
+#[derive(Component)]
+struct TeleportEffect {
+    velocity: Vec2,
+    /// Scale reached by the sprite at the end of its lifetime.
+    end_scale: f32,
+    spawn_time: Duration,
+}
+
+#[derive(Default)]
+pub struct TeleporterPlugin;
+
+impl Plugin for TeleporterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (teleport, update_effects).run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+fn teleport(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<AudioAssets>,
+    mut q_teleporters: Query<(&Transform, &mut Teleporter), Without<Player>>,
+    mut q_player: Query<(&mut Transform, &mut Velocity, &mut Player)>,
+    mut ev_enter: EventReader<TriggerEnter>,
+    mut ev_exit: EventReader<TriggerExit>,
+    mut epoch: Query<&mut Epoch>,
+    mut stats: ResMut<RunStats>,
+) {
+    let Ok((mut player_transform, mut velocity, mut player)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    // Save the side the player enters each teleporter from
+    for ev in ev_enter.read() {
+        if ev.kind != TriggerKind::Teleporter {
+            continue;
+        }
+        if let Ok((tp1_transform, tp1)) = q_teleporters.get(ev.trigger) {
+            let delta = player_transform.translation - tp1_transform.translation;
+            player.teleporter_side = delta.xy().dot(tp1.axis);
+        }
+    }
+
+    let mut epoch_delta = 0;
+    for ev in ev_exit.read() {
+        if ev.kind != TriggerKind::Teleporter {
+            continue;
+        }
+        let tp1_entity = ev.trigger;
+        let Ok((tp1_transform, tp1)) = q_teleporters.get(tp1_entity) else {
+            continue;
+        };
+
+        // Find the exit side, to determine the teleport edge.
+        let delta = (player_transform.translation - tp1_transform.translation).xy();
+        let side = delta.dot(tp1.axis);
+
+        // If the player exits from the same side it entered, or the teleporter
+        // was just used, ignore.
+        if side * player.teleporter_side >= 0. || !tp1.is_ready(time.elapsed()) {
+            player.teleporter_side = 0.;
+            continue;
+        }
+
+        let tp2_entity = tp1.target;
+        let Ok((tp2_transform, _)) = q_teleporters.get(tp2_entity) else {
+            continue;
+        };
+
+        // tp1 -> tp2
+
+        // Keep the same offset from the teleporter on exit, rotated if the
+        // player needs to exit in a different direction.
+        let mut offset = delta;
+        if let Some(exit_dir) = tp1.exit_dir {
+            let cross_dir = tp1.axis * side.signum();
+            let rotation = exit_dir.rotate(Vec2::new(cross_dir.x, -cross_dir.y));
+            offset = rotation.rotate(offset);
+            velocity.linvel = rotation.rotate(velocity.linvel);
+        }
+
+        let entry = player_transform.translation.xy();
+        let edge = tp2_transform.translation.xy();
+        debug!(
+            "Teleport player from TP {:?} at delta {:?} to TP {:?} at {:?}",
+            tp1_entity,
+            delta,
+            tp2_entity,
+            edge + offset
+        );
+        player_transform.translation.x = edge.x + offset.x;
+        player_transform.translation.y = edge.y + offset.y;
+        player.teleporter_side = 0.;
+        epoch_delta += tp1.epoch_delta;
+
+        audio.play(sounds.teleport.clone()).with_playback_rate(1.5);
+        spawn_effect(&mut commands, &mut rng, time.elapsed(), entry);
+        spawn_effect(&mut commands, &mut rng, time.elapsed(), edge + offset);
+
+        // Deactivate both ends for a while, so the player doesn't bounce back
+        for entity in [tp1_entity, tp2_entity] {
+            if let Ok((_, mut teleporter)) = q_teleporters.get_mut(entity) {
+                teleporter.ready_time = time.elapsed() + teleporter.cooldown;
+            }
+        }
+    }
+
+    // Change epoch
+    if epoch_delta != 0 {
+        let mut epoch = epoch.single_mut();
+        if epoch.locked {
+            debug!("Epoch locked at {}", epoch.cur);
+            return;
+        }
+        let cur = (epoch.cur + epoch_delta).clamp(epoch.min, epoch.max);
+        if cur != epoch.cur {
+            debug!("Epoch {} -> {}", epoch.cur, cur);
+            epoch.cur = cur;
+            stats.epoch_changes += 1;
+            audio
+                .play(sounds.epoch_change.clone())
+                .with_playback_rate(0.5);
+        }
+    }
+}
+
+/// Spawn a flash and a burst of particles at the given position.
+fn spawn_effect(commands: &mut Commands, rng: &mut GameRng, time: Duration, position: Vec2) {
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(position.extend(6.)),
+            sprite: Sprite {
+                color: Color::srgb(0.8, 0.9, 1.),
+                custom_size: Some(Vec2::splat(16.)),
+                ..default()
+            },
+            ..default()
+        },
+        TeleportEffect {
+            velocity: Vec2::ZERO,
+            end_scale: 2.,
+            spawn_time: time,
+        },
+        Name::new("TeleportFlash"),
+    ));
+
+    for index in 0..PARTICLE_COUNT {
+        let angle =
+            (index as f32 + rng.gen::<f32>()) / PARTICLE_COUNT as f32 * std::f32::consts::TAU;
+        let speed = 40. + rng.gen::<f32>() * 40.;
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(position.extend(6.)),
+                sprite: Sprite {
+                    color: Color::srgb(0.4, 0.8, 1.),
+                    custom_size: Some(Vec2::splat(2.)),
+                    ..default()
+                },
+                ..default()
+            },
+            TeleportEffect {
+                velocity: Vec2::from_angle(angle) * speed,
+                end_scale: 0.5,
+                spawn_time: time,
+            },
+            Name::new("TeleportParticle"),
+        ));
+    }
+}
+
+fn update_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_effects: Query<(Entity, &TeleportEffect, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, effect, mut transform, mut sprite) in &mut q_effects {
+        let delta = time.elapsed().saturating_sub(effect.spawn_time);
+        if delta >= EFFECT_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation += (effect.velocity * dt).extend(0.);
+        let ratio = delta.div_duration_f32(EFFECT_LIFETIME);
+        transform.scale = Vec3::splat(1. + (effect.end_scale - 1.) * ratio);
+        sprite.color.set_alpha(1. - ratio);
+    }
+}
@@ -17,11 +17,7 @@
 //   * Only finite tile layers are loaded. Infinite tile layers and object
 //     layers will be skipped.
 
-use std::{
-    io::{Cursor, ErrorKind},
-    path::Path,
-    sync::Arc,
-};
+use std::{io::Cursor, path::Path, sync::Arc, time::Duration};
 
 use bevy::{
     asset::{io::Reader, AssetLoader, AssetPath, AsyncReadExt},
@@ -33,13 +29,71 @@ use bevy::{
 };
 use bevy_ecs_tilemap::prelude::*;
 use bevy_rapier2d::prelude::*;
+use rand::Rng;
 use thiserror::Error;
 
-use crate::{Damage, Epoch, EpochSprite, Ladder, LevelEnd, PlayerStart, Teleporter, TileAnimation};
+use crate::{
+    checkpoint::Checkpoint,
+    cli::StartupArgs,
+    pickup::{spawn_pickup, Collectibles},
+    platform::DEFAULT_RESPAWN_DELAY,
+    rng::GameRng,
+    AmbienceRegion, AmbienceSound, ArenaWall, Boss, BossArena, BossLocked, BossStep, Bounce,
+    Breakable, CameraPath, CameraZone, Chaser, CheckpointFlag, Crusher, Damage, EmitterSound,
+    Enemy, EnemyLife, Epoch, EpochSprite, Explosive, FallingPlatform, GravityFlipZone,
+    GroundMaterial, HintZone, Ice, Interactable, Ladder, LevelEnd, LevelEntity, LightSource, Npc,
+    PathFollower, PathMode, Patrol, Pickup, PlayerStart, PowerUp, SecretArea, SecretLayer,
+    SoundEmitter, Spin, Switch, Teleporter, TileAnimation, TimedPlatform, Toggleable, Turret,
+    WaterZone, WindZone,
+};
 
 #[derive(Default, Component)]
 pub struct TileCollision;
 
+/// Amount of life restored by health pickups without a `life` property.
+const DEFAULT_HEALTH_PICKUP: f32 = 5.;
+
+/// Launch speed of springs without a `bounce` property, in pixels per second.
+const DEFAULT_BOUNCE: f32 = 250.;
+
+/// Force of wind zones without a `strength` property.
+const DEFAULT_WIND_STRENGTH: f32 = 2000.;
+
+/// Default travel distance of a crusher without a path, in pixels.
+const DEFAULT_CRUSHER_DISTANCE: f32 = 32.;
+
+/// Default damage of path-following hazards.
+const DEFAULT_HAZARD_DAMAGE: f32 = 10.;
+
+/// Default speed of path-following hazards, in pixels per second.
+const DEFAULT_HAZARD_SPEED: f32 = 60.;
+
+/// Default rotation speed of saw blades, in radians per second.
+const DEFAULT_SAW_SPIN: f32 = -8.;
+
+/// Default distance under which the player can talk to an NPC, in pixels.
+const DEFAULT_NPC_RADIUS: f32 = 24.;
+
+/// Distance beyond the edge of a switch under which the player can use it, in
+/// pixels.
+const SWITCH_REACH: f32 = 8.;
+
+/// Default duration a tutorial hint stays visible.
+const DEFAULT_HINT_DURATION: Duration = Duration::from_secs(5);
+
+/// Default radius of a light object, in pixels.
+const DEFAULT_LIGHT_RADIUS: f32 = 48.;
+
+/// Default distance at which the sound of an emitter fades out, in pixels.
+const DEFAULT_EMITTER_RADIUS: f32 = 160.;
+
+/// Default distance over which an ambience fades out around its region, in
+/// pixels.
+const DEFAULT_AMBIENCE_FADE: f32 = 96.;
+
+/// Default damage at the center of an explosion.
+const DEFAULT_EXPLOSION_DAMAGE: f32 = 10.;
+
 #[derive(Default)]
 pub struct TiledMapPlugin;
 
@@ -60,6 +114,167 @@ pub struct TiledMap {
     // The offset into the tileset_images for each tile id within each tileset.
     #[cfg(not(feature = "atlas"))]
     pub tile_image_offsets: HashMap<(usize, tiled::TileId), u32>,
+
+    // The tiles randomly substituted for each other within each tileset.
+    pub random_groups: HashMap<usize, RandomTileGroups>,
+
+    // Level metadata read from the map properties.
+    pub meta: LevelMeta,
+}
+
+/// Key identifying a group of tiles which can be substituted for each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RandomGroupKey {
+    /// Tiles sharing a same `random_group` property.
+    Named(String),
+    /// Tiles of a same wang set with the same wang ID, so they fit the same
+    /// neighbors. Tiled stores terrains as wang sets too.
+    Wang(usize, [u8; 8]),
+}
+
+/// Groups of tiles of a tileset randomly substituted for each other when
+/// placed, weighted by their Tiled probability.
+#[derive(Debug, Default)]
+pub struct RandomTileGroups {
+    groups: Vec<Vec<(tiled::TileId, f32)>>,
+    /// Index into `groups` of the group of each tile, if any.
+    tile_groups: HashMap<tiled::TileId, usize>,
+}
+
+impl RandomTileGroups {
+    /// Group the tiles sharing a `random_group` property, and the other tiles
+    /// sharing a wang ID within a wang set.
+    fn new(tileset: &tiled::Tileset) -> Self {
+        let mut groups = Self::default();
+        let mut keys = HashMap::<RandomGroupKey, usize>::default();
+        for (tile_id, tile) in tileset.tiles() {
+            let key = get_string_prop(&tile.properties, "random_group")
+                .map(|group| RandomGroupKey::Named(group.to_string()))
+                .or_else(|| {
+                    tileset
+                        .wang_sets
+                        .iter()
+                        .enumerate()
+                        .find_map(|(index, wang_set)| {
+                            let wang_tile = wang_set.wang_tiles.get(&tile_id)?;
+                            Some(RandomGroupKey::Wang(index, wang_tile.wang_id.0))
+                        })
+                });
+            let Some(key) = key else {
+                continue;
+            };
+            let index = *keys.entry(key).or_insert_with(|| {
+                groups.groups.push(vec![]);
+                groups.groups.len() - 1
+            });
+            groups.groups[index].push((tile_id, tile.probability));
+            groups.tile_groups.insert(tile_id, index);
+        }
+        groups
+    }
+
+    fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Get the candidates to substitute the given tile with, itself included.
+    fn candidates(&self, tile_id: tiled::TileId) -> Option<&[(tiled::TileId, f32)]> {
+        let index = *self.tile_groups.get(&tile_id)?;
+        Some(&self.groups[index])
+    }
+}
+
+/// Level metadata, read from the custom properties of a Tiled map.
+///
+/// The metadata of the last loaded map is also inserted as a resource.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct LevelMeta {
+    /// Display name of the level. Defaults to the map file name.
+    pub name: String,
+    /// Optional subtitle displayed below the name.
+    pub subtitle: Option<String>,
+    /// Target time to complete the level, if any.
+    pub par_time: Option<Duration>,
+    /// Asset path of the map to load once the level is completed, if any.
+    pub next_level: Option<String>,
+    /// Epoch the level starts at, if different from the default one.
+    pub start_epoch: Option<i32>,
+    /// Keep the current epoch when the player respawns, instead of restoring
+    /// the one at the time the checkpoint was reached.
+    pub keep_epoch_on_respawn: bool,
+    /// Ambient light level of each epoch, starting from the first one, from 0
+    /// (pitch black) to 1 (no darkness). Epochs past the end of the list use
+    /// its last value.
+    pub ambient_light: Vec<f32>,
+    /// Pan the camera from the level end to the player start when entering the
+    /// level, if the map has no `camera_pan` path.
+    pub intro_pan: bool,
+    /// Asset path of the background music of the level, if not the default
+    /// one.
+    pub music: Option<String>,
+}
+
+impl LevelMeta {
+    /// Read the level metadata from the properties of a map loaded from the
+    /// given asset path.
+    pub fn from_map(map: &tiled::Map, path: &Path) -> Self {
+        let props = &map.properties;
+        let name = get_string_prop(props, "name")
+            .map(|s| s.to_string())
+            .or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_default();
+        let par_time = get_duration_prop(props, "par_time")
+            .or_else(|| {
+                get_int_prop(props, "par_time").map(|t| Duration::from_secs(t.max(0) as u64))
+            })
+            .filter(|t| !t.is_zero());
+        // File properties are relative to the TMX file; convert into an asset path.
+        let asset_path = |name: &str| {
+            get_file_prop(props, name)
+                .or_else(|| get_string_prop(props, name))
+                .filter(|s| !s.is_empty())
+                .map(|file| {
+                    let dir = path.parent().unwrap_or(Path::new(""));
+                    dir.join(file).to_string_lossy().replace('\\', "/")
+                })
+        };
+        Self {
+            name,
+            subtitle: get_string_prop(props, "subtitle").map(|s| s.to_string()),
+            par_time,
+            next_level: asset_path("next_level"),
+            start_epoch: get_int_prop(props, "start_epoch"),
+            keep_epoch_on_respawn: get_bool_prop(props, "keep_epoch_on_respawn").unwrap_or(false),
+            ambient_light: get_float_prop(props, "ambient_light")
+                .map(|light| vec![light])
+                .or_else(|| {
+                    get_string_prop(props, "ambient_light").map(|lights| {
+                        lights
+                            .split(',')
+                            .filter_map(|light| light.trim().parse().ok())
+                            .collect()
+                    })
+                })
+                .unwrap_or_default(),
+            intro_pan: get_bool_prop(props, "intro_pan").unwrap_or(false),
+            music: asset_path("music"),
+        }
+    }
+
+    /// Ambient light level at the given epoch, from 0 (pitch black) to 1 (no
+    /// darkness).
+    pub fn ambient_light(&self, epoch: &Epoch) -> f32 {
+        let index = (epoch.cur - epoch.min).max(0) as usize;
+        self.ambient_light
+            .get(index)
+            .or(self.ambient_light.last())
+            .copied()
+            .unwrap_or(1.)
+            .clamp(0., 1.)
+    }
 }
 
 // Stores a list of tiled layers.
@@ -74,6 +289,8 @@ pub struct TiledMapBundle {
     pub storage: TiledLayersStorage,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
+    // Default render settings of all tile layers, which each layer can override
+    // with its own properties.
     pub render_settings: TilemapRenderSettings,
 }
 
@@ -106,6 +323,30 @@ pub enum TiledAssetLoaderError {
     /// An [IO](std::io) Error
     #[error("Could not load Tiled file: {0}")]
     Io(#[from] std::io::Error),
+    /// A tile layer uses an encoding or compression which cannot be decoded
+    #[error("Unsupported tile layer data encoding '{}' with compression '{}'; re-export the map with CSV or base64 (zlib/gzip/zstd) layer data", .encoding.as_deref().unwrap_or("xml"), .compression.as_deref().unwrap_or("none"))]
+    UnsupportedEncoding {
+        encoding: Option<String>,
+        compression: Option<String>,
+    },
+    /// A Tiled parsing error
+    #[error("Could not load TMX map: {0}")]
+    Tiled(tiled::Error),
+}
+
+impl From<tiled::Error> for TiledAssetLoaderError {
+    fn from(err: tiled::Error) -> Self {
+        match err {
+            tiled::Error::InvalidEncodingFormat {
+                encoding,
+                compression,
+            } => Self::UnsupportedEncoding {
+                encoding,
+                compression,
+            },
+            err => Self::Tiled(err),
+        }
+    }
 }
 
 impl AssetLoader for TiledLoader {
@@ -126,15 +367,19 @@ impl AssetLoader for TiledLoader {
             tiled::DefaultResourceCache::new(),
             BytesResourceReader::new(&bytes),
         );
-        let map = loader.load_tmx_map(load_context.path()).map_err(|e| {
-            std::io::Error::new(ErrorKind::Other, format!("Could not load TMX map: {e}"))
-        })?;
+        let map = loader.load_tmx_map(load_context.path())?;
 
         let mut tilemap_textures = HashMap::default();
         #[cfg(not(feature = "atlas"))]
         let mut tile_image_offsets = HashMap::default();
+        let mut random_groups = HashMap::default();
 
         for (tileset_index, tileset) in map.tilesets().iter().enumerate() {
+            let groups = RandomTileGroups::new(tileset);
+            if !groups.is_empty() {
+                random_groups.insert(tileset_index, groups);
+            }
+
             let tilemap_texture = match &tileset.image {
                 None => {
                     #[cfg(feature = "atlas")]
@@ -187,11 +432,14 @@ impl AssetLoader for TiledLoader {
             tilemap_textures.insert(tileset_index, tilemap_texture);
         }
 
+        let meta = LevelMeta::from_map(&map, load_context.path());
         let asset_map = TiledMap {
             map,
             tilemap_textures,
             #[cfg(not(feature = "atlas"))]
             tile_image_offsets,
+            random_groups,
+            meta,
         };
 
         log::info!("Loaded map: {}", load_context.path().display());
@@ -214,8 +462,62 @@ fn get_teleporter_dst(obj: &tiled::Object) -> Option<u32> {
     Some(*other_id)
 }
 
-fn get_int_prop(tile: &tiled::Tile, name: &str) -> Option<i32> {
-    let Some(prop) = tile.properties.get(name) else {
+/// Get a direction from a `left`, `right`, `up` or `down` string property.
+fn get_dir_prop(props: &tiled::Properties, name: &str) -> Option<Vec2> {
+    match get_string_prop(props, name)? {
+        "left" => Some(Vec2::NEG_X),
+        "right" => Some(Vec2::X),
+        "up" => Some(Vec2::Y),
+        "down" => Some(Vec2::NEG_Y),
+        dir => {
+            warn!("Unknown direction '{}' for property '{}'.", dir, name);
+            None
+        }
+    }
+}
+
+/// Get a `saw`, `fire` or `water` emitter sound from the `sound` property.
+fn get_emitter_sound_prop(props: &tiled::Properties) -> Option<EmitterSound> {
+    match get_string_prop(props, "sound")? {
+        "saw" => Some(EmitterSound::Saw),
+        "fire" => Some(EmitterSound::Fire),
+        "water" => Some(EmitterSound::Water),
+        sound => {
+            warn!("Unknown emitter sound '{}'.", sound);
+            None
+        }
+    }
+}
+
+/// Get a sound emitter from the `sound_radius` and `sound_volume` properties,
+/// playing the `sound` property if any or else the given default sound.
+fn get_sound_emitter(props: &tiled::Properties, default_sound: EmitterSound) -> SoundEmitter {
+    SoundEmitter {
+        sound: get_emitter_sound_prop(props).unwrap_or(default_sound),
+        radius: get_float_prop(props, "sound_radius").unwrap_or(DEFAULT_EMITTER_RADIUS),
+        volume: get_float_prop(props, "sound_volume").unwrap_or(1.),
+    }
+}
+
+/// Get the ground material of a tile from its `material` property, falling
+/// back to ice for `ice` tiles and to stone otherwise.
+fn get_ground_material(props: &tiled::Properties) -> GroundMaterial {
+    match get_string_prop(props, "material") {
+        Some("stone") => GroundMaterial::Stone,
+        Some("grass") => GroundMaterial::Grass,
+        Some("metal") => GroundMaterial::Metal,
+        Some("ice") => GroundMaterial::Ice,
+        Some(material) => {
+            warn!("Unknown ground material '{}'.", material);
+            GroundMaterial::default()
+        }
+        None if get_bool_prop(props, "ice").unwrap_or(false) => GroundMaterial::Ice,
+        None => GroundMaterial::default(),
+    }
+}
+
+fn get_int_prop(props: &tiled::Properties, name: &str) -> Option<i32> {
+    let Some(prop) = props.get(name) else {
         return None;
     };
     let tiled::PropertyValue::IntValue(value) = prop else {
@@ -224,8 +526,8 @@ fn get_int_prop(tile: &tiled::Tile, name: &str) -> Option<i32> {
     Some(*value)
 }
 
-fn get_float_prop(tile: &tiled::Tile, name: &str) -> Option<f32> {
-    let Some(prop) = tile.properties.get(name) else {
+fn get_float_prop(props: &tiled::Properties, name: &str) -> Option<f32> {
+    let Some(prop) = props.get(name) else {
         return None;
     };
     let tiled::PropertyValue::FloatValue(value) = prop else {
@@ -234,6 +536,450 @@ fn get_float_prop(tile: &tiled::Tile, name: &str) -> Option<f32> {
     Some(*value)
 }
 
+/// Get a float property as a duration in seconds, ignoring it with a warning if
+/// negative or not finite.
+fn get_duration_prop(props: &tiled::Properties, name: &str) -> Option<Duration> {
+    let secs = get_float_prop(props, name)?;
+    match Duration::try_from_secs_f32(secs) {
+        Ok(duration) => Some(duration),
+        Err(err) => {
+            warn!("Invalid duration {} for property '{}': {}", secs, name, err);
+            None
+        }
+    }
+}
+
+fn get_string_prop<'a>(props: &'a tiled::Properties, name: &str) -> Option<&'a str> {
+    let tiled::PropertyValue::StringValue(value) = props.get(name)? else {
+        return None;
+    };
+    Some(value)
+}
+
+fn get_file_prop<'a>(props: &'a tiled::Properties, name: &str) -> Option<&'a str> {
+    let tiled::PropertyValue::FileValue(value) = props.get(name)? else {
+        return None;
+    };
+    Some(value)
+}
+
+fn get_bool_prop(props: &tiled::Properties, name: &str) -> Option<bool> {
+    let tiled::PropertyValue::BoolValue(value) = props.get(name)? else {
+        return None;
+    };
+    Some(*value)
+}
+
+/// Get the launch speed of a spring object from its `bounce` property.
+fn get_bounce(obj: &tiled::Object) -> f32 {
+    get_float_prop(&obj.properties, "bounce").unwrap_or(DEFAULT_BOUNCE)
+}
+
+/// Get the IDs of the objects targeted by a switch, from all its object
+/// properties whose name starts with `target`.
+fn get_switch_targets(obj: &tiled::Object) -> Vec<u32> {
+    let mut targets: Vec<(&String, u32)> = obj
+        .properties
+        .iter()
+        .filter(|(name, _)| name.starts_with("target"))
+        .filter_map(|(name, value)| match value {
+            tiled::PropertyValue::ObjectValue(id) if *id != 0 => Some((name, *id)),
+            _ => None,
+        })
+        .collect();
+    targets.sort();
+    targets.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Friction of ice colliders, overriding the one of the bodies on them.
+fn ice_friction() -> Friction {
+    Friction {
+        coefficient: 0.,
+        combine_rule: CoefficientCombineRule::Min,
+    }
+}
+
+/// Get the explosion of a breakable object from its `explosion_radius` and
+/// `explosion_damage` properties, if it's explosive.
+fn get_explosive(obj: &tiled::Object) -> Option<Explosive> {
+    Some(Explosive {
+        radius: get_float_prop(&obj.properties, "explosion_radius")?,
+        damage: get_float_prop(&obj.properties, "explosion_damage")
+            .unwrap_or(DEFAULT_EXPLOSION_DAMAGE),
+    })
+}
+
+/// Get the pickup spawned by an object, based on its class.
+fn get_pickup(obj: &tiled::Object) -> Option<Pickup> {
+    match obj.user_type.as_str() {
+        "health" => Some(Pickup::Life(
+            get_float_prop(&obj.properties, "life").unwrap_or(DEFAULT_HEALTH_PICKUP),
+        )),
+        "coin" => Some(Pickup::Coin(obj.id())),
+        "1up" => Some(Pickup::OneUp),
+        "powerup" => {
+            let power_up = match get_string_prop(&obj.properties, "effect") {
+                Some("speed") => PowerUp::Speed,
+                Some("shield") => PowerUp::Shield,
+                Some("double_damage") => PowerUp::DoubleDamage,
+                Some("slow_time") => PowerUp::SlowTime,
+                effect => {
+                    warn!(
+                        "Unknown power-up effect {:?} for object #{}",
+                        effect,
+                        obj.id()
+                    );
+                    return None;
+                }
+            };
+            let duration = get_duration_prop(&obj.properties, "duration")
+                .unwrap_or_else(|| power_up.default_duration());
+            Some(Pickup::PowerUp(power_up, duration))
+        }
+        _ => None,
+    }
+}
+
+/// Get the ID of the object referenced by an object property, if set.
+fn get_object_prop(props: &tiled::Properties, name: &str) -> Option<u32> {
+    let tiled::PropertyValue::ObjectValue(id) = props.get(name)? else {
+        return None;
+    };
+    (*id != 0).then_some(*id)
+}
+
+/// Get the speed of an object following a path, and its path mode if set
+/// explicitly with the `mode` property (`loop` or `pingpong`).
+fn get_path_settings(obj: &tiled::Object) -> (f32, Option<PathMode>) {
+    let speed = get_float_prop(&obj.properties, "speed").unwrap_or(DEFAULT_HAZARD_SPEED);
+    let mode = match get_string_prop(&obj.properties, "mode") {
+        Some("loop") => Some(PathMode::Loop),
+        Some("pingpong") => Some(PathMode::PingPong),
+        Some(mode) => {
+            warn!("Unknown path mode '{}' for object #{}", mode, obj.id());
+            None
+        }
+        None => None,
+    };
+    (speed, mode)
+}
+
+/// Get the crusher settings of an object, and the ID of the object defining its
+/// path, if any.
+fn get_crusher(obj: &tiled::Object) -> (Crusher, Option<u32>) {
+    let default = Crusher::default();
+    let crusher = Crusher {
+        slam_speed: get_float_prop(&obj.properties, "slam_speed").unwrap_or(default.slam_speed),
+        rise_speed: get_float_prop(&obj.properties, "rise_speed").unwrap_or(default.rise_speed),
+        wait: get_duration_prop(&obj.properties, "wait").unwrap_or(default.wait),
+        hold: get_duration_prop(&obj.properties, "hold").unwrap_or(default.hold),
+        damage: get_float_prop(&obj.properties, "damage"),
+        ..default
+    };
+    (crusher, get_object_prop(&obj.properties, "path"))
+}
+
+/// Get the delay before a platform falls once stood on, from the `falls_after`
+/// property (in milliseconds) of the object or else of its tile.
+fn get_falls_after(obj: &tiled::Object, obj_tile: &tiled::LayerTile) -> Option<Duration> {
+    let ms = get_int_prop(&obj.properties, "falls_after").or_else(|| {
+        obj_tile
+            .get_tile()
+            .and_then(|tile| get_int_prop(&tile.properties, "falls_after"))
+    })?;
+    Some(Duration::from_millis(ms.max(0) as u64))
+}
+
+/// Get the cycle of a platform appearing and disappearing, from its `period`,
+/// `phase` (both in seconds) and `on_ratio` properties, if it has a period.
+fn get_timed_platform(obj: &tiled::Object) -> Option<TimedPlatform> {
+    let period = get_duration_prop(&obj.properties, "period")?;
+    let phase = get_duration_prop(&obj.properties, "phase").unwrap_or_default();
+    // Wrap the phase into the period, so adding it to the current time can't overflow
+    let phase = Duration::from_nanos((phase.as_nanos() % period.as_nanos().max(1)) as u64);
+    let on_ratio = match get_float_prop(&obj.properties, "on_ratio") {
+        Some(ratio) if !ratio.is_finite() => {
+            warn!(
+                "Invalid on_ratio {} for timed platform '{}'",
+                ratio, obj.name
+            );
+            None
+        }
+        ratio => ratio,
+    };
+    Some(TimedPlatform {
+        period,
+        phase,
+        on_ratio: on_ratio.unwrap_or(0.5),
+    })
+}
+
+/// Get the initial state of an object toggleable by a switch, from its
+/// `enabled` property.
+fn get_object_enabled(obj: &tiled::Object) -> bool {
+    get_bool_prop(&obj.properties, "enabled").unwrap_or(true)
+}
+
+/// Get the dialogue pages of an NPC from its `page1`, `page2`, ... object
+/// properties.
+fn get_dialogue_pages(obj: &tiled::Object) -> Vec<String> {
+    (1..)
+        .map_while(|index| get_string_prop(&obj.properties, &format!("page{index}")))
+        .map(|page| page.to_string())
+        .collect()
+}
+
+/// Get the attack patterns of a boss from its `phase1`, `phase2`, ... object
+/// properties, falling back to the default patterns if none is defined.
+fn get_boss_phases(obj: &tiled::Object) -> Vec<Vec<BossStep>> {
+    let mut phases = vec![];
+    for index in 1.. {
+        let Some(pattern) = get_string_prop(&obj.properties, &format!("phase{index}")) else {
+            break;
+        };
+        match crate::boss::parse_pattern(pattern) {
+            Some(steps) if !steps.is_empty() => phases.push(steps),
+            _ => warn!(
+                "Ignoring invalid attack pattern '{}' for phase #{} of boss #{}.",
+                pattern,
+                index,
+                obj.id()
+            ),
+        }
+    }
+    if phases.is_empty() {
+        crate::boss::default_phases()
+    } else {
+        phases
+    }
+}
+
+/// Get the render settings of a tile layer, overriding the map defaults with
+/// the `chunk_size` / `chunk_width` / `chunk_height` and `y_sort` layer
+/// properties if present.
+fn get_layer_render_settings(
+    layer: &tiled::Layer,
+    default: &TilemapRenderSettings,
+) -> TilemapRenderSettings {
+    let mut render_settings = *default;
+    if let Some(size) = get_int_prop(&layer.properties, "chunk_size") {
+        render_settings.render_chunk_size = UVec2::splat(size.max(1) as u32);
+    }
+    if let Some(width) = get_int_prop(&layer.properties, "chunk_width") {
+        render_settings.render_chunk_size.x = width.max(1) as u32;
+    }
+    if let Some(height) = get_int_prop(&layer.properties, "chunk_height") {
+        render_settings.render_chunk_size.y = height.max(1) as u32;
+    }
+    if let Some(y_sort) = get_bool_prop(&layer.properties, "y_sort") {
+        render_settings.y_sort = y_sort;
+    }
+    render_settings
+}
+
+/// Pick a random tile among a group of candidates, weighted by their Tiled
+/// probability. Returns `None` if no candidate has a positive probability.
+fn pick_random_tile(
+    candidates: &[(tiled::TileId, f32)],
+    rng: &mut GameRng,
+) -> Option<tiled::TileId> {
+    let total: f32 = candidates.iter().map(|(_, p)| p.max(0.)).sum();
+    if total <= 0. {
+        return None;
+    }
+    let mut x = rng.gen::<f32>() * total;
+    for (tile_id, p) in candidates {
+        let p = p.max(0.);
+        if x < p {
+            return Some(*tile_id);
+        }
+        x -= p;
+    }
+    candidates
+        .iter()
+        .rev()
+        .find(|(_, p)| *p > 0.)
+        .map(|(tile_id, _)| *tile_id)
+}
+
+/// Map the Tiled orientation of a map to the corresponding tilemap type.
+fn get_map_type(map: &tiled::Map) -> TilemapType {
+    match map.orientation {
+        tiled::Orientation::Hexagonal => {
+            // Tiled staggers odd rows; since rows are flipped on load, which rows are
+            // staggered once in Bevy depends on the parity of the map height.
+            if map.height & 1 == 0 {
+                TilemapType::Hexagon(HexCoordSystem::RowEven)
+            } else {
+                TilemapType::Hexagon(HexCoordSystem::RowOdd)
+            }
+        }
+        tiled::Orientation::Isometric => TilemapType::Isometric(IsoCoordSystem::Diamond),
+        tiled::Orientation::Staggered => TilemapType::Isometric(IsoCoordSystem::Staggered),
+        tiled::Orientation::Orthogonal => TilemapType::Square,
+    }
+}
+
+/// World space bounds of the current map, to keep the camera from showing
+/// the void beyond its edges.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct MapBounds {
+    pub rect: Rect,
+}
+
+/// Geometry of a loaded map, to convert Tiled coordinates into world
+/// coordinates consistently with how tiles are rendered, whatever the map
+/// orientation.
+#[derive(Debug, Clone, Copy)]
+pub struct MapGeometry {
+    pub orientation: tiled::Orientation,
+    pub map_type: TilemapType,
+    pub map_size: TilemapSize,
+    pub grid_size: TilemapGridSize,
+    /// Position of the center of tile (0, 0), relative to its layer.
+    origin: Vec2,
+    /// Offset between the centers of tile (0, 0) and tile (1, 0).
+    axis_x: Vec2,
+    /// Offset between the centers of tile (0, 0) and tile (0, 1).
+    axis_y: Vec2,
+}
+
+impl MapGeometry {
+    pub fn new(map: &tiled::Map) -> Self {
+        let map_type = get_map_type(map);
+        let grid_size = TilemapGridSize {
+            x: map.tile_width as f32,
+            y: map.tile_height as f32,
+        };
+        let origin = TilePos::new(0, 0).center_in_world(&grid_size, &map_type);
+        let axis_x = TilePos::new(1, 0).center_in_world(&grid_size, &map_type) - origin;
+        let axis_y = TilePos::new(0, 1).center_in_world(&grid_size, &map_type) - origin;
+        Self {
+            orientation: map.orientation,
+            map_type,
+            map_size: TilemapSize {
+                x: map.width,
+                y: map.height,
+            },
+            grid_size,
+            origin,
+            axis_x,
+            axis_y,
+        }
+    }
+
+    /// World space bounds of the whole tile grid.
+    pub fn bounds(&self) -> Rect {
+        let max_x = self.map_size.x.saturating_sub(1);
+        let max_y = self.map_size.y.saturating_sub(1);
+        let mut bounds = Rect::from_center_size(self.origin, Vec2::ZERO);
+        for tile_pos in [
+            TilePos::new(max_x, 0),
+            TilePos::new(0, max_y),
+            TilePos::new(max_x, max_y),
+        ] {
+            bounds = bounds.union_point(self.tile_to_world(&tile_pos));
+        }
+        let half_tile = Vec2::new(self.grid_size.x, self.grid_size.y) / 2.;
+        Rect::from_corners(bounds.min - half_tile, bounds.max + half_tile)
+    }
+
+    /// Position of the center of a tile, relative to its layer.
+    pub fn tile_to_world(&self, tile_pos: &TilePos) -> Vec2 {
+        tile_pos.center_in_world(&self.grid_size, &self.map_type)
+    }
+
+    /// Convert a position in Tiled object coordinates (pixels, Y down) into a
+    /// world position.
+    pub fn object_to_world(&self, x: f32, y: f32) -> Vec2 {
+        match self.orientation {
+            tiled::Orientation::Isometric => {
+                // Isometric objects are expressed along the map axes, in units of
+                // tile height, so go through (fractional) tile coordinates.
+                let u = x / self.grid_size.y - 0.5;
+                let v = self.map_size.y as f32 - 0.5 - y / self.grid_size.y;
+                self.origin + self.axis_x * u + self.axis_y * v
+            }
+            _ => {
+                // Other orientations use screen-space pixels, with rows flipped.
+                let top = self.origin.y + self.axis_y.y * (self.map_size.y as f32 - 1.);
+                Vec2::new(
+                    self.origin.x + x - self.grid_size.x / 2.,
+                    top - y + self.grid_size.y / 2.,
+                )
+            }
+        }
+    }
+
+    /// Get the world position of the center of a Tiled rectangle object, and a
+    /// collider covering it relative to that center.
+    pub fn rect_collider(&self, x: f32, y: f32, width: f32, height: f32) -> (Vec2, Collider) {
+        if self.orientation == tiled::Orientation::Isometric {
+            // Rectangles are drawn along the map axes, so become parallelograms
+            let corners = [
+                (x, y),
+                (x + width, y),
+                (x + width, y + height),
+                (x, y + height),
+            ]
+            .map(|(x, y)| self.object_to_world(x, y));
+            let center = corners.iter().sum::<Vec2>() / 4.;
+            let points: Vec<Vec2> = corners.iter().map(|c| *c - center).collect();
+            let collider = Collider::convex_hull(&points)
+                .unwrap_or_else(|| Collider::cuboid(width / 2., height / 2.));
+            (center, collider)
+        } else {
+            let center = self.object_to_world(x + width / 2., y + height / 2.);
+            (center, Collider::cuboid(width / 2., height / 2.))
+        }
+    }
+
+    /// Get the world position of the center of a Tiled tile object of the given
+    /// size. Unlike other objects, tile objects are anchored at their bottom-left
+    /// corner on orthogonal maps, and at their bottom-center on isometric ones.
+    pub fn tile_object_center(&self, x: f32, y: f32, width: f32, height: f32) -> Vec2 {
+        if self.orientation == tiled::Orientation::Isometric {
+            self.object_to_world(x, y) + Vec2::new(0., height / 2.)
+        } else {
+            self.object_to_world(x + width / 2., y - height / 2.)
+        }
+    }
+
+    /// Get a collider covering a whole tile cell, relative to the tile center.
+    pub fn tile_collider(&self) -> Collider {
+        let hw = self.grid_size.x / 2.;
+        let hh = self.grid_size.y / 2.;
+        let points = match self.map_type {
+            TilemapType::Square => return Collider::cuboid(hw, hh),
+            TilemapType::Isometric(_) => vec![
+                Vec2::new(0., hh),
+                Vec2::new(hw, 0.),
+                Vec2::new(0., -hh),
+                Vec2::new(-hw, 0.),
+            ],
+            TilemapType::Hexagon(HexCoordSystem::Column)
+            | TilemapType::Hexagon(HexCoordSystem::ColumnEven)
+            | TilemapType::Hexagon(HexCoordSystem::ColumnOdd) => vec![
+                Vec2::new(hw, 0.),
+                Vec2::new(hw / 2., hh),
+                Vec2::new(-hw / 2., hh),
+                Vec2::new(-hw, 0.),
+                Vec2::new(-hw / 2., -hh),
+                Vec2::new(hw / 2., -hh),
+            ],
+            TilemapType::Hexagon(_) => vec![
+                Vec2::new(0., hh),
+                Vec2::new(hw, hh / 2.),
+                Vec2::new(hw, -hh / 2.),
+                Vec2::new(0., -hh),
+                Vec2::new(-hw, -hh / 2.),
+                Vec2::new(-hw, hh / 2.),
+            ],
+        };
+        Collider::convex_hull(&points).unwrap_or_else(|| Collider::cuboid(hw, hh))
+    }
+}
+
 pub fn process_loaded_maps(
     mut commands: Commands,
     mut map_events: EventReader<AssetEvent<TiledMap>>,
@@ -246,6 +992,11 @@ pub fn process_loaded_maps(
     )>,
     new_maps: Query<&Handle<TiledMap>, Added<Handle<TiledMap>>>,
     mut q_epoch: Query<&mut Epoch>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut collectibles: ResMut<Collectibles>,
+    mut checkpoint: ResMut<Checkpoint>,
+    startup_args: Res<StartupArgs>,
+    mut rng: ResMut<GameRng>,
 ) {
     let mut changed_maps = Vec::<AssetId<TiledMap>>::default();
     for event in map_events.read() {
@@ -277,6 +1028,7 @@ pub fn process_loaded_maps(
     let mut min_epoch = epoch.min;
     let mut max_epoch = epoch.max;
     let mut epoch_change = false;
+    let mut start_epoch = None;
 
     for changed_map in changed_maps.iter() {
         for (map_handle, mut layer_storage, render_settings) in map_query.iter_mut() {
@@ -293,6 +1045,11 @@ pub fn process_loaded_maps(
                 continue;
             };
 
+            commands.insert_resource(tiled_map.meta.clone());
+            collectibles.enter_level(&tiled_map.meta.name);
+            checkpoint.enter_level(&tiled_map.meta.name);
+            start_epoch = startup_args.epoch.or(tiled_map.meta.start_epoch);
+
             // TODO: Create a RemoveMap component..
             for layer_entity in layer_storage.storage.values() {
                 if let Ok((_, layer_tile_storage)) = tile_storage_query.get(*layer_entity) {
@@ -303,15 +1060,15 @@ pub fn process_loaded_maps(
                 // commands.entity(*layer_entity).despawn_recursive();
             }
 
-            let map_size = TilemapSize {
-                x: tiled_map.map.width,
-                y: tiled_map.map.height,
-            };
-
-            let grid_size = TilemapGridSize {
-                x: tiled_map.map.tile_width as f32,
-                y: tiled_map.map.tile_height as f32,
-            };
+            let geometry = MapGeometry::new(&tiled_map.map);
+            commands.insert_resource(MapBounds {
+                rect: geometry.bounds(),
+            });
+            // World space bounds of the tiles of each secret layer, by layer ID
+            let mut secret_bounds: HashMap<u32, (Rect, f32)> = HashMap::new();
+            let map_size = geometry.map_size;
+            let grid_size = geometry.grid_size;
+            let map_type = geometry.map_type;
 
             // The TilemapBundle requires that all tile images come exclusively from a
             // single tiled texture or from a Vec of independent per-tile
@@ -364,21 +1121,13 @@ pub fn process_loaded_maps(
                         continue;
                     };
 
-                    let map_type = match tiled_map.map.orientation {
-                        tiled::Orientation::Hexagonal => TilemapType::Hexagon(HexCoordSystem::Row),
-                        tiled::Orientation::Isometric => {
-                            TilemapType::Isometric(IsoCoordSystem::Diamond)
-                        }
-                        tiled::Orientation::Staggered => {
-                            TilemapType::Isometric(IsoCoordSystem::Staggered)
-                        }
-                        tiled::Orientation::Orthogonal => TilemapType::Square,
-                    };
+                    let layer_render_settings = get_layer_render_settings(&layer, render_settings);
 
                     let mut tile_storage = TileStorage::empty(map_size);
-                    let layer_entity = commands.spawn_empty().id();
+                    let layer_entity = commands.spawn(LevelEntity).id();
 
                     let is_wall = layer.name == "Walls";
+                    let is_secret = get_bool_prop(&layer.properties, "secret").unwrap_or(false);
                     let layer_transform =
                                     // get_tilemap_center_transform(
                                     //     &map_size,
@@ -388,6 +1137,20 @@ pub fn process_loaded_maps(
                                     // ) * 
                                     Transform::from_xyz(offset_x, -offset_y, layer_index as f32);
 
+                    // Convert a tile ID into an index into the tilemap texture. For image
+                    // collection tilesets, this is an index into the vector of tile images.
+                    let to_texture_index = |tile_id: tiled::TileId| -> u32 {
+                        match tilemap_texture {
+                            TilemapTexture::Single(_) => tile_id,
+                            #[cfg(not(feature = "atlas"))]
+                            TilemapTexture::Vector(_) =>
+                                *tiled_map.tile_image_offsets.get(&(tileset_index, tile_id))
+                                .expect("The offset into to image vector should have been saved during the initial load."),
+                            #[cfg(not(feature = "atlas"))]
+                            _ => unreachable!()
+                        }
+                    };
+
                     for x in 0..map_size.x {
                         for y in 0..map_size.y {
                             // Transform TMX coords into bevy coords.
@@ -410,24 +1173,23 @@ pub fn process_loaded_maps(
                                 continue;
                             };
 
+                            // Randomly substitute the tile with one of its group siblings
                             let tile_id = layer_tile_data.id();
+                            let tile_id = tiled_map
+                                .random_groups
+                                .get(&tileset_index)
+                                .and_then(|groups| groups.candidates(tile_id))
+                                .and_then(|candidates| pick_random_tile(candidates, &mut rng))
+                                .unwrap_or(tile_id);
                             let Some(tile) = tileset.get_tile(tile_id) else {
                                 continue;
                             };
 
-                            let epoch = get_int_prop(&tile, "epoch");
-                            let epoch_min = get_int_prop(&tile, "epoch_min");
-                            let epoch_max = get_int_prop(&tile, "epoch_max");
+                            let epoch = get_int_prop(&tile.properties, "epoch");
+                            let epoch_min = get_int_prop(&tile.properties, "epoch_min");
+                            let epoch_max = get_int_prop(&tile.properties, "epoch_max");
 
-                            let texture_index = match tilemap_texture {
-                                            TilemapTexture::Single(_) => layer_tile.id(),
-                                            #[cfg(not(feature = "atlas"))]
-                                            TilemapTexture::Vector(_) =>
-                                                *tiled_map.tile_image_offsets.get(&(tileset_index, layer_tile.id()))
-                                                .expect("The offset into to image vector should have been saved during the initial load."),
-                                            #[cfg(not(feature = "atlas"))]
-                                            _ => unreachable!()
-                                        };
+                            let texture_index = to_texture_index(tile_id);
 
                             let (epoch_sprite, is_visible) = if let Some(epoch_id) = epoch {
                                 let min0 = epoch_min.unwrap_or(epoch_id);
@@ -460,9 +1222,16 @@ pub fn process_loaded_maps(
 
                             // Tile animation
                             let tile_anim = tile.animation.as_ref().map(|frames| TileAnimation {
-                                frames: frames.clone(),
-                                index: rand::random::<u32>() % frames.len() as u32,
-                                clock: rand::random::<u32>() % 1000,
+                                // Pre-resolve frames into texture indices
+                                frames: frames
+                                    .iter()
+                                    .map(|frame| tiled::Frame {
+                                        tile_id: to_texture_index(frame.tile_id),
+                                        duration: frame.duration,
+                                    })
+                                    .collect(),
+                                index: rng.gen_range(0..frames.len() as u32),
+                                clock: rng.gen_range(0..1000),
                             });
 
                             let tile_pos = TilePos { x, y };
@@ -495,30 +1264,41 @@ pub fn process_loaded_maps(
                             let tile_entity = ent_cmds.id();
                             tile_storage.set(&tile_pos, tile_entity);
 
+                            if is_secret {
+                                let tile_center = geometry.tile_to_world(&tile_pos)
+                                    + layer_transform.translation.xy();
+                                let tile_rect = Rect::from_center_size(
+                                    tile_center,
+                                    Vec2::new(grid_size.x, grid_size.y),
+                                );
+                                secret_bounds
+                                    .entry(layer.id())
+                                    .and_modify(|(rect, _)| *rect = rect.union(tile_rect))
+                                    .or_insert((tile_rect, layer_index as f32));
+                            }
+
                             // Damage-inducing tile
-                            if let Some(damage) = get_float_prop(&tile, "damage") {
+                            if let Some(damage) = get_float_prop(&tile.properties, "damage") {
                                 if let Some(obj_data) = &tile.collision {
                                     for data in obj_data.object_data() {
                                         if data.user_type == "collider" {
                                             if let tiled::ObjectShape::Rect { width, height } =
                                                 data.shape
                                             {
-                                                let tile_pos: Vec2 = tile_pos.into();
-                                                let grid_size: Vec2 = grid_size.into();
-                                                let tile_pos2: Vec2 = tile_pos * grid_size
-                                                    + Vec2::new(
-                                                        layer_transform.translation.x,
-                                                        layer_transform.translation.y,
-                                                    );
+                                                let tile_center = geometry.tile_to_world(&tile_pos)
+                                                    + layer_transform.translation.xy();
+                                                // Collision objects are relative to the top-left
+                                                // corner of the tile image.
+                                                let offset = Vec2::new(
+                                                    data.x + width / 2. - tile_size.x / 2.,
+                                                    tile_size.y / 2. - data.y - height / 2.,
+                                                );
 
                                                 commands.spawn((
+                                                    LevelEntity,
                                                     TileCollision,
-                                                    Transform::from_xyz(
-                                                        tile_pos2.x + data.x,
-                                                        tile_pos2.y + grid_size.y / 2.
-                                                            - data.y
-                                                            - height / 2.,
-                                                        0.,
+                                                    Transform::from_translation(
+                                                        (tile_center + offset).extend(0.),
                                                     ),
                                                     GlobalTransform::default(),
                                                     RigidBody::Fixed,
@@ -533,29 +1313,28 @@ pub fn process_loaded_maps(
                                 }
                             }
 
-                            // Static world collider tile
-                            if is_wall {
-                                let tile_pos: Vec2 = tile_pos.into();
-                                let grid_size: Vec2 = grid_size.into();
-                                let tile_pos2: Vec2 = tile_pos * grid_size
-                                    + Vec2::new(
-                                        layer_transform.translation.x,
-                                        layer_transform.translation.y,
-                                    );
-                                // trace!(
-                                //     "tile_pos={:?} grid_size={:?} tile_pos2={:?}",
-                                //     tile_pos,
-                                //     grid_size,
-                                //     tile_pos2
-                                // );
-                                commands.spawn((
+                            // Static world collider tile, possibly bouncing the player.
+                            // Bounce tiles outside of the walls layer get their own collider.
+                            let bounce = get_float_prop(&tile.properties, "bounce");
+                            if is_wall || bounce.is_some() {
+                                let tile_center = geometry.tile_to_world(&tile_pos)
+                                    + layer_transform.translation.xy();
+                                let mut ent_cmds = commands.spawn((
+                                    LevelEntity,
                                     TileCollision,
-                                    Transform::from_xyz(tile_pos2.x, tile_pos2.y, 0.),
+                                    Transform::from_translation(tile_center.extend(0.)),
                                     GlobalTransform::default(),
                                     RigidBody::Fixed,
-                                    Collider::cuboid(8., 8.),
+                                    geometry.tile_collider(),
                                     Name::new(format!("tile{}x{}", x, y)),
                                 ));
+                                if let Some(bounce) = bounce {
+                                    ent_cmds.insert(Bounce(bounce));
+                                }
+                                if get_bool_prop(&tile.properties, "ice").unwrap_or(false) {
+                                    ent_cmds.insert((Ice, ice_friction()));
+                                }
+                                ent_cmds.insert(get_ground_material(&tile.properties));
                             }
                         }
                     }
@@ -569,9 +1348,15 @@ pub fn process_loaded_maps(
                         spacing: tile_spacing,
                         transform: layer_transform,
                         map_type,
-                        render_settings: *render_settings,
+                        render_settings: layer_render_settings,
                         ..Default::default()
                     });
+                    if is_secret {
+                        commands.entity(layer_entity).insert(SecretLayer {
+                            id: layer.id(),
+                            alpha: 1.,
+                        });
+                    }
 
                     layer_storage
                         .storage
@@ -579,8 +1364,33 @@ pub fn process_loaded_maps(
                 }
             }
 
+            // Spawn the areas revealing the secret layers
+            collectibles.level_secrets = secret_bounds.len() as u32;
+            for (id, (rect, z)) in secret_bounds {
+                let half_size = rect.half_size();
+                commands.spawn((
+                    LevelEntity,
+                    TransformBundle::from(Transform::from_translation(rect.center().extend(z))),
+                    Collider::cuboid(half_size.x, half_size.y),
+                    Sensor,
+                    SecretArea { layer: id },
+                    Name::new(format!("secret{}", id)),
+                ));
+            }
+
             // Process object layers (once only)
             let mut tp_map = HashMap::new();
+            let mut atlas_layouts = HashMap::new();
+            // Entity and initial enabled state of objects which switches can toggle
+            let mut obj_entities = HashMap::new();
+            let mut switches = vec![];
+            // World position of all objects, to resolve crusher paths
+            let mut obj_positions = HashMap::new();
+            let mut crushers = vec![];
+            // World points of polyline and polygon objects, and whether they're closed
+            let mut obj_paths = HashMap::new();
+            let mut path_followers = vec![];
+            let mut level_total_coins = 0;
             for (layer_index, layer) in tiled_map.map.layers().enumerate() {
                 let tiled::LayerType::Objects(object_layer) = layer.layer_type() else {
                     continue;
@@ -589,69 +1399,700 @@ pub fn process_loaded_maps(
                 for obj in object_layer.objects() {
                     trace!("Object: {} #{}", obj.name, obj.user_type);
 
-                    let x = obj.x - grid_size.x / 2.;
-                    let y = map_size.y as f32 * grid_size.y - obj.y - grid_size.y / 2.;
-                    let position = Vec2::new(x, y).extend(layer_index as f32);
+                    // Coins already collected don't reappear when reloading the level
+                    if obj.user_type == "coin" {
+                        level_total_coins += 1;
+                        if collectibles.is_collected(obj.id()) {
+                            continue;
+                        }
+                    }
+
+                    let position = geometry
+                        .object_to_world(obj.x, obj.y)
+                        .extend(layer_index as f32);
+                    obj_positions.insert(obj.id(), position.xy());
+                    if let tiled::ObjectShape::Polyline { points }
+                    | tiled::ObjectShape::Polygon { points } = &obj.shape
+                    {
+                        let path: Vec<Vec2> = points
+                            .iter()
+                            .map(|(x, y)| geometry.object_to_world(obj.x + x, obj.y + y))
+                            .collect();
+                        let is_closed = matches!(obj.shape, tiled::ObjectShape::Polygon { .. });
+                        obj_paths.insert(obj.id(), (path, is_closed));
+                    }
 
                     if obj.user_type == "player_start" {
-                        commands.spawn((PlayerStart { position }, Name::new(obj.name.clone())));
+                        commands.spawn((
+                            LevelEntity,
+                            PlayerStart { position },
+                            Name::new(obj.name.clone()),
+                        ));
                     } else if obj.user_type == "teleport" {
                         let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
                             continue;
                         };
 
-                        let offset = Vec3::new(width / 2., -height / 2., 0.);
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        let center = center.extend(position.z);
                         let Some(dst_id) = get_teleporter_dst(&obj) else {
                             warn!("Teleporter #{} is missing a 'dst' property.", obj.id());
                             continue;
                         };
                         let entity = commands
                             .spawn((
-                                TransformBundle::from(Transform::from_translation(
-                                    position + offset,
-                                )),
-                                Collider::cuboid(width / 2., height / 2.),
+                                LevelEntity,
+                                TransformBundle::from(Transform::from_translation(center)),
+                                collider,
                                 Sensor,
                                 Name::new(obj.name.clone()),
                             ))
                             .id();
                         trace!(
-                            "Spawned teleporter #{} '{}' entity {:?} at {:?} ({:?} + {:?}) -> {}",
+                            "Spawned teleporter #{} '{}' entity {:?} at {:?} (origin {:?}) -> {}",
                             obj.id(),
                             obj.name,
                             entity,
-                            position + offset,
+                            center,
                             position,
-                            offset,
                             dst_id,
                         );
-                        tp_map.insert(obj.id(), (entity, dst_id));
+                        // Teleporters wider than tall are crossed vertically, unless
+                        // the map says otherwise.
+                        let axis = match get_string_prop(&obj.properties, "axis") {
+                            Some("horizontal") => Vec2::X,
+                            Some("vertical") => Vec2::Y,
+                            _ if *width > *height => Vec2::Y,
+                            _ => Vec2::X,
+                        };
+                        let teleporter = Teleporter {
+                            axis,
+                            epoch_delta: get_int_prop(&obj.properties, "epoch_delta")
+                                .unwrap_or_default(),
+                            exit_dir: get_dir_prop(&obj.properties, "exit_dir"),
+                            cooldown: get_duration_prop(&obj.properties, "cooldown")
+                                .unwrap_or(Teleporter::DEFAULT_COOLDOWN),
+                            ..default()
+                        };
+                        let has_epoch_delta = obj.properties.contains_key("epoch_delta");
+                        tp_map.insert(
+                            obj.id(),
+                            (entity, dst_id, center.xy(), teleporter, has_epoch_delta),
+                        );
+                    } else if obj.user_type == "camera_pan" {
+                        let Some((points, _)) = obj_paths.get(&obj.id()) else {
+                            warn!("Camera pan #{} is not a polyline.", obj.id());
+                            continue;
+                        };
+                        commands.spawn((
+                            LevelEntity,
+                            CameraPath {
+                                points: points.clone(),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "camera_zone" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            warn!("Camera zone #{} is not a rectangle.", obj.id());
+                            continue;
+                        };
+                        let (center, _) = geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        let rect = Rect::from_center_size(center, Vec2::new(*width, *height));
+                        let lock_axis = get_string_prop(&obj.properties, "lock_axis");
+                        let is_clamped = get_bool_prop(&obj.properties, "clamp").unwrap_or(true);
+                        commands.spawn((
+                            LevelEntity,
+                            CameraZone {
+                                rect,
+                                is_fixed: get_bool_prop(&obj.properties, "fixed").unwrap_or(false),
+                                lock_x: lock_axis == Some("x"),
+                                lock_y: lock_axis == Some("y"),
+                                zoom: get_float_prop(&obj.properties, "zoom").unwrap_or(1.),
+                                bounds: is_clamped.then_some(rect),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "light" {
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(position)),
+                            LightSource {
+                                radius: get_float_prop(&obj.properties, "radius")
+                                    .unwrap_or(DEFAULT_LIGHT_RADIUS),
+                                intensity: get_float_prop(&obj.properties, "intensity")
+                                    .unwrap_or(1.),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "sound" {
+                        let Some(sound) = get_emitter_sound_prop(&obj.properties) else {
+                            warn!("Sound object '{}' has no valid 'sound' property.", obj.name);
+                            continue;
+                        };
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(position)),
+                            get_sound_emitter(&obj.properties, sound),
+                            Name::new(obj.name.clone()),
+                        ));
                     } else if obj.user_type == "ladder" {
                         let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
                             continue;
                         };
 
-                        let offset = Vec3::new(width / 2., -height / 2., 0.);
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
                         commands.spawn((
-                            TransformBundle::from(Transform::from_translation(position + offset)),
-                            Collider::cuboid(width / 2., height / 2.),
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
                             Sensor,
                             Ladder,
                             Name::new(obj.name.clone()),
                         ));
-                    } else if obj.user_type == "level_end" {
+                    } else if obj.user_type == "wind" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        // Direction is an angle in degrees, counter-clockwise from the right
+                        let angle = get_float_prop(&obj.properties, "angle").unwrap_or(0.);
+                        let strength = get_float_prop(&obj.properties, "strength")
+                            .unwrap_or(DEFAULT_WIND_STRENGTH);
+                        let force = Vec2::from_angle(angle.to_radians()) * strength;
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Sensor,
+                            WindZone {
+                                force,
+                                size: Vec2::new(*width, *height),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "checkpoint" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Sensor,
+                            CheckpointFlag,
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "gravity_flip" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Sensor,
+                            GravityFlipZone,
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "hint" {
                         let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
                             continue;
                         };
+                        let Some(text) = get_string_prop(&obj.properties, "text") else {
+                            warn!("Hint #{} has no text", obj.id());
+                            continue;
+                        };
 
-                        let offset = Vec3::new(width / 2., -height / 2., 0.);
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
                         commands.spawn((
-                            TransformBundle::from(Transform::from_translation(position + offset)),
-                            Collider::cuboid(width / 2., height / 2.),
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Sensor,
+                            HintZone {
+                                text: text.to_string(),
+                                // A zero duration keeps the hint until dismissed
+                                duration: Some(
+                                    get_int_prop(&obj.properties, "duration")
+                                        .map(|ms| Duration::from_millis(ms.max(0) as u64))
+                                        .unwrap_or(DEFAULT_HINT_DURATION),
+                                )
+                                .filter(|duration| !duration.is_zero()),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "ambience" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+                        let sound = match get_string_prop(&obj.properties, "sound") {
+                            Some("drips") => AmbienceSound::Drips,
+                            Some("wind") => AmbienceSound::Wind,
+                            Some("machinery") => AmbienceSound::Machinery,
+                            sound => {
+                                warn!("Ambience #{} has unknown sound {:?}", obj.id(), sound);
+                                continue;
+                            }
+                        };
+
+                        let (center, _) = geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            AmbienceRegion {
+                                sound,
+                                half_size: Vec2::new(*width, *height) / 2.,
+                                fade_distance: get_float_prop(&obj.properties, "fade")
+                                    .unwrap_or(DEFAULT_AMBIENCE_FADE),
+                                volume: get_float_prop(&obj.properties, "volume").unwrap_or(1.),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "water" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Sensor,
+                            WaterZone {
+                                size: Vec2::new(*width, *height),
+                                drowning: get_bool_prop(&obj.properties, "drowning")
+                                    .unwrap_or(false),
+                            },
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "level_end" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        let mut ent_cmds = commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
                             Sensor,
                             LevelEnd,
                             Name::new(obj.name.clone()),
                         ));
+                        if get_bool_prop(&obj.properties, "boss_lock").unwrap_or(false) {
+                            ent_cmds.insert((BossLocked, ColliderDisabled));
+                        }
+                    } else if obj.user_type == "boss_arena" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Sensor,
+                            BossArena,
+                            Name::new(obj.name.clone()),
+                        ));
+
+                        // Walls closing the arena during the fight. These are axis-aligned
+                        // around the arena bounds, whatever the map orientation.
+                        let (hw, hh) = (width / 2., height / 2.);
+                        let walls = [
+                            (Vec2::new(-hw, 0.), Vec2::new(2., hh)),
+                            (Vec2::new(hw, 0.), Vec2::new(2., hh)),
+                            (Vec2::new(0., hh), Vec2::new(hw, 2.)),
+                            (Vec2::new(0., -hh), Vec2::new(hw, 2.)),
+                        ];
+                        for (offset, half_size) in walls {
+                            commands.spawn((
+                                LevelEntity,
+                                TransformBundle::from(Transform::from_translation(
+                                    (center + offset).extend(position.z),
+                                )),
+                                RigidBody::Fixed,
+                                Collider::cuboid(half_size.x, half_size.y),
+                                ColliderDisabled,
+                                ArenaWall,
+                                Name::new(format!("{}_wall", obj.name)),
+                            ));
+                        }
+                    } else if let Some(obj_tile) = obj.get_tile() {
+                        let tiled::ObjectShape::Rect { width, height } = obj.shape else {
+                            continue;
+                        };
+
+                        let tileset_index = obj_tile.tileset_index();
+                        let tileset = obj_tile.get_tileset();
+                        let Some(TilemapTexture::Single(image)) =
+                            tiled_map.tilemap_textures.get(&tileset_index)
+                        else {
+                            warn!(
+                                "Skipped tile object #{} with unsupported tileset '{}'.",
+                                obj.id(),
+                                tileset.name
+                            );
+                            continue;
+                        };
+
+                        let tile_size =
+                            Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32);
+                        let layout = atlas_layouts
+                            .entry(tileset_index)
+                            .or_insert_with(|| {
+                                let rows = tileset.tilecount.div_ceil(tileset.columns.max(1));
+                                texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+                                    UVec2::new(tileset.tile_width, tileset.tile_height),
+                                    tileset.columns,
+                                    rows,
+                                    Some(UVec2::splat(tileset.spacing)),
+                                    Some(UVec2::splat(tileset.margin)),
+                                ))
+                            })
+                            .clone();
+
+                        // Resized tile objects stretch the tile image to the object size
+                        let size = Vec2::new(width, height);
+                        let scale = size / tile_size;
+                        let center = geometry.tile_object_center(obj.x, obj.y, width, height);
+
+                        let entity = commands
+                            .spawn((
+                                LevelEntity,
+                                SpriteBundle {
+                                    transform: Transform::from_translation(
+                                        center.extend(position.z),
+                                    ),
+                                    texture: image.clone(),
+                                    sprite: Sprite {
+                                        custom_size: Some(size),
+                                        flip_x: obj_tile.flip_h,
+                                        flip_y: obj_tile.flip_v,
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                TextureAtlas {
+                                    layout,
+                                    index: obj_tile.id() as usize,
+                                },
+                                Name::new(obj.name.clone()),
+                            ))
+                            .id();
+                        obj_entities.insert(obj.id(), (entity, get_object_enabled(&obj)));
+
+                        if obj.user_type == "spring" {
+                            commands.entity(entity).insert((
+                                RigidBody::Fixed,
+                                Collider::cuboid(size.x / 2., size.y / 2.),
+                                Bounce(get_bounce(&obj)),
+                            ));
+                            continue;
+                        } else if obj.user_type == "crusher" {
+                            commands.entity(entity).insert((
+                                RigidBody::KinematicPositionBased,
+                                Collider::cuboid(size.x / 2., size.y / 2.),
+                            ));
+                            crushers.push((entity, obj.id(), center, get_crusher(&obj)));
+                            continue;
+                        } else if obj.user_type == "npc" {
+                            let pages = get_dialogue_pages(&obj);
+                            if !pages.is_empty() {
+                                commands.entity(entity).insert(Interactable {
+                                    verb: "interact-talk",
+                                    radius: get_float_prop(&obj.properties, "radius")
+                                        .unwrap_or(DEFAULT_NPC_RADIUS),
+                                });
+                            }
+                            commands.entity(entity).insert(Npc {
+                                name: obj.name.clone(),
+                                pages,
+                            });
+                            continue;
+                        } else if obj.user_type == "saw" || obj.user_type == "fireball" {
+                            let (spin, sound) = if obj.user_type == "saw" {
+                                (DEFAULT_SAW_SPIN, EmitterSound::Saw)
+                            } else {
+                                (0., EmitterSound::Fire)
+                            };
+                            commands.entity(entity).insert((
+                                RigidBody::KinematicPositionBased,
+                                Collider::ball(size.min_element() / 2.),
+                                Sensor,
+                                Damage(
+                                    get_float_prop(&obj.properties, "damage")
+                                        .unwrap_or(DEFAULT_HAZARD_DAMAGE),
+                                ),
+                                Spin(get_float_prop(&obj.properties, "spin").unwrap_or(spin)),
+                                get_sound_emitter(&obj.properties, sound),
+                            ));
+                            if let Some(path_id) = get_object_prop(&obj.properties, "path") {
+                                path_followers.push((
+                                    entity,
+                                    obj.id(),
+                                    path_id,
+                                    get_path_settings(&obj),
+                                ));
+                            }
+                            continue;
+                        } else if let Some(falls_after) = get_falls_after(&obj, &obj_tile) {
+                            // The whole object is a single body, so it can fall at once
+                            commands.entity(entity).insert((
+                                RigidBody::Fixed,
+                                Collider::cuboid(size.x / 2., size.y / 2.),
+                                LockedAxes::ROTATION_LOCKED,
+                                Velocity::zero(),
+                                // Pushed by wind zones once falling
+                                ExternalForce::default(),
+                                FallingPlatform {
+                                    start: center.extend(position.z),
+                                    falls_after,
+                                    respawn_after: get_int_prop(&obj.properties, "respawn_after")
+                                        .map(|ms| Duration::from_millis(ms.max(0) as u64))
+                                        .unwrap_or(DEFAULT_RESPAWN_DELAY),
+                                    state: default(),
+                                    state_start_time: Duration::ZERO,
+                                },
+                            ));
+                            continue;
+                        } else if obj.user_type == "switch" {
+                            commands
+                                .entity(entity)
+                                .insert((Collider::cuboid(size.x / 2., size.y / 2.), Sensor));
+                            switches.push((
+                                entity,
+                                obj.id(),
+                                get_switch_targets(&obj),
+                                get_bool_prop(&obj.properties, "on_touch").unwrap_or(false),
+                                size,
+                            ));
+                            continue;
+                        }
+
+                        // Enemies get their body from the object size instead of the
+                        // tile colliders.
+                        let is_enemy = matches!(
+                            obj.user_type.as_str(),
+                            "enemy" | "flying_enemy" | "turret" | "boss"
+                        );
+                        if is_enemy {
+                            let enemy = Enemy {
+                                size,
+                                damage: get_float_prop(&obj.properties, "damage")
+                                    .unwrap_or(Enemy::default().damage),
+                            };
+                            let life = get_float_prop(&obj.properties, "life")
+                                .unwrap_or(EnemyLife::default().max_life);
+                            let enemy_life = EnemyLife {
+                                drop: get_float_prop(&obj.properties, "drop_life")
+                                    .map(Pickup::Life),
+                                ..EnemyLife::new(life)
+                            };
+                            let mut ent_cmds = commands.entity(entity);
+                            ent_cmds.insert((enemy, enemy_life));
+                            match obj.user_type.as_str() {
+                                "enemy" => {
+                                    ent_cmds.insert(Patrol {
+                                        speed: get_float_prop(&obj.properties, "speed")
+                                            .unwrap_or(Patrol::default().speed),
+                                        dir: if obj_tile.flip_h { -1. } else { 1. },
+                                    });
+                                }
+                                "turret" => {
+                                    let default = Turret::default();
+                                    ent_cmds.insert(Turret {
+                                        fire_interval: get_duration_prop(
+                                            &obj.properties,
+                                            "fire_interval",
+                                        )
+                                        .unwrap_or(default.fire_interval),
+                                        range: get_float_prop(&obj.properties, "range")
+                                            .unwrap_or(default.range),
+                                        projectile_speed: get_float_prop(
+                                            &obj.properties,
+                                            "projectile_speed",
+                                        )
+                                        .unwrap_or(default.projectile_speed),
+                                        ..default
+                                    });
+                                }
+                                "flying_enemy" => {
+                                    let default = Chaser::default();
+                                    ent_cmds.insert(Chaser {
+                                        speed: get_float_prop(&obj.properties, "speed")
+                                            .unwrap_or(default.speed),
+                                        acceleration: get_float_prop(
+                                            &obj.properties,
+                                            "acceleration",
+                                        )
+                                        .unwrap_or(default.acceleration),
+                                        aggro_range: get_float_prop(&obj.properties, "aggro_range")
+                                            .unwrap_or(default.aggro_range),
+                                    });
+                                }
+                                _ => {
+                                    ent_cmds.insert(Boss::new(
+                                        get_boss_phases(&obj),
+                                        center.extend(position.z),
+                                    ));
+                                }
+                            }
+                            continue;
+                        }
+
+                        if let Some(timed_platform) = get_timed_platform(&obj) {
+                            commands
+                                .entity(entity)
+                                .insert((timed_platform, Toggleable { enabled: true }));
+                        }
+
+                        if obj.user_type == "breakable" {
+                            commands.entity(entity).insert(Breakable);
+                            if let Some(explosive) = get_explosive(&obj) {
+                                commands.entity(entity).insert(explosive);
+                            }
+                        } else if let Some(pickup) = get_pickup(&obj) {
+                            commands.entity(entity).insert((
+                                pickup,
+                                Collider::cuboid(size.x / 2., size.y / 2.),
+                                Sensor,
+                            ));
+                            continue;
+                        }
+
+                        // Colliders of the tile, scaled to match the sprite
+                        let Some(tile) = obj_tile.get_tile() else {
+                            continue;
+                        };
+                        let Some(obj_data) = &tile.collision else {
+                            continue;
+                        };
+                        let damage = get_float_prop(&tile.properties, "damage");
+                        let is_ice = get_bool_prop(&tile.properties, "ice").unwrap_or(false);
+                        let material = get_ground_material(&tile.properties);
+                        for data in obj_data.object_data() {
+                            if data.user_type != "collider" {
+                                continue;
+                            }
+                            let tiled::ObjectShape::Rect { width, height } = data.shape else {
+                                continue;
+                            };
+
+                            // Collision objects are relative to the top-left corner of the
+                            // tile image.
+                            let mut offset = Vec2::new(
+                                (data.x + width / 2.) * scale.x - size.x / 2.,
+                                size.y / 2. - (data.y + height / 2.) * scale.y,
+                            );
+                            if obj_tile.flip_h {
+                                offset.x = -offset.x;
+                            }
+                            if obj_tile.flip_v {
+                                offset.y = -offset.y;
+                            }
+
+                            let mut ent_cmds = commands.spawn((
+                                LevelEntity,
+                                TileCollision,
+                                TransformBundle::from(Transform::from_translation(
+                                    offset.extend(0.),
+                                )),
+                                RigidBody::Fixed,
+                                Collider::cuboid(width * scale.x / 2., height * scale.y / 2.),
+                                Name::new(format!("{}_collider", obj.name)),
+                            ));
+                            ent_cmds.set_parent(entity);
+                            if let Some(damage) = damage {
+                                ent_cmds.insert((Sensor, Damage(damage)));
+                            } else if is_ice {
+                                ent_cmds.insert((Ice, ice_friction(), material));
+                            } else {
+                                ent_cmds.insert(material);
+                            }
+                        }
+                    } else if obj.user_type == "spring" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            RigidBody::Fixed,
+                            collider,
+                            Bounce(get_bounce(&obj)),
+                            Name::new(obj.name.clone()),
+                        ));
+                    } else if obj.user_type == "switch" || obj.user_type == "gate" {
+                        let tiled::ObjectShape::Rect { width, height } = &obj.shape else {
+                            continue;
+                        };
+
+                        let (center, collider) =
+                            geometry.rect_collider(obj.x, obj.y, *width, *height);
+                        let mut ent_cmds = commands.spawn((
+                            LevelEntity,
+                            TransformBundle::from(Transform::from_translation(
+                                center.extend(position.z),
+                            )),
+                            collider,
+                            Name::new(obj.name.clone()),
+                        ));
+                        if obj.user_type == "switch" {
+                            ent_cmds.insert(Sensor);
+                            switches.push((
+                                ent_cmds.id(),
+                                obj.id(),
+                                get_switch_targets(&obj),
+                                get_bool_prop(&obj.properties, "on_touch").unwrap_or(false),
+                                Vec2::new(*width, *height),
+                            ));
+                        } else {
+                            ent_cmds.insert(RigidBody::Fixed);
+                        }
+                        obj_entities.insert(obj.id(), (ent_cmds.id(), get_object_enabled(&obj)));
+                    } else if let Some(pickup) = get_pickup(&obj) {
+                        spawn_pickup(&mut commands, position, pickup);
                     } else {
                         debug!(
                             "Ignoring unknown object '{}' of class '{}'",
@@ -663,20 +2104,101 @@ pub fn process_loaded_maps(
 
             // Resolve teleporters once all entities are created, and insert the Teleporter
             // component with a link to the destination entity.
-            for (id, (entity, dst_id)) in &tp_map {
-                if let Some((dst_entity, src_id)) = tp_map.get(dst_id) {
+            for (id, (entity, dst_id, center, teleporter, has_epoch_delta)) in tp_map.iter() {
+                if let Some((dst_entity, src_id, dst_center, _, _)) = tp_map.get(dst_id) {
                     assert_eq!(*src_id, *id);
                     info!(
                         "Adding teleporter to entity {:?} -> {:?}",
                         entity, dst_entity
                     );
-                    commands
-                        .entity(*entity)
-                        .insert(Teleporter::new(*dst_entity));
+                    // Without an explicit epoch delta, going backward along the
+                    // teleporter axis moves forward in time, and vice versa.
+                    let epoch_delta = if *has_epoch_delta {
+                        teleporter.epoch_delta
+                    } else if (*dst_center - *center).dot(teleporter.axis) > 0. {
+                        -1
+                    } else {
+                        1
+                    };
+                    commands.entity(*entity).insert(Teleporter {
+                        target: *dst_entity,
+                        axis: teleporter.axis,
+                        epoch_delta,
+                        exit_dir: teleporter.exit_dir,
+                        cooldown: teleporter.cooldown,
+                        ready_time: Duration::ZERO,
+                    });
                 } else {
                     warn!("Teleporter #{} has unknown destination #{}", id, *dst_id);
                 }
             }
+
+            // Resolve the path of crushers, which slam along the points of their
+            // `path` polyline, toward the position of their `path` object if not
+            // a polyline, or straight down if they don't have any.
+            for (entity, id, start, (crusher, path_id)) in crushers {
+                let mut points = vec![start];
+                match path_id {
+                    Some(path_id) => {
+                        if let Some((path, _)) = obj_paths.get(&path_id) {
+                            points.extend(path.iter().copied());
+                        } else if let Some(end) = obj_positions.get(&path_id) {
+                            points.push(*end);
+                        } else {
+                            warn!("Crusher #{} has unknown path #{}", id, path_id);
+                        }
+                    }
+                    None => points.push(start - Vec2::Y * DEFAULT_CRUSHER_DISTANCE),
+                }
+                commands
+                    .entity(entity)
+                    .insert(Crusher { points, ..crusher });
+            }
+
+            collectibles.level_total_coins = level_total_coins;
+
+            // Resolve the path of path-following objects
+            for (entity, id, path_id, (speed, mode)) in path_followers {
+                let Some((points, is_closed)) = obj_paths.get(&path_id) else {
+                    warn!("Object #{} has unknown or invalid path #{}", id, path_id);
+                    continue;
+                };
+                // Closed paths loop by default, while open ones go back and forth
+                let mode = mode.unwrap_or(if *is_closed {
+                    PathMode::Loop
+                } else {
+                    PathMode::PingPong
+                });
+                commands
+                    .entity(entity)
+                    .insert(PathFollower::new(points.clone(), speed, mode));
+            }
+
+            // Resolve switch targets, and make them toggleable
+            for (entity, id, target_ids, on_touch, size) in switches {
+                let mut targets = vec![];
+                for target_id in target_ids {
+                    let Some((target, enabled)) = obj_entities.get(&target_id) else {
+                        warn!("Switch #{} has unknown target #{}", id, target_id);
+                        continue;
+                    };
+                    commands
+                        .entity(*target)
+                        .insert(Toggleable { enabled: *enabled });
+                    targets.push(*target);
+                }
+                commands.entity(entity).insert(Switch {
+                    targets,
+                    on_touch,
+                    is_on: false,
+                });
+                if !on_touch {
+                    commands.entity(entity).insert(Interactable {
+                        verb: "interact-use",
+                        radius: size.max_element() / 2. + SWITCH_REACH,
+                    });
+                }
+            }
         }
     }
 
@@ -685,4 +2207,8 @@ pub fn process_loaded_maps(
         epoch.min = min_epoch;
         epoch.max = max_epoch;
     }
+    if let Some(start_epoch) = start_epoch {
+        epoch.cur = start_epoch.clamp(epoch.min, epoch.max);
+        info!("Level starts at epoch {}", epoch.cur);
+    }
 }
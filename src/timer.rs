@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::AppState;
+
+/// Time spent in the current level, only counting while in game.
+#[derive(Debug, Default, Resource)]
+pub struct LevelTimer {
+    pub elapsed: Duration,
+    pub is_running: bool,
+}
+
+impl LevelTimer {
+    /// Stop the timer on level completion.
+    pub fn finish(&mut self) {
+        self.is_running = false;
+    }
+
+    /// Restart the timer from zero.
+    pub fn restart(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.is_running = true;
+    }
+}
+
+/// Format a duration as a speedrun time, like `01:23.45`.
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!(
+        "{:02}:{:02}.{:02}",
+        secs / 60,
+        secs % 60,
+        time.subsec_millis() / 10
+    )
+}
+
+#[derive(Default)]
+pub struct TimerPlugin;
+
+impl Plugin for TimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelTimer>()
+            // Only ticking in game pauses the timer in any other state, including
+            // while paused. The timer is restarted when a level is loaded.
+            .add_systems(Update, tick_timer.run_if(in_state(AppState::InGame)));
+    }
+}
+
+fn tick_timer(time: Res<Time>, mut timer: ResMut<LevelTimer>) {
+    if timer.is_running {
+        timer.elapsed += time.delta();
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_keith::Canvas;
+
+use crate::{layout::UiLayout, locale::Localizer, AppState, LevelMeta, UiRes};
+
+/// Total duration the title card stays visible, including its fades.
+const TITLE_DURATION: Duration = Duration::from_millis(3500);
+
+/// Duration of each of the fade in and fade out of the title card.
+const FADE_DURATION: Duration = Duration::from_millis(600);
+
+/// Title card showing the name and subtitle of the level when it starts.
+#[derive(Debug, Default, Resource)]
+struct TitleCard {
+    name: String,
+    subtitle: Option<String>,
+    /// Time the card appeared, or `None` when hidden.
+    start_time: Option<Duration>,
+}
+
+impl TitleCard {
+    /// Opacity of the card at the given time, or `None` once it disappeared.
+    fn alpha(&self, time: Duration) -> Option<f32> {
+        let delta = time.saturating_sub(self.start_time?);
+        if delta >= TITLE_DURATION {
+            return None;
+        }
+        let fade_in = delta.div_duration_f32(FADE_DURATION);
+        let fade_out = (TITLE_DURATION - delta).div_duration_f32(FADE_DURATION);
+        Some(fade_in.min(fade_out).min(1.))
+    }
+}
+
+#[derive(Default)]
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TitleCard>().add_systems(
+            Update,
+            (show_title_card, ui_title_card.after(crate::hud::main_ui))
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Show the title card once a new level is loaded and the game starts.
+fn show_title_card(
+    time: Res<Time>,
+    level_meta: Option<Res<LevelMeta>>,
+    mut title_card: ResMut<TitleCard>,
+    loc: Localizer,
+) {
+    let Some(level_meta) = level_meta else {
+        return;
+    };
+    if !level_meta.is_changed() {
+        return;
+    }
+    debug!("Showing title card of '{}'", level_meta.name);
+    *title_card = TitleCard {
+        name: loc.text(&level_meta.name),
+        subtitle: level_meta.subtitle.as_deref().map(|s| loc.text(s)),
+        start_time: Some(time.elapsed()),
+    };
+}
+
+fn ui_title_card(
+    time: Res<Time>,
+    mut title_card: ResMut<TitleCard>,
+    mut q_canvas: Query<&mut Canvas>,
+    ui_res: Res<UiRes>,
+    layout: Res<UiLayout>,
+) {
+    let Some(alpha) = title_card.alpha(time.elapsed()) else {
+        title_card.start_time = None;
+        return;
+    };
+
+    let mut canvas = q_canvas.single_mut();
+    let mut ctx = canvas.render_context();
+
+    let center = Vec2::new(0., -120.);
+    let height = if title_card.subtitle.is_some() {
+        100.
+    } else {
+        70.
+    };
+    let brush = ctx.solid_brush(Color::srgba(0., 0., 0., 0.6 * alpha));
+    let size = Vec2::new(layout.screen_rect().width(), height);
+    ctx.fill(
+        Rect::from_center_size(center + Vec2::Y * (height - 70.) / 2., size),
+        &brush,
+    );
+
+    // Name with a drop shadow, like a pixel art logo
+    for (offset, color) in [
+        (Vec2::splat(3.), Color::srgba(0., 0., 0., alpha)),
+        (Vec2::ZERO, Color::srgba(1., 0.85, 0., alpha)),
+    ] {
+        let txt = ctx
+            .new_layout(title_card.name.clone())
+            .font(ui_res.font.clone())
+            .font_size(32.)
+            .color(color)
+            .alignment(JustifyText::Center)
+            .bounds(Vec2::new(900., 40.))
+            .build();
+        ctx.draw_text(txt, center + offset);
+    }
+
+    if let Some(subtitle) = &title_card.subtitle {
+        let txt = ctx
+            .new_layout(subtitle.clone())
+            .font(ui_res.font.clone())
+            .font_size(16.)
+            .color(Color::srgba(1., 1., 1., alpha))
+            .alignment(JustifyText::Center)
+            .bounds(Vec2::new(900., 20.))
+            .build();
+        ctx.draw_text(txt, center + Vec2::new(0., 40.));
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::{prelude::*, rapier::geometry::CollisionEventFlags};
+
+use crate::{
+    BossArena, Bounce, CheckpointFlag, Damage, HintZone, Ladder, LevelEnd, Pickup, Player, Switch,
+    Teleporter,
+};
+
+/// Kind of a trigger, from the component of the sensor the player overlaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriggerKind {
+    Teleporter,
+    Ladder,
+    Damage,
+    LevelEnd,
+    Checkpoint,
+    Hint,
+    BossArena,
+    Pickup,
+    Switch,
+    /// Bounce pads are solid, so trigger on contact instead of overlap.
+    Bounce,
+}
+
+/// The player started overlapping a trigger.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct TriggerEnter {
+    pub trigger: Entity,
+    pub kind: TriggerKind,
+}
+
+/// The player stopped overlapping a trigger, or the trigger was despawned
+/// while overlapping it.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct TriggerExit {
+    pub trigger: Entity,
+    pub kind: TriggerKind,
+}
+
+/// Triggers the player currently overlaps.
+#[derive(Debug, Default, Resource)]
+pub struct ActiveTriggers(HashMap<Entity, TriggerKind>);
+
+impl ActiveTriggers {
+    /// Check if the player overlaps any trigger of the given kind.
+    pub fn contains(&self, kind: TriggerKind) -> bool {
+        self.0.values().any(|k| *k == kind)
+    }
+}
+
+#[derive(Default)]
+pub struct TriggerPlugin;
+
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveTriggers>()
+            .add_event::<TriggerEnter>()
+            .add_event::<TriggerExit>()
+            .add_systems(PreUpdate, update_triggers);
+    }
+}
+
+/// Convert the sensor collision events of the player, and its contacts with
+/// bounce pads, into trigger events, so systems reacting to a kind of trigger
+/// don't need to sort out the entities of each collision themselves.
+pub fn update_triggers(
+    q_player: Query<(Entity, Ref<Player>)>,
+    q_triggers: Query<(
+        Has<Teleporter>,
+        Has<Ladder>,
+        Has<Damage>,
+        Has<LevelEnd>,
+        Has<CheckpointFlag>,
+        Has<HintZone>,
+        Has<BossArena>,
+        Has<Pickup>,
+        Has<Switch>,
+        Has<Bounce>,
+    )>,
+    mut events: EventReader<CollisionEvent>,
+    mut active: ResMut<ActiveTriggers>,
+    mut ev_enter: EventWriter<TriggerEnter>,
+    mut ev_exit: EventWriter<TriggerExit>,
+) {
+    let Ok((player_entity, player)) = q_player.get_single() else {
+        active.0.clear();
+        events.clear();
+        return;
+    };
+    // Forget the triggers of the previous player, like after loading a level
+    if player.is_added() {
+        active.0.clear();
+    }
+
+    for ev in events.read() {
+        let (e1, e2, flags, is_enter) = match ev {
+            CollisionEvent::Started(e1, e2, flags) => (*e1, *e2, *flags, true),
+            CollisionEvent::Stopped(e1, e2, flags) => (*e1, *e2, *flags, false),
+        };
+        let trigger = if e1 == player_entity {
+            e2
+        } else if e2 == player_entity {
+            e1
+        } else {
+            continue;
+        };
+
+        if is_enter {
+            let Ok((
+                teleporter,
+                ladder,
+                damage,
+                level_end,
+                checkpoint,
+                hint,
+                boss_arena,
+                pickup,
+                switch,
+                bounce,
+            )) = q_triggers.get(trigger)
+            else {
+                continue;
+            };
+            let Some(kind) = [
+                (teleporter, TriggerKind::Teleporter),
+                (ladder, TriggerKind::Ladder),
+                (damage, TriggerKind::Damage),
+                (level_end, TriggerKind::LevelEnd),
+                (checkpoint, TriggerKind::Checkpoint),
+                (hint, TriggerKind::Hint),
+                (boss_arena, TriggerKind::BossArena),
+                (pickup, TriggerKind::Pickup),
+                (switch, TriggerKind::Switch),
+                (bounce, TriggerKind::Bounce),
+            ]
+            .into_iter()
+            .find_map(|(has, kind)| has.then_some(kind)) else {
+                continue;
+            };
+            // Only bounce pads trigger on solid contacts, so solid colliders
+            // with another trigger component, like damaging walls, don't
+            let is_sensor = flags.contains(CollisionEventFlags::SENSOR);
+            if is_sensor != (kind != TriggerKind::Bounce) {
+                continue;
+            }
+            trace!("Player entered {:?} trigger {:?}", kind, trigger);
+            active.0.insert(trigger, kind);
+            ev_enter.send(TriggerEnter { trigger, kind });
+        } else if let Some(kind) = active.0.remove(&trigger) {
+            // The trigger may be despawned already, so use the kind saved on
+            // enter instead of its components
+            trace!("Player exited {:?} trigger {:?}", kind, trigger);
+            ev_exit.send(TriggerExit { trigger, kind });
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{loading::LoadingAssets, Player, PlayerLife};
+
+/// Asset path of the gameplay tuning.
+const TUNING_PATH: &str = "game.tuning.ron";
+
+/// Values tuning the feel of the gameplay, loaded from a RON asset so they can
+/// be tweaked without recompiling. With the `debug` feature, the asset is
+/// reloaded whenever its file changes.
+///
+/// Values missing from the asset keep their default.
+#[derive(Debug, Clone, Asset, TypePath, Resource, Deserialize)]
+#[serde(default)]
+pub struct GameTuning {
+    /// Factor converting the movement input into an impulse on the player.
+    pub impulse_factor: f32,
+    /// Vertical input of a jump, scaled by the impulse factor.
+    pub jump_impulse: f32,
+    /// Vertical input of a swimming stroke, scaled by the impulse factor.
+    pub swim_impulse: f32,
+    /// Impulse pushing the player away from the source of damage.
+    pub knockback_impulse: f32,
+    /// Duration of the knockback after taking damage, in milliseconds.
+    pub damage_duration_ms: u64,
+    /// Vertical speed gained each frame while climbing a ladder.
+    pub climb_speed: f32,
+    /// Horizontal speed gained each frame while climbing a ladder.
+    pub climb_strafe_speed: f32,
+    /// Maximum speed while climbing a ladder.
+    pub max_climb_speed: f32,
+}
+
+impl Default for GameTuning {
+    fn default() -> Self {
+        Self {
+            impulse_factor: 500.,
+            jump_impulse: 30.,
+            swim_impulse: 15.,
+            knockback_impulse: 6000.,
+            damage_duration_ms: 400,
+            climb_speed: 2.,
+            climb_strafe_speed: 1.,
+            max_climb_speed: 50.,
+        }
+    }
+}
+
+impl GameTuning {
+    pub fn damage_duration(&self) -> Duration {
+        Duration::from_millis(self.damage_duration_ms)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum GameTuningLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load tuning file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file is not valid RON for the tuning
+    #[error("Invalid tuning file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+#[derive(Default)]
+struct GameTuningLoader;
+
+impl AssetLoader for GameTuningLoader {
+    type Asset = GameTuning;
+    type Settings = ();
+    type Error = GameTuningLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+/// Handle keeping the tuning asset loaded.
+#[derive(Default, Resource)]
+struct GameTuningHandle(Handle<GameTuning>);
+
+#[derive(Default)]
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<GameTuning>()
+            .register_asset_loader(GameTuningLoader)
+            .init_resource::<GameTuning>()
+            .init_resource::<GameTuningHandle>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(Update, (update_tuning, apply_player_tuning).chain());
+    }
+}
+
+fn load_tuning(
+    asset_server: Res<AssetServer>,
+    mut handle: ResMut<GameTuningHandle>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    handle.0 = asset_server.load(TUNING_PATH);
+    loading.track(handle.0.clone());
+}
+
+/// Copy the tuning asset into the [`GameTuning`] resource once loaded, and
+/// again each time it's reloaded.
+fn update_tuning(
+    mut events: EventReader<AssetEvent<GameTuning>>,
+    handle: Res<GameTuningHandle>,
+    assets: Res<Assets<GameTuning>>,
+    mut tuning: ResMut<GameTuning>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        if let Some(asset) = assets.get(&handle.0) {
+            info!("Applying gameplay tuning from '{}'", TUNING_PATH);
+            *tuning = asset.clone();
+        }
+    }
+}
+
+/// Apply the tuning to the values the player components keep, for new players
+/// and whenever the tuning changes.
+fn apply_player_tuning(
+    tuning: Res<GameTuning>,
+    mut q_player: Query<(&mut Player, &mut PlayerLife)>,
+) {
+    for (mut player, mut player_life) in &mut q_player {
+        if tuning.is_changed() || player.is_added() {
+            player.impulse_factor = tuning.impulse_factor;
+            player_life.damage_duration = tuning.damage_duration();
+        }
+    }
+}